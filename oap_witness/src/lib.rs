@@ -0,0 +1,564 @@
+//! Reference witness for VER v1.0 receipts: validation, RFC 8785
+//! canonicalization and SHA-256 receipt ids.
+//!
+//! VER objects may also arrive CBOR-encoded (RFC 8949). They are decoded
+//! into the same value tree and then audited, canonicalized to JSON and
+//! hashed exactly like the JSON form, so both encodings of a receipt share
+//! one id. The VER number rule applies unchanged: CBOR integers and floats
+//! are rejected like JSON numbers, and numeric values must be text strings.
+
+pub mod vectors;
+
+use serde::de::{Deserialize, Deserializer, IgnoredAny, MapAccess, Visitor};
+use serde_json::Value;
+use serde_json_canonicalizer::to_vec;
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+use std::fmt;
+use std::io::Read;
+use subtle::ConstantTimeEq;
+
+/// The only VER version the witness accepts.
+pub const VER_VERSION: &str = "1.0";
+
+/// Fixed-point fields of the VER v1.0 schema validated by `--strict-numeric`
+/// when no explicit path list is given.
+pub const STRICT_NUMERIC_PATHS: &[&str] = &[
+    "input.h4_high",
+    "input.h4_low",
+    "input.current_bid",
+    "input.rsi_val",
+    "output.sl",
+    "output.tp",
+];
+
+/// Top-level key order mandated by the VER v1.0 spec, checked by
+/// `--require-order v1`.
+pub const V1_KEY_ORDER: &[&str] = &["version", "context", "input", "output"];
+
+/// Top-level fields of a VER v1.0 object; any other is refused by
+/// `--no-extra`.
+pub const V1_TOP_LEVEL_FIELDS: &[&str] = &["version", "context", "input", "output"];
+
+/// Fields of a VER v1.0 `context` object; any other is refused by
+/// `--no-extra`.
+pub const V1_CONTEXT_FIELDS: &[&str] = &["engine", "logic_hash"];
+
+/// Default cap on input size accepted by the witness (16 MiB).
+///
+/// Canonicalization needs the whole document in memory, so oversized input
+/// is refused up front instead of being buffered.
+pub const DEFAULT_MAX_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Every way a VER object can be rejected by the witness.
+#[derive(Debug)]
+pub enum VerError {
+    Syntax(serde_json::Error),
+    /// Input is not well-formed CBOR, or holds a CBOR item with no JSON
+    /// counterpart (byte string, non-text map key).
+    Cbor(String),
+    Version(String),
+    MissingField(String),
+    NumberFound(String),
+    InvalidFixedPoint(String, String),
+    /// Field outside the known set of its object, under `--no-extra`.
+    UnexpectedField(String),
+    /// Top-level keys out of spec order; carries the order found.
+    KeyOrder(Vec<String>),
+    Canonicalization,
+    EmptyBatch,
+    /// Input could not be read; carries the source (`-` for stdin).
+    Io(String, std::io::Error),
+    /// Input exceeds the configured byte limit.
+    TooLarge(u64),
+}
+
+impl fmt::Display for VerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerError::Syntax(e) => write!(f, "SYNTAX ERROR: Invalid JSON format ({})", e),
+            VerError::Cbor(e) => write!(f, "SYNTAX ERROR: Invalid CBOR ({})", e),
+            VerError::Version(v) => write!(
+                f,
+                "SPEC ERROR: Unsupported VER version {:?} (Expected '1.0')",
+                v
+            ),
+            VerError::MissingField(path) => {
+                write!(f, "SCHEMA ERROR: Missing required field path: {}", path)
+            }
+            VerError::NumberFound(path) => write!(
+                f,
+                "SPEC VIOLATION: JSON Number detected at {}. All numbers must be fixed-point strings.",
+                path
+            ),
+            VerError::InvalidFixedPoint(path, value) => write!(
+                f,
+                "SPEC VIOLATION: Field {} is not a canonical fixed-point string: {}",
+                path, value
+            ),
+            VerError::UnexpectedField(path) => {
+                write!(f, "SCHEMA ERROR: Unexpected field path: {}", path)
+            }
+            VerError::KeyOrder(found) => write!(
+                f,
+                "SPEC VIOLATION: Top-level keys out of order: found [{}]",
+                found.join(", ")
+            ),
+            VerError::Canonicalization => write!(
+                f,
+                "INTERNAL ERROR: Canonicalization failed despite validation"
+            ),
+            VerError::Io(source, e) => write!(f, "IO ERROR: Could not read {}: {}", source, e),
+            VerError::TooLarge(max) => {
+                write!(f, "INPUT ERROR: Input exceeds the {} byte limit", max)
+            }
+            VerError::EmptyBatch => write!(f, "BATCH ERROR: No receipts to build a Merkle root"),
+        }
+    }
+}
+
+impl std::error::Error for VerError {}
+
+impl VerError {
+    /// Stable snake_case name of the error category, for machine-readable
+    /// output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            VerError::Syntax(_) => "syntax",
+            VerError::Cbor(_) => "cbor",
+            VerError::Version(_) => "version",
+            VerError::MissingField(_) => "missing_field",
+            VerError::NumberFound(_) => "number_found",
+            VerError::InvalidFixedPoint(..) => "invalid_fixed_point",
+            VerError::UnexpectedField(_) => "unexpected_field",
+            VerError::KeyOrder(_) => "key_order",
+            VerError::Canonicalization => "canonicalization",
+            VerError::EmptyBatch => "empty_batch",
+            VerError::Io(..) => "io",
+            VerError::TooLarge(_) => "too_large",
+        }
+    }
+
+    /// Location of the offending field as object keys and array indices,
+    /// e.g. `input.bars[2]` becomes `["input", "bars", 2]`. Empty for errors
+    /// that are not about a single field.
+    pub fn path(&self) -> Vec<Value> {
+        let path = match self {
+            VerError::MissingField(path)
+            | VerError::UnexpectedField(path)
+            | VerError::NumberFound(path)
+            | VerError::InvalidFixedPoint(path, _) => path,
+            _ => return Vec::new(),
+        };
+        let mut segments = Vec::new();
+        for part in path.split('.').filter(|p| !p.is_empty()) {
+            let mut pieces = part.split('[');
+            if let Some(key) = pieces.next().filter(|k| !k.is_empty()) {
+                segments.push(Value::from(key));
+            }
+            for index in pieces {
+                let index = index.trim_end_matches(']');
+                segments.push(match index.parse::<u64>() {
+                    Ok(i) => Value::from(i),
+                    Err(_) => Value::from(index),
+                });
+            }
+        }
+        segments
+    }
+}
+
+/// Process exit status of the `oap_witness` binary. The values are stable
+/// so that scripts can branch on the category of a failure.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitCode {
+    Ok = 0,
+    /// Bad command line, or an internal failure.
+    Usage = 1,
+    /// Input is not valid JSON (or, with `--cbor`, valid CBOR).
+    Syntax = 2,
+    /// Unsupported VER version.
+    Version = 3,
+    /// Missing or (with `--no-extra`) unexpected field, top-level key
+    /// order or an empty batch.
+    Schema = 4,
+    /// JSON number or non-canonical fixed-point string.
+    Number = 5,
+    /// Duplicate object key. Reserved: the parser keeps the last value.
+    Duplicate = 6,
+    /// Input could not be read or exceeds `--max-bytes`.
+    Io = 7,
+    /// Nesting too deep. Reserved: serde_json's recursion limit reports a
+    /// syntax error.
+    TooDeep = 8,
+    /// `--expect` mismatch or a failing self-test vector.
+    DigestMismatch = 9,
+}
+
+/// Finalization hash applied to the canonical bytes.
+///
+/// `Sha256` is the VER spec receipt id; `Keccak256` matches the digest
+/// convention of the on-chain StylusHardwareAnchor contract.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HashAlg {
+    #[default]
+    Sha256,
+    Keccak256,
+}
+
+impl std::str::FromStr for HashAlg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(HashAlg::Sha256),
+            "keccak256" => Ok(HashAlg::Keccak256),
+            other => Err(format!(
+                "unknown hash {:?} (expected sha256 or keccak256)",
+                other
+            )),
+        }
+    }
+}
+
+impl HashAlg {
+    /// Hash already-canonical bytes into a receipt id.
+    pub fn digest(self, canonical_bytes: &[u8]) -> [u8; 32] {
+        match self {
+            HashAlg::Sha256 => Sha256::digest(canonical_bytes).into(),
+            HashAlg::Keccak256 => Keccak256::digest(canonical_bytes).into(),
+        }
+    }
+}
+
+/// Command-line switches controlling the optional audits.
+#[derive(Default)]
+pub struct Options {
+    /// Field paths (dot-separated) that must hold canonical fixed-point strings.
+    pub strict_numeric: Option<Vec<String>>,
+    /// Exact top-level key order the raw input must already have.
+    pub require_order: Option<&'static [&'static str]>,
+    /// Refuse top-level and `context` fields outside the known set, so a
+    /// misspelt key fails instead of yielding a different id.
+    pub no_extra: bool,
+    /// Hash used to finalize the receipt id.
+    pub hash: HashAlg,
+}
+
+/// Read at most `max_bytes` of VER input from `reader`.
+///
+/// Reads one byte past the limit so that input of exactly `max_bytes` is
+/// accepted; anything longer fails with `TooLarge` without buffering the rest.
+/// `source` names the input in `Io` errors.
+pub fn read_bounded<R: Read>(reader: R, source: &str, max_bytes: u64) -> Result<String, VerError> {
+    let buffer = read_bounded_bytes(reader, source, max_bytes)?;
+    String::from_utf8(buffer).map_err(|e| {
+        VerError::Io(
+            source.to_string(),
+            std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        )
+    })
+}
+
+/// `read_bounded` for binary input such as CBOR.
+pub fn read_bounded_bytes<R: Read>(
+    reader: R,
+    source: &str,
+    max_bytes: u64,
+) -> Result<Vec<u8>, VerError> {
+    let mut buffer = Vec::new();
+    reader
+        .take(max_bytes.saturating_add(1))
+        .read_to_end(&mut buffer)
+        .map_err(|e| VerError::Io(source.to_string(), e))?;
+    if buffer.len() as u64 > max_bytes {
+        return Err(VerError::TooLarge(max_bytes));
+    }
+    Ok(buffer)
+}
+
+/// Top-level object keys in the order they appear in the raw input.
+///
+/// `Value` sorts its keys, so the order has to be read off the token stream.
+struct TopLevelKeys(Vec<String>);
+
+impl<'de> Deserialize<'de> for TopLevelKeys {
+    fn deserialize<D: Deserializer<'de>>(de: D) -> Result<Self, D::Error> {
+        de.deserialize_map(Keys).map(TopLevelKeys)
+    }
+}
+
+struct Keys;
+
+impl<'de> Visitor<'de> for Keys {
+    type Value = Vec<String>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut keys = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            map.next_value::<IgnoredAny>()?;
+            keys.push(key);
+        }
+        Ok(keys)
+    }
+}
+
+/// Join a parent path and a child segment into a dot-separated path.
+fn child_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+/// Recursive check to ensure NO JSON Numbers exist in the tree.
+/// All numeric values must be strings as per VER v1.0 spec.
+fn assert_no_numbers(v: &Value, path: &str) -> Result<(), VerError> {
+    match v {
+        Value::Number(_) => Err(VerError::NumberFound(path.to_string())),
+        Value::Array(arr) => {
+            for (i, item) in arr.iter().enumerate() {
+                assert_no_numbers(item, &format!("{}[{}]", path, i))?;
+            }
+            Ok(())
+        }
+        Value::Object(map) => {
+            for (key, val) in map {
+                assert_no_numbers(val, &child_path(path, key))?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Enforce the presence of a nested field and return it.
+fn require_field<'a>(v: &'a Value, path: &[&str]) -> Result<&'a Value, VerError> {
+    let mut cur = v;
+    for key in path {
+        cur = cur
+            .get(*key)
+            .ok_or_else(|| VerError::MissingField(path.join(".")))?;
+    }
+    Ok(cur)
+}
+
+/// Fail on the first key of `v` (an object) not listed in `known`.
+fn check_known_fields(v: &Value, parent: &str, known: &[&str]) -> Result<(), VerError> {
+    if let Value::Object(map) = v {
+        if let Some(key) = map.keys().find(|k| !known.contains(&k.as_str())) {
+            return Err(VerError::UnexpectedField(child_path(parent, key)));
+        }
+    }
+    Ok(())
+}
+
+/// Canonical decimal: optional leading minus, an integer part without leading
+/// zeros (a lone "0" is allowed), and an optional single dot followed by at
+/// least one digit. Exponents, plus signs and whitespace are rejected.
+fn is_canonical_fixed_point(s: &str) -> bool {
+    let unsigned = s.strip_prefix('-').unwrap_or(s);
+    let (int_part, frac_part) = match unsigned.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (unsigned, None),
+    };
+
+    let all_digits = |p: &str| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit());
+    if !all_digits(int_part) || (int_part.len() > 1 && int_part.starts_with('0')) {
+        return false;
+    }
+    frac_part.is_none_or(all_digits)
+}
+
+/// Validate the configured fixed-point fields. Absent fields are left to the
+/// schema check; present ones must be canonical decimal strings.
+fn check_fixed_point(v: &Value, paths: &[String]) -> Result<(), VerError> {
+    for path in paths {
+        let mut cur = Some(v);
+        for key in path.split('.') {
+            cur = cur.and_then(|c| c.get(key));
+        }
+        match cur {
+            None => {}
+            Some(Value::String(s)) if is_canonical_fixed_point(s) => {}
+            Some(other) => {
+                return Err(VerError::InvalidFixedPoint(path.clone(), other.to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Run the audits and return the RFC 8785 canonical bytes that the receipt
+/// id is computed over.
+pub fn canonical_bytes(raw_ver: &str, opts: &Options) -> Result<Vec<u8>, VerError> {
+    // 2. Initial Parse
+    let json_value: Value = serde_json::from_str(raw_ver).map_err(VerError::Syntax)?;
+
+    audit(json_value, opts, || {
+        serde_json::from_str(raw_ver)
+            .map(|TopLevelKeys(keys)| keys)
+            .map_err(VerError::Syntax)
+    })
+}
+
+/// `canonical_bytes` for a CBOR-encoded VER object. The output is the JSON
+/// canonical form, identical to that of the equivalent JSON document.
+pub fn canonical_bytes_cbor(raw_ver: &[u8], opts: &Options) -> Result<Vec<u8>, VerError> {
+    // 2. Initial Parse
+    let json_value: Value =
+        ciborium::from_reader(raw_ver).map_err(|e| VerError::Cbor(e.to_string()))?;
+
+    audit(json_value, opts, || {
+        ciborium::from_reader(raw_ver)
+            .map(|TopLevelKeys(keys)| keys)
+            .map_err(|e| VerError::Cbor(e.to_string()))
+    })
+}
+
+/// Audits and canonicalization shared by every input encoding.
+/// `raw_keys` reads the top-level key order off the raw input, which the
+/// parsed value no longer has.
+fn audit(
+    json_value: Value,
+    opts: &Options,
+    raw_keys: impl FnOnce() -> Result<Vec<String>, VerError>,
+) -> Result<Vec<u8>, VerError> {
+    // 3. HARD AUDIT: Version Lock
+    let version = json_value["version"].as_str().unwrap_or("");
+    if version != VER_VERSION {
+        return Err(VerError::Version(version.to_string()));
+    }
+
+    // 4. HARD AUDIT: Schema Compliance
+    require_field(&json_value, &["context", "engine"])?;
+    require_field(&json_value, &["context", "logic_hash"])?;
+    require_field(&json_value, &["input"])?;
+    require_field(&json_value, &["output"])?;
+
+    // 4a. OPTIONAL AUDIT: No fields outside the schema
+    if opts.no_extra {
+        check_known_fields(&json_value, "", V1_TOP_LEVEL_FIELDS)?;
+        check_known_fields(&json_value["context"], "context", V1_CONTEXT_FIELDS)?;
+    }
+
+    // 4b. OPTIONAL AUDIT: Raw top-level key order
+    if let Some(expected) = opts.require_order {
+        let found = raw_keys()?;
+        if found
+            .iter()
+            .map(String::as_str)
+            .ne(expected.iter().copied())
+        {
+            return Err(VerError::KeyOrder(found));
+        }
+    }
+
+    // 5. HARD AUDIT: Determinism Enforcement (No Floats)
+    assert_no_numbers(&json_value, "")?;
+
+    // 5b. OPTIONAL AUDIT: Fixed-point string format
+    if let Some(paths) = &opts.strict_numeric {
+        check_fixed_point(&json_value, paths)?;
+    }
+
+    // 6. RFC 8785 Canonicalization
+    to_vec(&json_value).map_err(|_| VerError::Canonicalization)
+}
+
+/// Run the full audit pipeline and return the raw SHA-256 receipt id.
+pub fn receipt_digest(raw_ver: &str, opts: &Options) -> Result<[u8; 32], VerError> {
+    // 7. Hashing (SHA-256 per VER spec unless keccak256 was requested)
+    Ok(opts.hash.digest(&canonical_bytes(raw_ver, opts)?))
+}
+
+/// Run the full audit pipeline and return the lowercase hex receipt id.
+pub fn receipt_id(raw_ver: &str, opts: &Options) -> Result<String, VerError> {
+    // 8. FINAL OUTPUT: Lowercase Hex (Immutable Receipt ID)
+    let digest = receipt_digest(raw_ver, opts)?;
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Check a claimed receipt id against the id computed from `raw_ver`.
+///
+/// `Err` means the VER object itself was rejected; `Ok(false)` means it is
+/// valid but `claimed_id` is not its id. The claim is hex in either case,
+/// optionally `0x`-prefixed; anything that is not 32 bytes of hex cannot
+/// match. The digests are compared in constant time.
+pub fn verify_receipt_id(raw_ver: &str, claimed_id: &str) -> Result<bool, VerError> {
+    let computed = receipt_digest(raw_ver, &Options::default())?;
+    let Some(claimed) = decode_id(claimed_id) else {
+        return Ok(false);
+    };
+    Ok(computed.ct_eq(&claimed).into())
+}
+
+/// Decode a 64-digit hex id, accepting either case and a `0x` prefix.
+fn decode_id(hex: &str) -> Option<[u8; 32]> {
+    let hex = hex
+        .strip_prefix("0x")
+        .or_else(|| hex.strip_prefix("0X"))
+        .unwrap_or(hex);
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut id = [0u8; 32];
+    for (byte, pair) in id.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        let pair = std::str::from_utf8(pair).ok()?;
+        *byte = u8::from_str_radix(pair, 16).ok()?;
+    }
+    Some(id)
+}
+
+/// `receipt_id` for a CBOR-encoded VER object; equal to the id of the
+/// equivalent JSON document.
+pub fn receipt_id_cbor(raw_ver: &[u8], opts: &Options) -> Result<String, VerError> {
+    let digest = opts.hash.digest(&canonical_bytes_cbor(raw_ver, opts)?);
+    Ok(digest.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Merkle root over the receipt ids of a batch, for anchoring the whole
+/// batch with a single 32-byte value.
+///
+/// Leaves are the receipt ids in input order (no sorting). Each level pairs
+/// adjacent nodes as `SHA-256(0x01 || left || right)`; the 0x01 prefix keeps
+/// interior nodes from colliding with 32-byte leaves. An odd node at the end
+/// of a level is promoted unchanged rather than duplicated, so a batch and
+/// the same batch with its last receipt repeated have different roots. A
+/// single receipt's root is its own id.
+pub fn merkle_root(receipts: &[&str]) -> Result<[u8; 32], VerError> {
+    if receipts.is_empty() {
+        return Err(VerError::EmptyBatch);
+    }
+
+    let opts = Options::default();
+    let mut level = receipts
+        .iter()
+        .map(|raw| receipt_digest(raw, &opts))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Sha256::new();
+                    hasher.update([0x01]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().into()
+                }
+                [odd] => *odd,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    Ok(level[0])
+}
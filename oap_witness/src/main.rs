@@ -1,75 +1,462 @@
-use sha2::{Sha256, Digest};
+use oap_witness::vectors::VECTORS;
+use oap_witness::{
+    canonical_bytes, canonical_bytes_cbor, merkle_root, read_bounded, read_bounded_bytes,
+    receipt_id, ExitCode, HashAlg, Options, VerError, DEFAULT_MAX_BYTES, STRICT_NUMERIC_PATHS,
+    V1_KEY_ORDER, VER_VERSION,
+};
 use serde_json::Value;
-use serde_json_canonicalizer::to_vec;
 use std::env;
+use std::io;
 
-/// Recursive check to ensure NO JSON Numbers exist in the tree.
-/// All numeric values must be strings as per VER v1.0 spec.
-fn assert_no_numbers(v: &Value) {
-    match v {
-        Value::Number(_) => panic!("SPEC VIOLATION: JSON Number detected. All numbers must be fixed-point strings."),
-        Value::Array(arr) => for i in arr { assert_no_numbers(i); },
-        Value::Object(map) => for (_, val) in map { assert_no_numbers(val); },
-        _ => {}
+const USAGE: &str = "\
+Usage: oap_witness [id] [OPTIONS] [<VER_JSON>]
+       oap_witness merkle [--file <PATH>] [--max-bytes <N>]
+       oap_witness selftest
+Run 'oap_witness --help' for details.";
+
+const HELP: &str = "\
+Compute OAP receipt ids from VER JSON.
+
+Usage: oap_witness [id] [OPTIONS] [<VER_JSON>]
+       oap_witness merkle [--file <PATH>] [--max-bytes <N>]
+       oap_witness selftest
+
+Commands:
+  id         Print the receipt id of one VER document (the default)
+  merkle     Print the Merkle root over receipts, one VER JSON per line
+  selftest   Check the built-in golden vectors
+
+Input and output (id, merkle):
+  <VER_JSON>                VER document as an argument (id only); without
+                            it, input is read from --file or stdin
+  -f, --file <PATH>         Read input from PATH ('-' = stdin)
+      --max-bytes <N>       Refuse input longer than N bytes (default 16 MiB)
+      --json                Print a JSON object instead of bare hex; errors are
+                            printed as an {\"error\": ...} object on stdout
+
+Options (id):
+      --hash <ALG>          sha256 (default) or keccak256
+      --require-order <V>   Required top-level key order: v1 or off (default)
+      --no-extra            Reject top-level and context fields outside the
+                            VER v1.0 schema (default: extra fields allowed)
+      --strict-numeric[=PATH,...]
+                            Require canonical fixed-point strings at PATHs
+                            (default: the VER v1.0 numeric fields)
+      --expect <HEX>[,...]  Exit 9 unless the id equals HEX; JSON results
+                            then carry \"match\": true or false. With
+                            --ndjson, one HEX is checked against every line,
+                            or a list gives each line its own, in order
+      --cbor                Input is CBOR from --file or stdin; the id is the
+                            same as for the equivalent JSON. Numbers must
+                            still be text strings: CBOR integers are rejected
+      --ndjson              Read one VER document per line and print one
+                            JSON object per line (implies --json); exits
+                            with the first failing line's status, 9 for
+                            an --expect mismatch
+
+  -h, --help                Print this help
+
+Exit status:
+  0  success              5  JSON number or bad fixed-point string
+  1  usage or internal    6  duplicate key (reserved)
+  2  invalid JSON/CBOR    7  input unreadable or too large
+  3  unsupported version  8  nesting too deep (reserved)
+  4  schema violation     9  --expect mismatch or selftest failure
+
+--merkle and --selftest are accepted as aliases for the subcommands.";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Command {
+    Id,
+    Merkle,
+    Selftest,
+    Help,
+}
+
+/// Parsed command line.
+struct Cli {
+    command: Command,
+    opts: Options,
+    /// Expected ids, normalized; more than one only with `--ndjson`.
+    expect: Vec<String>,
+    literal: Option<String>,
+    file: Option<String>,
+    max_bytes: u64,
+    json: bool,
+    ndjson: bool,
+    cbor: bool,
+    /// Whether an id-only option was given, for rejecting it elsewhere.
+    id_options: bool,
+}
+
+/// Normalize a user-supplied hex id for comparison: trim, drop `0x`, lowercase.
+fn normalize_hex(s: &str) -> String {
+    let s = s.trim();
+    s.strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s)
+        .to_ascii_lowercase()
+}
+
+/// Read VER input from a file path, or from stdin when the path is `-`.
+fn read_input(path: &str, max_bytes: u64) -> Result<String, VerError> {
+    let buffer = if path == "-" {
+        read_bounded(io::stdin().lock(), path, max_bytes)?
+    } else {
+        let file = std::fs::File::open(path).map_err(|e| VerError::Io(path.to_string(), e))?;
+        read_bounded(file, path, max_bytes)?
+    };
+    Ok(buffer.trim().to_string())
+}
+
+/// read_input for binary CBOR input, which is taken as-is (no trimming).
+fn read_cbor_input(path: &str, max_bytes: u64) -> Result<Vec<u8>, VerError> {
+    if path == "-" {
+        read_bounded_bytes(io::stdin().lock(), path, max_bytes)
+    } else {
+        let file = std::fs::File::open(path).map_err(|e| VerError::Io(path.to_string(), e))?;
+        read_bounded_bytes(file, path, max_bytes)
     }
 }
 
-/// Enforce the presence of a nested field and return it.
-fn require_field<'a>(v: &'a Value, path: &[&str]) -> &'a Value {
-    let mut cur = v;
-    for key in path {
-        cur = cur.get(*key).expect(&format!("SCHEMA ERROR: Missing required field path: {:?}", path));
+fn parse_order(name: &str) -> Result<Option<&'static [&'static str]>, String> {
+    match name {
+        "v1" => Ok(Some(V1_KEY_ORDER)),
+        "off" => Ok(None),
+        _ => Err(format!("unknown key order '{}' (expected v1 or off)", name)),
     }
-    cur
 }
 
-fn main() {
-    // 1. Capture Raw Input (from Argument or Stdin)
-    let args: Vec<String> = env::args().collect();
-    let raw_ver = if args.len() > 1 {
-        args[1].clone()
-    } else {
-        use std::io::{self, Read};
-        let mut buffer = String::new();
-        io::stdin().read_to_string(&mut buffer).expect("FAILED: Could not read from stdin");
-        buffer.trim().to_string()
+/// Split `--flag=value` or take the value from the next argument.
+fn flag_value(
+    inline: Option<&str>,
+    args: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> Result<String, String> {
+    match inline {
+        Some(value) => Ok(value.to_string()),
+        None => args
+            .next()
+            .ok_or_else(|| format!("{} requires a value", flag)),
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Cli, String> {
+    let mut cli = Cli {
+        command: Command::Id,
+        opts: Options::default(),
+        expect: Vec::new(),
+        literal: None,
+        file: None,
+        max_bytes: DEFAULT_MAX_BYTES,
+        json: false,
+        ndjson: false,
+        cbor: false,
+        id_options: false,
     };
+    let mut first = true;
+    while let Some(arg) = args.next() {
+        let is_first = std::mem::replace(&mut first, false);
+        if is_first {
+            let subcommand = match arg.as_str() {
+                "id" => Some(Command::Id),
+                "merkle" => Some(Command::Merkle),
+                "selftest" => Some(Command::Selftest),
+                "help" => Some(Command::Help),
+                _ => None,
+            };
+            if let Some(command) = subcommand {
+                cli.command = command;
+                continue;
+            }
+        }
 
-    if raw_ver.is_empty() {
-        eprintln!("Usage: oap_witness <VER_JSON> or pipe JSON into it.");
-        std::process::exit(1);
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value)),
+            _ => (arg.as_str(), None),
+        };
+        match flag {
+            "-h" | "--help" if inline.is_none() => cli.command = Command::Help,
+            "--merkle" if inline.is_none() => cli.command = Command::Merkle,
+            "--selftest" if inline.is_none() => cli.command = Command::Selftest,
+            "--json" if inline.is_none() => cli.json = true,
+            "--ndjson" if inline.is_none() => {
+                cli.json = true;
+                cli.ndjson = true;
+                cli.id_options = true;
+            }
+            "--cbor" if inline.is_none() => {
+                cli.cbor = true;
+                cli.id_options = true;
+            }
+            "-f" | "--file" => cli.file = Some(flag_value(inline, &mut args, flag)?),
+            "--max-bytes" => {
+                let n = flag_value(inline, &mut args, flag)?;
+                cli.max_bytes = n
+                    .parse()
+                    .map_err(|_| format!("invalid --max-bytes '{}'", n))?;
+            }
+            "--expect" => {
+                let list = flag_value(inline, &mut args, flag)?;
+                cli.expect.extend(list.split(',').map(normalize_hex));
+                cli.id_options = true;
+            }
+            "--hash" => {
+                let name = flag_value(inline, &mut args, flag)?;
+                cli.opts.hash = name.parse::<HashAlg>().map_err(|e| e.to_string())?;
+                cli.id_options = true;
+            }
+            "--require-order" => {
+                cli.opts.require_order = parse_order(&flag_value(inline, &mut args, flag)?)?;
+                cli.id_options = true;
+            }
+            "--no-extra" if inline.is_none() => {
+                cli.opts.no_extra = true;
+                cli.id_options = true;
+            }
+            "--strict-numeric" => {
+                cli.opts.strict_numeric = Some(match inline {
+                    Some(list) => list.split(',').map(str::to_string).collect(),
+                    None => STRICT_NUMERIC_PATHS.iter().map(|p| p.to_string()).collect(),
+                });
+                cli.id_options = true;
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ if cli.literal.is_some() => return Err("more than one VER argument".to_string()),
+            _ => cli.literal = Some(arg),
+        }
     }
 
-    // 2. Initial Parse
-    let json_value: Value = serde_json::from_str(&raw_ver)
-        .expect("SYNTAX ERROR: Invalid JSON format");
+    if cli.literal.is_some() && cli.file.is_some() {
+        return Err("give the VER document as an argument or --file, not both".to_string());
+    }
+    if cli.ndjson && cli.literal.is_some() {
+        return Err("--ndjson reads --file or stdin".into());
+    }
+    if !cli.ndjson && cli.expect.len() > 1 {
+        return Err("--expect takes a list of ids only with --ndjson".into());
+    }
+    if cli.cbor && (cli.literal.is_some() || cli.ndjson) {
+        return Err("--cbor reads one document from --file or stdin".into());
+    }
+    if cli.command == Command::Merkle && (cli.id_options || cli.literal.is_some()) {
+        return Err("merkle reads receipts from --file or stdin and takes no id options".into());
+    }
+    Ok(cli)
+}
 
-    // 3. HARD AUDIT: Version Lock
-    assert_eq!(
-        json_value["version"].as_str().unwrap_or(""),
-        "1.0",
-        "SPEC ERROR: Unsupported VER version (Expected '1.0')"
+/// Run the built-in golden vectors; exits nonzero if any id differs.
+fn selftest() -> ! {
+    let mut failed = 0;
+    for vector in VECTORS {
+        match receipt_id(vector.ver, &Options::default()) {
+            Ok(id) if id == vector.id => println!("PASS {}", vector.name),
+            Ok(id) => {
+                failed += 1;
+                println!(
+                    "FAIL {}: computed {}, expected {}",
+                    vector.name, id, vector.id
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                println!("FAIL {}: {}", vector.name, e);
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!("SELFTEST FAILED: {} of {} vectors", failed, VECTORS.len());
+        exit(ExitCode::DigestMismatch);
+    }
+    exit(ExitCode::Ok);
+}
+
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    exit(ExitCode::Usage);
+}
+
+fn exit(code: ExitCode) -> ! {
+    std::process::exit(code as i32);
+}
+
+/// Exit status for each way a VER input can be rejected.
+fn exit_code(e: &VerError) -> ExitCode {
+    match e {
+        VerError::Syntax(_) | VerError::Cbor(_) => ExitCode::Syntax,
+        VerError::Version(_) => ExitCode::Version,
+        VerError::MissingField(_)
+        | VerError::UnexpectedField(_)
+        | VerError::KeyOrder(_)
+        | VerError::EmptyBatch => ExitCode::Schema,
+        VerError::NumberFound(_) | VerError::InvalidFixedPoint(..) => ExitCode::Number,
+        VerError::Io(..) | VerError::TooLarge(_) => ExitCode::Io,
+        VerError::Canonicalization => ExitCode::Usage,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `{"error":{...}}` object for `--json` output.
+fn error_json(e: &VerError) -> String {
+    format!(
+        r#"{{"error":{{"kind":"{}","message":{},"path":{}}}}}"#,
+        e.kind(),
+        Value::from(e.to_string()),
+        Value::Array(e.path())
+    )
+}
+
+/// Receipt id and JSON result line for an audited document's canonical
+/// bytes; the line carries `"match"` when an id is expected.
+fn id_result(canonical: &[u8], hash: HashAlg, expected: Option<&str>) -> (String, String) {
+    let id = hex(&hash.digest(canonical));
+    let matched = match expected {
+        Some(expected) => format!(r#","match":{}"#, expected == id),
+        None => String::new(),
+    };
+    let json = format!(
+        r#"{{"version":"{}","receipt_id":"{}","canonical_len":{}{}}}"#,
+        VER_VERSION,
+        id,
+        canonical.len(),
+        matched
     );
+    (id, json)
+}
+
+/// Print the id of one audited document and check it against `--expect`.
+fn report_id(canonical: Result<Vec<u8>, VerError>, cli: &Cli) {
+    match canonical {
+        Ok(canonical) => {
+            let expected = cli.expect.first();
+            let (id, json) = id_result(&canonical, cli.opts.hash, expected.map(String::as_str));
+            println!("{}", if cli.json { &json } else { &id });
+            if let Some(expected) = expected {
+                if *expected != id {
+                    eprintln!("MISMATCH: computed {}, expected {}", id, expected);
+                    exit(ExitCode::DigestMismatch);
+                }
+            }
+        }
+        Err(e) => fail(&e, cli.json),
+    }
+}
 
-    // 4. HARD AUDIT: Schema Compliance
-    require_field(&json_value, &["context", "engine"]);
-    require_field(&json_value, &["context", "logic_hash"]);
-    require_field(&json_value, &["input"]);
-    require_field(&json_value, &["output"]);
+/// Report a failure on stdout (`--json`) or stderr, then exit with its code.
+fn fail(e: &VerError, json: bool) -> ! {
+    if json {
+        println!("{}", error_json(e));
+    } else {
+        eprintln!("{}", e);
+    }
+    exit(exit_code(e));
+}
 
-    // 5. HARD AUDIT: Determinism Enforcement (No Floats)
-    assert_no_numbers(&json_value);
+fn main() {
+    // 1. Parse the command line
+    let mut cli = match parse_args(env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("USAGE ERROR: {}", e);
+            usage();
+        }
+    };
+    match cli.command {
+        Command::Help => {
+            println!("{}", HELP);
+            return;
+        }
+        Command::Selftest => selftest(),
+        Command::Id | Command::Merkle => {}
+    }
 
-    // 6. RFC 8785 Canonicalization
-    let canonical_bytes = to_vec(&json_value)
-        .expect("INTERNAL ERROR: Canonicalization failed despite validation");
+    // 2. Capture Raw Input (from Argument, File or Stdin)
+    let max_bytes = cli.max_bytes;
+    if cli.cbor {
+        let raw_ver = match read_cbor_input(cli.file.as_deref().unwrap_or("-"), max_bytes) {
+            Ok(input) => input,
+            Err(e) => fail(&e, cli.json),
+        };
+        if raw_ver.is_empty() {
+            usage();
+        }
+        report_id(canonical_bytes_cbor(&raw_ver, &cli.opts), &cli);
+        return;
+    }
+    let input = match cli.literal.take() {
+        Some(json) if json.len() as u64 > max_bytes => Err(VerError::TooLarge(max_bytes)),
+        Some(json) => Ok(json),
+        None => read_input(cli.file.as_deref().unwrap_or("-"), max_bytes),
+    };
+    let raw_ver = match input {
+        Ok(input) => input,
+        Err(e) => fail(&e, cli.json),
+    };
 
-    // 7. SHA-256 Hashing
-    let mut hasher = Sha256::new();
-    hasher.update(&canonical_bytes);
-    let result = hasher.finalize();
+    if raw_ver.is_empty() {
+        usage();
+    }
+
+    let lines = || raw_ver.lines().map(str::trim).filter(|l| !l.is_empty());
+
+    if cli.command == Command::Merkle {
+        let receipts: Vec<&str> = lines().collect();
+        match merkle_root(&receipts) {
+            Ok(root) if cli.json => println!(
+                r#"{{"version":"{}","merkle_root":"{}","leaves":{}}}"#,
+                VER_VERSION,
+                hex(&root),
+                receipts.len()
+            ),
+            Ok(root) => println!("{}", hex(&root)),
+            Err(e) => fail(&e, cli.json),
+        }
+        return;
+    }
+
+    if cli.ndjson {
+        let count = lines().count();
+        if cli.expect.len() > 1 && cli.expect.len() != count {
+            eprintln!(
+                "USAGE ERROR: --expect lists {} ids for {} lines",
+                cli.expect.len(),
+                count
+            );
+            usage();
+        }
+        let expected = |i: usize| match cli.expect.len() {
+            1 => Some(cli.expect[0].as_str()),
+            _ => cli.expect.get(i).map(String::as_str),
+        };
+
+        let mut status = ExitCode::Ok;
+        for (i, line) in lines().enumerate() {
+            let failure = match canonical_bytes(line, &cli.opts) {
+                Ok(canonical) => {
+                    let (id, json) = id_result(&canonical, cli.opts.hash, expected(i));
+                    println!("{}", json);
+                    expected(i)
+                        .filter(|expected| *expected != id)
+                        .map(|_| ExitCode::DigestMismatch)
+                }
+                Err(e) => {
+                    println!("{}", error_json(&e));
+                    Some(exit_code(&e))
+                }
+            };
+            if let (ExitCode::Ok, Some(code)) = (status, failure) {
+                status = code;
+            }
+        }
+        if status != ExitCode::Ok {
+            exit(status);
+        }
+        return;
+    }
 
-    // 8. FINAL OUTPUT: Lowercase Hex (Immutable Receipt ID)
-    // The {:x} format specifier ensures lowercase per VER spec.
-    println!("{:x}", result);
+    // 3. Compute and check the receipt id
+    report_id(canonical_bytes(&raw_ver, &cli.opts), &cli);
 }
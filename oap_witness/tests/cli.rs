@@ -0,0 +1,397 @@
+//! End-to-end runs of the oap_witness binary.
+
+use oap_witness::vectors::VECTORS;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn run(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_oap_witness"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+fn temp_file(name: &str, contents: impl AsRef<[u8]>) -> String {
+    let path =
+        std::env::temp_dir().join(format!("oap_witness_cli_{}_{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn id_from_argument_stdin_and_file_agree() {
+    let vector = &VECTORS[0];
+    let path = temp_file("id.json", vector.ver);
+    for (args, stdin) in [
+        (vec![vector.ver], ""),
+        (vec!["id", vector.ver], ""),
+        (vec![], vector.ver),
+        (vec!["id", "--file", "-"], vector.ver),
+        (vec!["--file", path.as_str()], ""),
+        (vec!["id", "-f", path.as_str()], ""),
+        (vec![&format!("--file={}", path)], ""),
+    ] {
+        let output = run(&args, stdin);
+        assert!(output.status.success(), "{:?}", args);
+        assert_eq!(stdout(&output), vector.id, "{:?}", args);
+    }
+}
+
+#[test]
+fn expect_sets_the_exit_status() {
+    let vector = &VECTORS[0];
+    let upper = format!("0x{}", vector.id.to_ascii_uppercase());
+    assert!(run(&["--expect", &upper, vector.ver], "").status.success());
+
+    let output = run(&["--expect=00", vector.ver], "");
+    assert_eq!(output.status.code(), Some(9));
+    assert_eq!(stdout(&output), vector.id);
+}
+
+/// keccak256 of the canonical bytes of `VECTORS[0]`, from an independent
+/// Keccak implementation.
+const SPEC_EXAMPLE_KECCAK_ID: &str =
+    "9c10ba3dfbbda9c16e68af17dcefdd7f9e65a72a1b5d462a9b9fbd39aaa68dbe";
+
+#[test]
+fn hash_and_order_options() {
+    let vector = &VECTORS[0];
+    let keccak = run(&["--hash", "keccak256", vector.ver], "");
+    assert!(keccak.status.success());
+    assert_eq!(stdout(&keccak), SPEC_EXAMPLE_KECCAK_ID);
+    assert_eq!(stdout(&run(&["--hash=sha256", vector.ver], "")), vector.id);
+
+    // The second vector's keys are deliberately out of order.
+    let unordered = VECTORS[1].ver;
+    assert!(run(&["--require-order", "off", unordered], "")
+        .status
+        .success());
+    assert_eq!(
+        run(&["--require-order=v1", unordered], "").status.code(),
+        Some(4)
+    );
+}
+
+/// CBOR encoding of a JSON document, keeping its key order.
+fn to_cbor(json: &str) -> Vec<u8> {
+    let value: ciborium::Value = serde_json::from_str(json).unwrap();
+    let mut cbor = Vec::new();
+    ciborium::into_writer(&value, &mut cbor).unwrap();
+    cbor
+}
+
+#[test]
+fn cbor_input_has_the_json_receipt_id() {
+    use oap_witness::{receipt_id_cbor, Options};
+
+    for vector in VECTORS {
+        let cbor = to_cbor(vector.ver);
+        assert_eq!(
+            receipt_id_cbor(&cbor, &Options::default()).unwrap(),
+            vector.id,
+            "{}",
+            vector.name
+        );
+        let path = temp_file(&format!("{}.cbor", vector.name), &cbor);
+        let output = run(&["--cbor", "--file", &path], "");
+        assert!(output.status.success(), "{}", vector.name);
+        assert_eq!(stdout(&output), vector.id, "{}", vector.name);
+    }
+
+    // Key order is read off the CBOR map as written.
+    let unordered = temp_file("unordered.cbor", to_cbor(VECTORS[1].ver));
+    assert_eq!(
+        run(&["--cbor", "--require-order=v1", "-f", &unordered], "")
+            .status
+            .code(),
+        Some(4)
+    );
+
+    // CBOR integers are numbers too, and byte strings have no JSON form.
+    let integer = temp_file(
+        "integer.cbor",
+        to_cbor(
+            r#"{"version":"1.0","context":{"engine":"e","logic_hash":"h"},"input":{"x":15},"output":{}}"#,
+        ),
+    );
+    assert_eq!(run(&["--cbor", "-f", &integer], "").status.code(), Some(5));
+    let mut bytes = Vec::new();
+    ciborium::into_writer(&ciborium::Value::Bytes(vec![1, 2]), &mut bytes).unwrap();
+    let bytes = temp_file("bytes.cbor", bytes);
+    assert_eq!(run(&["--cbor", "-f", &bytes], "").status.code(), Some(2));
+    assert_eq!(run(&["--cbor", VECTORS[0].ver], "").status.code(), Some(1));
+}
+
+#[test]
+fn extra_fields_pass_unless_no_extra() {
+    let vector = &VECTORS[0];
+    for (ver, path) in [
+        (
+            vector.ver.replacen("{", r#"{"contex":{},"#, 1),
+            r#"["contex"]"#,
+        ),
+        (
+            vector
+                .ver
+                .replacen(r#""context":{"#, r#""context":{"mode":"x","#, 1),
+            r#"["context","mode"]"#,
+        ),
+    ] {
+        let default = run(&[&ver], "");
+        assert!(default.status.success(), "{}", ver);
+        assert_ne!(stdout(&default), vector.id);
+
+        let strict = run(&["--no-extra", "--json", &ver], "");
+        assert_eq!(strict.status.code(), Some(4), "{}", ver);
+        let v: serde_json::Value = serde_json::from_str(&stdout(&strict)).unwrap();
+        assert_eq!(v["error"]["kind"], "unexpected_field");
+        assert_eq!(v["error"]["path"].to_string(), path);
+    }
+
+    // Extra fields inside input and output are not checked.
+    let ver = vector
+        .ver
+        .replacen(r#""input":{"#, r#""input":{"extra":"1","#, 1);
+    assert!(run(&["--no-extra", &ver], "").status.success());
+    assert_eq!(stdout(&run(&["--no-extra", vector.ver], "")), vector.id);
+}
+
+#[test]
+fn merkle_subcommand_and_alias_agree() {
+    let batch: String = VECTORS.iter().map(|v| format!("{}\n", v.ver)).collect();
+    let subcommand = run(&["merkle"], &batch);
+    let alias = run(&["--merkle"], &batch);
+    assert!(subcommand.status.success() && alias.status.success());
+    assert_eq!(stdout(&subcommand).len(), 64);
+    assert_eq!(stdout(&subcommand), stdout(&alias));
+
+    // A single receipt's root is its own id.
+    assert_eq!(stdout(&run(&["merkle"], VECTORS[0].ver)), VECTORS[0].id);
+}
+
+#[test]
+fn selftest_subcommand_and_alias_pass() {
+    for args in [["selftest"], ["--selftest"]] {
+        let output = run(&args, "");
+        assert!(output.status.success());
+        assert_eq!(stdout(&output).lines().count(), VECTORS.len());
+    }
+}
+
+#[test]
+fn help_documents_commands_and_options() {
+    for args in [["--help"], ["-h"], ["help"]] {
+        let output = run(&args, "");
+        assert!(output.status.success());
+        let help = stdout(&output);
+        for item in [
+            "merkle",
+            "selftest",
+            "--file",
+            "--hash",
+            "--expect",
+            "--max-bytes",
+        ] {
+            assert!(help.contains(item), "{} missing from help", item);
+        }
+    }
+}
+
+#[test]
+fn usage_errors_exit_1() {
+    let ver = VECTORS[0].ver;
+    for args in [
+        vec!["--bogus"],
+        vec!["--hash"],
+        vec!["--hash", "md5", ver],
+        vec!["--max-bytes", "lots", ver],
+        vec![ver, ver],
+        vec![ver, "--file", "x"],
+        vec!["merkle", "--hash", "sha256"],
+        vec!["--selftest=1"],
+        vec!["--ndjson", ver],
+        vec!["--expect", "00,11", ver],
+        vec!["merkle", "--ndjson"],
+    ] {
+        let output = run(&args, "");
+        assert_eq!(output.status.code(), Some(1), "{:?}", args);
+        assert!(output.stdout.is_empty(), "{:?}", args);
+    }
+}
+
+#[test]
+fn max_bytes_limits_every_input() {
+    let ver = VECTORS[0].ver;
+    assert_eq!(run(&["--max-bytes", "10", ver], "").status.code(), Some(7));
+    assert_eq!(run(&["--max-bytes=10"], ver).status.code(), Some(7));
+    let exact = ver.len().to_string();
+    assert!(run(&["--max-bytes", &exact, ver], "").status.success());
+}
+
+#[test]
+fn json_success_object() {
+    let vector = &VECTORS[0];
+    let output = run(&["--json", vector.ver], "");
+    assert!(output.status.success());
+    let v: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(v["version"], "1.0");
+    assert_eq!(v["receipt_id"], vector.id);
+    let canonical = oap_witness::canonical_bytes(vector.ver, &Default::default()).unwrap();
+    assert_eq!(v["canonical_len"], canonical.len());
+
+    // Without --json the output stays bare hex.
+    assert_eq!(stdout(&run(&[vector.ver], "")), vector.id);
+}
+
+#[test]
+fn json_errors_go_to_stdout_with_kind_and_path() {
+    let ver = r#"{"version":"1.0","context":{"engine":"e","logic_hash":"h"},"input":{"bars":["1",2]},"output":{}}"#;
+    let output = run(&["--json", ver], "");
+    assert_eq!(output.status.code(), Some(5));
+    let v: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(v["error"]["kind"], "number_found");
+    assert_eq!(v["error"]["path"], serde_json::json!(["input", "bars", 1]));
+    assert!(v["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("input.bars[1]"));
+
+    let output = run(&["--json", "{"], "");
+    assert_eq!(output.status.code(), Some(2));
+    let v: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(v["error"]["kind"], "syntax");
+    assert_eq!(v["error"]["path"], serde_json::json!([]));
+}
+
+#[test]
+fn ndjson_emits_one_object_per_line() {
+    let input = format!("{}\n\nnot json\n{}\n", VECTORS[0].ver, VECTORS[1].ver);
+    let output = run(&["--ndjson"], &input);
+    assert_eq!(output.status.code(), Some(2));
+    let lines: Vec<serde_json::Value> = stdout(&output)
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["receipt_id"], VECTORS[0].id);
+    assert_eq!(lines[1]["error"]["kind"], "syntax");
+    assert_eq!(lines[2]["receipt_id"], VECTORS[1].id);
+
+    let valid: String = VECTORS.iter().map(|v| format!("{}\n", v.ver)).collect();
+    assert!(run(&["id", "--ndjson"], &valid).status.success());
+}
+
+#[test]
+fn ndjson_checks_expect_per_line() {
+    let input: String = VECTORS.iter().map(|v| format!("{}\n", v.ver)).collect();
+    let ids: Vec<&str> = VECTORS.iter().map(|v| v.id).collect();
+    let matches = |output: &Output| -> Vec<serde_json::Value> {
+        stdout(output)
+            .lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["match"].clone())
+            .collect()
+    };
+
+    // A list gives each line its own id.
+    let list = run(&["--ndjson", "--expect", &ids.join(",")], &input);
+    assert!(list.status.success());
+    assert_eq!(matches(&list), vec![serde_json::json!(true); VECTORS.len()]);
+    let mut swapped = ids.clone();
+    swapped.swap(0, 1);
+    let output = run(&["--ndjson", "--expect", &swapped.join(",")], &input);
+    assert_eq!(output.status.code(), Some(9));
+    let mut expected = vec![serde_json::json!(true); VECTORS.len()];
+    expected[0] = serde_json::json!(false);
+    expected[1] = serde_json::json!(false);
+    assert_eq!(matches(&output), expected);
+
+    // A single id is checked against every line.
+    let twice = format!("{}\n{}\n", VECTORS[0].ver, VECTORS[0].ver);
+    let upper = format!("0x{}", VECTORS[0].id.to_ascii_uppercase());
+    let output = run(&["--ndjson", "--expect", &upper], &twice);
+    assert!(output.status.success());
+    assert_eq!(matches(&output), vec![serde_json::json!(true); 2]);
+    let output = run(&["--ndjson", "--expect", VECTORS[1].id], &input);
+    assert_eq!(output.status.code(), Some(9));
+    assert_eq!(matches(&output)[1], serde_json::json!(true));
+
+    // A list that does not cover every line is a usage error.
+    let output = run(&["--ndjson", "--expect", &ids[..2].join(",")], &input);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn merkle_json() {
+    let batch: String = VECTORS.iter().map(|v| format!("{}\n", v.ver)).collect();
+    let root = stdout(&run(&["merkle"], &batch));
+    let v: serde_json::Value =
+        serde_json::from_str(&stdout(&run(&["merkle", "--json"], &batch))).unwrap();
+    assert_eq!(v["merkle_root"], root.as_str());
+    assert_eq!(v["leaves"], VECTORS.len());
+}
+
+#[test]
+fn each_error_category_has_its_exit_code() {
+    use oap_witness::ExitCode;
+
+    let missing = std::env::temp_dir().join("oap_witness_cli_does_not_exist");
+    let missing = missing.to_string_lossy();
+    let cases: &[(&[&str], ExitCode)] = &[
+        (&[VECTORS[0].ver], ExitCode::Ok),
+        (&["--bogus"], ExitCode::Usage),
+        (&[r#"{"version":"1.0","#], ExitCode::Syntax),
+        (&[r#"{"version":"2.0"}"#], ExitCode::Version),
+        (&[r#"{"version":"1.0","context":{}}"#], ExitCode::Schema),
+        (
+            &[
+                r#"{"version":"1.0","context":{"engine":"e","logic_hash":"h"},"input":{"x":1.5},"output":{}}"#,
+            ],
+            ExitCode::Number,
+        ),
+        (
+            &[
+                "--strict-numeric=input.x",
+                r#"{"version":"1.0","context":{"engine":"e","logic_hash":"h"},"input":{"x":"01"},"output":{}}"#,
+            ],
+            ExitCode::Number,
+        ),
+        (&["--file", &missing], ExitCode::Io),
+        (&["--max-bytes=1", VECTORS[0].ver], ExitCode::Io),
+        (&["--expect=00", VECTORS[0].ver], ExitCode::DigestMismatch),
+    ];
+    for (args, code) in cases {
+        assert_eq!(
+            run(args, "").status.code(),
+            Some(*code as i32),
+            "{:?}",
+            args
+        );
+        // --json changes where the error goes, not the status.
+        let json: Vec<&str> = std::iter::once("--json")
+            .chain(args.iter().copied())
+            .collect();
+        if *code != ExitCode::Usage {
+            assert_eq!(
+                run(&json, "").status.code(),
+                Some(*code as i32),
+                "{:?}",
+                json
+            );
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Receipt-id stability: the wire contract signed receipts depend on.
+//!
+//! Each expected id was computed by an independent RFC 8785 implementation,
+//! not by this crate. A failure here means canonicalization or hashing
+//! output changed, typically through a serde_json, serde_json_canonicalizer
+//! or sha2 upgrade, and every previously issued receipt id would stop
+//! matching. Do not update a vector to make this pass; fix the regression.
+
+use oap_witness::{canonical_bytes, receipt_id, Options};
+
+/// (name, VER input, expected SHA-256 receipt id)
+const STABILITY_VECTORS: &[(&str, &str, &str)] = &[
+    // Required objects with no members.
+    (
+        "empty_input_output",
+        r#"{"version":"1.0","context":{"engine":"anchor-v1","logic_hash":"00"},"input":{},"output":{}}"#,
+        "720868791d4b506f4f439474f167f15a30bf625df619a43db6783279aa339d3b",
+    ),
+    // Empty arrays, nested empty arrays and empty objects.
+    (
+        "empty_arrays",
+        r#"{"version":"1.0","context":{"engine":"anchor-v1","logic_hash":"01"},"input":{"bars":[],"tags":[[]]},"output":{"fills":[],"meta":{}}}"#,
+        "44da4f3debc8d21f9071f0e407a0a66fa5d5e31bcbb3c8b54b2b136d8a0af284",
+    ),
+    // true, false and null pass through unchanged.
+    (
+        "literals",
+        r#"{"version":"1.0","context":{"engine":"anchor-v1","logic_hash":"02"},"input":{"live":true,"paper":false,"note":null},"output":{}}"#,
+        "f126fe247a29963feda7db3c349a045ab4854d70726d85e33a47c61107b9a85e",
+    ),
+    // Fixed-point strings are opaque: no trimming, rounding or exponent form,
+    // however deep they sit.
+    (
+        "nested_fixed_point",
+        r#"{"version":"1.0","context":{"engine":"anchor-v1","logic_hash":"03"},"input":{"bars":[{"o":"2045.5000","c":"-0.0001"},{"o":"0","c":"10.10"}],"risk":{"limits":{"max":"1.000000000000000001","min":"-99999999999999999999.5"}}},"output":{"orders":[{"qty":"0.5","px":"2030.1000"}]}}"#,
+        "9413f30fe48c34766be76948011de88d3a48fb720944cf8ba6ddceeec0e5c202",
+    ),
+    // Members are sorted at every level regardless of input order.
+    (
+        "key_order_reversed",
+        r#"{"output":{"tp":"2","sl":"1","action":"BUY"},"input":{"z":"1","a":"2"},"context":{"logic_hash":"04","engine":"anchor-v1"},"version":"1.0"}"#,
+        "e44aa12eca3ea21efec9852a1827605418cde7341076ea3a80d8fa60fa52b603",
+    ),
+    // Byte-wise order: digits before uppercase before `_` before lowercase,
+    // and a key sorts before its extensions.
+    (
+        "key_order_case_and_prefix",
+        r#"{"version":"1.0","context":{"engine":"anchor-v1","logic_hash":"05"},"input":{"b":"1","B":"2","a1":"3","a":"4","_":"5","A":"6","10":"7","9":"8"},"output":{}}"#,
+        "afcbb09f037af163d7af3782574746280a72321bb8ddcd0833efde58322041f0",
+    ),
+    // Keys sort by UTF-16 code units (RFC 8785 3.2.3), so the astral U+1F600
+    // (surrogate 0xD83D) sorts before U+FF61, unlike a code-point sort.
+    (
+        "key_order_utf16",
+        r#"{"version":"1.0","context":{"engine":"anchor-v1","logic_hash":"06"},"input":{"\uff61":"halfwidth","\ud83d\ude00":"astral","\u00e9":"latin","z":"ascii"},"output":{}}"#,
+        "84bb8335c51ed10c084a6d1e70f57e34bac5772528a2d99981c1bd6db34ed688",
+    ),
+    // An escaped and a raw U+00E9 decode to the same string and id.
+    (
+        "unicode_escaped_nfc",
+        r#"{"version":"1.0","context":{"engine":"anchor-v1","logic_hash":"07"},"input":{"symbol":"caf\u00e9"},"output":{}}"#,
+        "b57bd238a60557f8c8222f57f454a298681c74a95672093cfdb16802851d6709",
+    ),
+    (
+        "unicode_raw_nfc",
+        "{\"version\":\"1.0\",\"context\":{\"engine\":\"anchor-v1\",\"logic_hash\":\"07\"},\"input\":{\"symbol\":\"caf\u{e9}\"},\"output\":{}}",
+        "b57bd238a60557f8c8222f57f454a298681c74a95672093cfdb16802851d6709",
+    ),
+    // No Unicode normalization: decomposed e + U+0301 keeps its own id.
+    (
+        "unicode_nfd_not_normalized",
+        r#"{"version":"1.0","context":{"engine":"anchor-v1","logic_hash":"07"},"input":{"symbol":"cafe\u0301"},"output":{}}"#,
+        "b87a310668b0a458adf4a6a9161318a06443e94be43febca87ebebf1bbdb41f6",
+    ),
+    // Only `"`, `\\` and controls below U+0020 are escaped on output (short
+    // forms where defined, else lowercase \u00xx); DEL, `/`, U+2028/U+2029
+    // and astral characters are emitted as raw UTF-8.
+    (
+        "string_escapes",
+        r#"{"version":"1.0","context":{"engine":"anchor-v1","logic_hash":"08"},"input":{"ctl":"\u0000\u0001\u001f\u007f","ws":"\b\t\n\f\r","q":"\"\\\/","sep":"\u2028\u2029","emoji":"\ud83d\ude00"},"output":{}}"#,
+        "2bf9b84d385e7e942542228ed34dc8f2f3d1696084ca3b86ffb91078546cbcf6",
+    ),
+    // Insignificant whitespace is dropped.
+    (
+        "whitespace_insensitive",
+        "{ \"version\" : \"1.0\" , \"context\" : { \"engine\" : \"anchor-v1\" , \"logic_hash\" : \"09\" } , \"input\" : { } ,\n\t\"output\" : { \"action\" : \"HOLD\" } }",
+        "0cbd0a9bff0ab4c092aa65231535e848c0111f9c669629f9f29fedb80a000424",
+    ),
+];
+
+#[test]
+fn receipt_ids_are_stable() {
+    let opts = Options::default();
+    let mut failures = Vec::new();
+    for (name, ver, expected) in STABILITY_VECTORS {
+        match receipt_id(ver, &opts) {
+            Ok(id) if id == *expected => {}
+            Ok(id) => {
+                let canonical = canonical_bytes(ver, &opts).unwrap();
+                failures.push(format!(
+                    "{}: computed {}, expected {}\n  canonical: {}",
+                    name,
+                    id,
+                    expected,
+                    String::from_utf8_lossy(&canonical)
+                ));
+            }
+            Err(e) => failures.push(format!("{}: {}", name, e)),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "receipt ids changed; issued receipts would no longer verify:\n{}",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn unicode_is_not_normalized() {
+    let id = |name: &str| {
+        STABILITY_VECTORS
+            .iter()
+            .find(|(n, ..)| *n == name)
+            .unwrap()
+            .2
+    };
+    assert_eq!(id("unicode_escaped_nfc"), id("unicode_raw_nfc"));
+    assert_ne!(id("unicode_escaped_nfc"), id("unicode_nfd_not_normalized"));
+}
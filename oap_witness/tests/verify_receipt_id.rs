@@ -0,0 +1,51 @@
+//! `verify_receipt_id` separates rejected input from a wrong claimed id.
+
+use oap_witness::vectors::VECTORS;
+use oap_witness::{verify_receipt_id, VerError};
+
+#[test]
+fn matching_id_verifies_in_any_case() {
+    for vector in VECTORS {
+        assert!(verify_receipt_id(vector.ver, vector.id).unwrap());
+        let upper = vector.id.to_ascii_uppercase();
+        assert!(verify_receipt_id(vector.ver, &upper).unwrap());
+        assert!(verify_receipt_id(vector.ver, &format!("0x{}", upper)).unwrap());
+    }
+}
+
+#[test]
+fn wrong_id_is_false() {
+    let vector = &VECTORS[0];
+    // Another receipt's id, a one-digit change, and claims that are not ids.
+    let mut flipped = vector.id.to_string();
+    flipped.replace_range(63.., if flipped.ends_with('0') { "1" } else { "0" });
+    for claimed in [VECTORS[1].id, flipped.as_str(), "", "00", &vector.id[..62]] {
+        assert!(
+            !verify_receipt_id(vector.ver, claimed).unwrap(),
+            "{}",
+            claimed
+        );
+    }
+    let not_hex = format!("{}zz", &vector.id[..62]);
+    assert!(!verify_receipt_id(vector.ver, &not_hex).unwrap());
+}
+
+#[test]
+fn malformed_ver_is_an_error() {
+    let id = VECTORS[0].id;
+    assert!(matches!(
+        verify_receipt_id(r#"{"version":"1.0","#, id),
+        Err(VerError::Syntax(_))
+    ));
+    assert!(matches!(
+        verify_receipt_id(r#"{"version":"2.0"}"#, id),
+        Err(VerError::Version(_))
+    ));
+    assert!(matches!(
+        verify_receipt_id(
+            r#"{"version":"1.0","context":{"engine":"e","logic_hash":"h"},"input":{"x":1},"output":{}}"#,
+            id
+        ),
+        Err(VerError::NumberFound(_))
+    ));
+}
@@ -110,6 +110,8 @@ def _send_and_measure(
 def main() -> None:
     ap = argparse.ArgumentParser()
     ap.add_argument("--contract", default=os.environ.get("CONTRACT_ADDRESS", ""))
+    # AnchorLens in front of the contract; it serves the batch views.
+    ap.add_argument("--lens", default=os.environ.get("LENS_ADDRESS", ""))
     ap.add_argument("--rpc-url", default=os.environ.get("RPC_URL", ""))
     ap.add_argument("--private-key", default=os.environ.get("PRIVATE_KEY", ""))
     ap.add_argument("--hw-id", default=os.environ.get("HW_ID", ""))
@@ -126,6 +128,7 @@ def main() -> None:
     args = ap.parse_args()
 
     contract = args.contract or _must_env("CONTRACT_ADDRESS")
+    lens = args.lens or _must_env("LENS_ADDRESS")
     rpc_url = args.rpc_url or _must_env("RPC_URL")
     pk = args.private_key or _must_env("PRIVATE_KEY")
     hw_hex = args.hw_id or _must_env("HW_ID")
@@ -221,7 +224,7 @@ def main() -> None:
         tx, gas, st = _send_and_measure(
             f"batch_{n}",
             [
-                contract,
+                lens,
                 sig,
                 packed_hex,
                 "--rpc-url",
@@ -0,0 +1,1025 @@
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(feature = "export-abi")), no_std)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use anchor_interface::receipt_codec::{self, RECEIPT_VERSION};
+use anchor_interface::{eip712, IHardwareAnchor};
+use stylus_sdk::abi::Bytes;
+use stylus_sdk::alloy_primitives::{address, hex, keccak256, Address, FixedBytes, Uint};
+use stylus_sdk::call::{static_call, Call};
+use stylus_sdk::function_selector;
+use stylus_sdk::{block, evm, msg, prelude::*};
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod test_vm;
+
+// Deployed builds leave out std: a panic traps, which reverts the call.
+#[cfg(all(target_arch = "wasm32", not(feature = "export-abi")))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+type U64 = Uint<64, 1>;
+
+// RIP-7212 secp256r1 verifier: input is hash || r || s || x || y (160 bytes),
+// output is the 32-byte word 1 on success and empty otherwise.
+const P256_VERIFIER: Address = address!("0000000000000000000000000000000000000100");
+
+// A valid P-256 signature in the verifier's input layout, over
+// sha256("AnchorGateway.p256-probe"). A chain without the precompile
+// answers every call with empty output, just as the verifier answers a bad
+// signature; when the caller's signature fails, this one is tried to tell
+// the two apart.
+const P256_PROBE: [u8; 160] = hex!(
+    "c5a127e93278292108034ea0d53fa7fb6fa40a268e3a3efd9c45b1d4a9d9edbb"
+    "6855352b3655de5afa51854bb48a0374345db37712f13e49532781827f4b0f56"
+    "5322190b88ae2e046268b1976265349efff40a7e133aedc9ad526e828e37c845"
+    "1c912bf9ae0b3a35894a58baa521035fba5cdd61bea25a9e8769867d5d8e81e2"
+    "69c69106aa13cafe5926e081d226e1c225868396f151f66fa7dc6bc1da1c6de5"
+);
+
+// EIP-712 struct types for verify_receipt_delegated (see eip712 for the
+// domain). The node signer signs a Delegation; the session key signs a
+// SessionReceipt. Distinct type hashes keep either signature from passing
+// as the other.
+const DELEGATION_TYPE: &[u8] =
+    b"Delegation(bytes32 node_id,address session_key,uint64 expiry,uint64 chain_id)";
+const SESSION_RECEIPT_TYPE: &[u8] = b"SessionReceipt(bytes32 node_id,bytes32 digest)";
+
+// Status the anchor's submit_receipt returns for an accepted receipt.
+const STATUS_OK: u8 = 0;
+
+sol! {
+    error AlreadyInitialized();
+    error UnauthorizedCaller();
+    error InvalidOwner();
+    error P256Unsupported();
+    error P256KeyMismatch();
+    error InvalidP256Signature();
+    error InvalidSignature();
+    error NodeSignerNotSet();
+    error DelegationExpired();
+    error DelegationChainMismatch();
+    error MalformedReceipt();
+    error DigestMismatch();
+    error HashUnavailable();
+
+    event NodeMetaSet(uint64 seq, bytes32 indexed node_id, bytes32 model_id, bytes32 location_hash);
+    event NodePurged(uint64 seq, bytes32 indexed node_id);
+    event FirmwareSwept(uint64 seq, uint64 count);
+    event ConfigChanged(uint64 seq, bytes4 indexed selector, address indexed by);
+}
+
+sol_storage! {
+    /// Submission paths in front of a StylusHardwareAnchor that need more
+    /// than its five-field receipt: node-held P-256 and delegated
+    /// secp256k1 signatures, packed receipts and batches, plus the node
+    /// registry they rely on and firmware sweeps. Signed receipts are
+    /// anchored through the anchor's anchor_receipt, which needs this
+    /// gateway registered as a verifier; the rest go through its
+    /// verify_receipt (or submit_receipt). Either way its checks and
+    /// counters apply unchanged.
+    #[entrypoint]
+    pub struct AnchorGateway {
+        // The anchor receipts are forwarded to.
+        address anchor;
+        // Manages the node registry below.
+        address owner;
+        mapping(bytes32 => P256Key) p256_keys;
+        // Long-term secp256k1 signer of each node, which delegates to
+        // session keys for verify_receipt_delegated (zero = none).
+        mapping(bytes32 => address) node_signers;
+        // Asset metadata for indexers; not consulted by verification.
+        mapping(bytes32 => NodeMeta) node_meta;
+        // Sequence number of the last emitted event; every event carries
+        // the next one as its first field.
+        uint64 event_seq;
+    }
+
+    pub struct P256Key {
+        bytes32 x;
+        bytes32 y;
+    }
+
+    pub struct NodeMeta {
+        bytes32 model_id;
+        bytes32 location_hash;
+    }
+}
+
+#[derive(SolidityError)]
+pub enum GatewayError {
+    AlreadyInitialized(AlreadyInitialized),
+    UnauthorizedCaller(UnauthorizedCaller),
+    InvalidOwner(InvalidOwner),
+    P256Unsupported(P256Unsupported),
+    P256KeyMismatch(P256KeyMismatch),
+    InvalidP256Signature(InvalidP256Signature),
+    InvalidSignature(InvalidSignature),
+    NodeSignerNotSet(NodeSignerNotSet),
+    DelegationExpired(DelegationExpired),
+    DelegationChainMismatch(DelegationChainMismatch),
+    MalformedReceipt(MalformedReceipt),
+    DigestMismatch(DigestMismatch),
+    HashUnavailable(HashUnavailable),
+}
+
+/// Whether the RIP-7212 verifier accepts `input`, failing with
+/// P256Unsupported when the chain has no verifier at all.
+fn p256_verify(input: &[u8; 160]) -> Result<bool, GatewayError> {
+    let accepted = |input: &[u8]| {
+        static_call(Call::new(), P256_VERIFIER, input)
+            .map(|output| output.len() == 32 && output[31] == 1 && output[..31] == [0; 31])
+            .map_err(|_| GatewayError::P256Unsupported(P256Unsupported {}))
+    };
+    if accepted(input)? {
+        return Ok(true);
+    }
+    if !accepted(&P256_PROBE)? {
+        return Err(GatewayError::P256Unsupported(P256Unsupported {}));
+    }
+    Ok(false)
+}
+
+#[public]
+impl AnchorGateway {
+    /// One-time initialization: the sender becomes owner of a gateway in
+    /// front of `anchor`.
+    pub fn initialize(&mut self, anchor: Address) -> Result<(), GatewayError> {
+        if self.owner.get() != Address::ZERO {
+            return Err(GatewayError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        self.owner.set(msg::sender());
+        self.anchor.set(anchor);
+        Ok(())
+    }
+
+    /// verify_receipt for secure-element nodes that additionally sign the
+    /// digest with their registered P-256 key, checked via the RIP-7212
+    /// precompile. The signed digest must be the anchor's preview_digest
+    /// for the receipt (else DigestMismatch); the receipt is then anchored
+    /// through anchor_receipt, so it also passes for nodes the anchor
+    /// marks signature-required. On a chain without the precompile (or
+    /// with a reverting one) the call fails with P256Unsupported, so
+    /// callers can fall back to verify_receipt.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_receipt_p256(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+        pubkey_x: FixedBytes<32>,
+        pubkey_y: FixedBytes<32>,
+        r: FixedBytes<32>,
+        s: FixedBytes<32>,
+    ) -> Result<(), Vec<u8>> {
+        let key = self.p256_keys.get(hw_id);
+        if pubkey_x != key.x.get() || pubkey_y != key.y.get() || pubkey_x == FixedBytes::ZERO {
+            return Err(GatewayError::P256KeyMismatch(P256KeyMismatch {}).into());
+        }
+
+        let mut input = [0u8; 160];
+        input[0..32].copy_from_slice(claimed_digest.as_slice());
+        input[32..64].copy_from_slice(r.as_slice());
+        input[64..96].copy_from_slice(s.as_slice());
+        input[96..128].copy_from_slice(pubkey_x.as_slice());
+        input[128..160].copy_from_slice(pubkey_y.as_slice());
+        if !p256_verify(&input)? {
+            return Err(GatewayError::InvalidP256Signature(InvalidP256Signature {}).into());
+        }
+
+        self.anchor_signed(hw_id, fw_hash, exec_hash, counter, claimed_digest)
+    }
+
+    /// verify_receipt for receipts signed by a session key the node
+    /// delegated to. Both signatures are 65-byte r || s || v EIP-712
+    /// signatures under this gateway's domain:
+    ///
+    /// - `delegation_sig`, by the node's registered signer, over
+    ///   `Delegation(bytes32 node_id,address session_key,uint64 expiry,uint64 chain_id)`;
+    /// - `session_sig`, by `session_key`, over
+    ///   `SessionReceipt(bytes32 node_id,bytes32 digest)` with the
+    ///   claimed digest, which must be the anchor's preview_digest for the
+    ///   receipt (else DigestMismatch).
+    ///
+    /// The delegation lapses at `delegation_expiry` (a block timestamp)
+    /// and must name the current chain. As with verify_receipt_p256, the
+    /// receipt is anchored through anchor_receipt, so it also passes for
+    /// nodes the anchor marks signature-required.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_receipt_delegated(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+        session_key: Address,
+        delegation_expiry: u64,
+        delegation_chain_id: u64,
+        delegation_sig: Bytes,
+        session_sig: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        if delegation_chain_id != block::chainid() {
+            return Err(GatewayError::DelegationChainMismatch(DelegationChainMismatch {}).into());
+        }
+        if block::timestamp() >= delegation_expiry {
+            return Err(GatewayError::DelegationExpired(DelegationExpired {}).into());
+        }
+        let signer = self.node_signers.get(hw_id);
+        if signer == Address::ZERO {
+            return Err(GatewayError::NodeSignerNotSet(NodeSignerNotSet {}).into());
+        }
+
+        let mut delegation = [0u8; 160];
+        delegation[0..32].copy_from_slice(keccak256(DELEGATION_TYPE).as_slice());
+        delegation[32..64].copy_from_slice(hw_id.as_slice());
+        delegation[76..96].copy_from_slice(session_key.as_slice());
+        delegation[120..128].copy_from_slice(&delegation_expiry.to_be_bytes());
+        delegation[152..160].copy_from_slice(&delegation_chain_id.to_be_bytes());
+        let mut receipt = [0u8; 96];
+        receipt[0..32].copy_from_slice(keccak256(SESSION_RECEIPT_TYPE).as_slice());
+        receipt[32..64].copy_from_slice(hw_id.as_slice());
+        receipt[64..96].copy_from_slice(claimed_digest.as_slice());
+
+        if eip712::recover_signer(keccak256(delegation), &delegation_sig) != Some(signer)
+            || session_key == Address::ZERO
+            || eip712::recover_signer(keccak256(receipt), &session_sig) != Some(session_key)
+        {
+            return Err(GatewayError::InvalidSignature(InvalidSignature {}).into());
+        }
+
+        self.anchor_signed(hw_id, fw_hash, exec_hash, counter, claimed_digest)
+    }
+
+    /// verify_receipt taking the receipt as one buffer:
+    /// hw_id || fw_hash || exec_hash || counter_be8 || claimed_digest
+    /// (receipt_codec::RECEIPT_LEN bytes). Any other length reverts with
+    /// MalformedReceipt.
+    pub fn verify_receipt_packed(&mut self, receipt: Bytes) -> Result<(), Vec<u8>> {
+        let receipt = receipt_codec::decode_receipt(&receipt)
+            .map_err(|_| GatewayError::MalformedReceipt(MalformedReceipt {}))?;
+        self.forward(
+            receipt.hw_id,
+            receipt.fw_hash,
+            receipt.exec_hash,
+            receipt.counter,
+            receipt.claimed_digest,
+        )
+    }
+
+    /// Stateful verification of a packed batch (receipt_codec's packed
+    /// layout). With `atomic`, each receipt goes through the anchor's
+    /// verify_receipt and the first rejection reverts the whole batch with
+    /// the anchor's error; an unknown receipt version reverts with
+    /// MalformedReceipt. Otherwise each receipt goes through submit_receipt,
+    /// so only accepted receipts advance their counter, and the result
+    /// flags which were accepted. Receipts are applied in order, so a node
+    /// may appear several times with increasing counters. A blob that is
+    /// not a whole number of receipts reverts in either mode.
+    pub fn verify_receipts(&mut self, packed: Bytes, atomic: bool) -> Result<Vec<bool>, Vec<u8>> {
+        let receipts = receipt_codec::decode_packed(&packed)
+            .map_err(|_| GatewayError::MalformedReceipt(MalformedReceipt {}))?;
+
+        let anchor = IHardwareAnchor::new(self.anchor.get());
+        let mut accepted = Vec::with_capacity(receipts.len());
+        for r in &receipts {
+            if r.version != RECEIPT_VERSION {
+                if atomic {
+                    return Err(GatewayError::MalformedReceipt(MalformedReceipt {}).into());
+                }
+                accepted.push(false);
+            } else if atomic {
+                self.forward(r.hw_id, r.fw_hash, r.exec_hash, r.counter, r.claimed_digest)?;
+                accepted.push(true);
+            } else {
+                let status = anchor.submit_receipt(
+                    Call::new_in(self),
+                    r.hw_id,
+                    r.fw_hash,
+                    r.exec_hash,
+                    r.counter,
+                    r.claimed_digest,
+                )?;
+                accepted.push(status == STATUS_OK);
+            }
+        }
+        Ok(accepted)
+    }
+
+    /// Purge every candidate whose approval has expired through the
+    /// anchor's purge_firmware, which anyone may call for expired hashes.
+    /// The anchor's map cannot be enumerated on-chain, so candidates come
+    /// from the caller's off-chain index; unexpired or unknown hashes are
+    /// skipped. Returns (and logs in FirmwareSwept) the number actually
+    /// purged.
+    pub fn sweep_firmware(&mut self, fw_hashes: Vec<FixedBytes<32>>) -> u64 {
+        let anchor = IHardwareAnchor::new(self.anchor.get());
+        let mut count = 0u64;
+        for fw_hash in fw_hashes {
+            if anchor.purge_firmware(Call::new_in(self), fw_hash).is_ok() {
+                count += 1;
+            }
+        }
+        evm::log(FirmwareSwept {
+            seq: self.next_event_seq(),
+            count,
+        });
+        count
+    }
+
+    pub fn register_p256_key(
+        &mut self,
+        node_id: FixedBytes<32>,
+        pubkey_x: FixedBytes<32>,
+        pubkey_y: FixedBytes<32>,
+    ) -> Result<(), GatewayError> {
+        self.only_owner()?;
+        let mut key = self.p256_keys.setter(node_id);
+        key.x.set(pubkey_x);
+        key.y.set(pubkey_y);
+        self.config_changed(function_selector!(
+            "registerP256Key",
+            FixedBytes<32>,
+            FixedBytes<32>,
+            FixedBytes<32>,
+        ));
+        Ok(())
+    }
+
+    /// Register the node's long-term secp256k1 signer for
+    /// verify_receipt_delegated; the zero address unregisters it.
+    pub fn set_node_signer(
+        &mut self,
+        node_id: FixedBytes<32>,
+        signer: Address,
+    ) -> Result<(), GatewayError> {
+        self.only_owner()?;
+        self.node_signers.insert(node_id, signer);
+        self.config_changed(function_selector!("setNodeSigner", FixedBytes<32>, Address));
+        Ok(())
+    }
+
+    /// Attach asset metadata to a node: a model id and a hash of its
+    /// location. Verification ignores it; purge_node clears it.
+    pub fn set_node_meta(
+        &mut self,
+        node_id: FixedBytes<32>,
+        model_id: FixedBytes<32>,
+        location_hash: FixedBytes<32>,
+    ) -> Result<(), GatewayError> {
+        self.only_owner()?;
+        let mut meta = self.node_meta.setter(node_id);
+        meta.model_id.set(model_id);
+        meta.location_hash.set(location_hash);
+        evm::log(NodeMetaSet {
+            seq: self.next_event_seq(),
+            node_id,
+            model_id,
+            location_hash,
+        });
+        Ok(())
+    }
+
+    /// Zero every slot this gateway keeps for a node (P-256 key,
+    /// delegating signer and metadata), each earning the EIP-3529 refund.
+    /// The anchor's purge_node handles its authorization separately.
+    pub fn purge_node(&mut self, node_id: FixedBytes<32>) -> Result<(), GatewayError> {
+        self.only_owner()?;
+        let mut key = self.p256_keys.setter(node_id);
+        key.x.set(FixedBytes::ZERO);
+        key.y.set(FixedBytes::ZERO);
+        self.node_signers.delete(node_id);
+        let mut meta = self.node_meta.setter(node_id);
+        meta.model_id.set(FixedBytes::ZERO);
+        meta.location_hash.set(FixedBytes::ZERO);
+        evm::log(NodePurged {
+            seq: self.next_event_seq(),
+            node_id,
+        });
+        Ok(())
+    }
+
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), GatewayError> {
+        self.only_owner()?;
+        if new_owner == Address::ZERO {
+            return Err(GatewayError::InvalidOwner(InvalidOwner {}));
+        }
+        self.owner.set(new_owner);
+        self.config_changed(function_selector!("transferOwnership", Address));
+        Ok(())
+    }
+
+    pub fn get_anchor(&self) -> Address {
+        self.anchor.get()
+    }
+
+    pub fn get_owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    pub fn get_p256_key(&self, node_id: FixedBytes<32>) -> (FixedBytes<32>, FixedBytes<32>) {
+        let key = self.p256_keys.get(node_id);
+        (key.x.get(), key.y.get())
+    }
+
+    pub fn get_node_signer(&self, node_id: FixedBytes<32>) -> Address {
+        self.node_signers.get(node_id)
+    }
+
+    /// (model_id, location_hash) set by set_node_meta; zero if unset.
+    pub fn get_node_meta(&self, node_id: FixedBytes<32>) -> (FixedBytes<32>, FixedBytes<32>) {
+        let meta = self.node_meta.get(node_id);
+        (meta.model_id.get(), meta.location_hash.get())
+    }
+
+    /// EIP-712 domain separator delegations must be signed under.
+    pub fn get_delegation_domain_separator(&self) -> FixedBytes<32> {
+        eip712::domain_separator()
+    }
+
+    /// Sequence number of the last event emitted (0 = none yet).
+    pub fn get_event_seq(&self) -> u64 {
+        self.event_seq.get().to()
+    }
+}
+
+impl AnchorGateway {
+    fn only_owner(&self) -> Result<(), GatewayError> {
+        if msg::sender() != self.owner.get() {
+            return Err(GatewayError::UnauthorizedCaller(UnauthorizedCaller {}));
+        }
+        Ok(())
+    }
+
+    /// Anchor a receipt through the anchor's verify_receipt; its revert
+    /// data (e.g. ReplayDetected) is bubbled up unchanged.
+    fn forward(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<(), Vec<u8>> {
+        IHardwareAnchor::new(self.anchor.get()).verify_receipt(
+            Call::new_in(self),
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+            claimed_digest,
+        )?;
+        Ok(())
+    }
+
+    /// Anchor a receipt whose digest the node's signature covers, as a
+    /// verifier: the digest must be the one the anchor reconstructs for
+    /// the receipt, which anchor_receipt does not check itself. The
+    /// anchor's revert data is bubbled up unchanged.
+    fn anchor_signed(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<(), Vec<u8>> {
+        let anchor = IHardwareAnchor::new(self.anchor.get());
+        let digest = anchor.preview_digest(Call::new(), hw_id, fw_hash, exec_hash, counter)?;
+        // The anchor answers zero when its sha256 precompile is missing.
+        if digest == FixedBytes::ZERO {
+            return Err(GatewayError::HashUnavailable(HashUnavailable {}).into());
+        }
+        if digest != claimed_digest {
+            return Err(GatewayError::DigestMismatch(DigestMismatch {}).into());
+        }
+        anchor.anchor_receipt(
+            Call::new_in(self),
+            hw_id,
+            FixedBytes::ZERO,
+            fw_hash,
+            exec_hash,
+            counter,
+            digest,
+        )?;
+        Ok(())
+    }
+
+    fn next_event_seq(&mut self) -> u64 {
+        let seq = self.event_seq.get() + U64::from(1);
+        self.event_seq.set(seq);
+        seq.to()
+    }
+
+    /// Logs a mutation that has no dedicated event, keyed by the selector
+    /// of the public method that made it.
+    fn config_changed(&mut self, selector: [u8; 4]) {
+        evm::log(ConfigChanged {
+            seq: self.next_event_seq(),
+            selector: selector.into(),
+            by: msg::sender(),
+        });
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::{SolCall, SolError, SolEvent};
+    use anchor_interface::receipt_codec::{Receipt, PACKED_RECEIPT_LEN};
+    use test_vm::{CHAIN_ID, DEVICE_DIGEST, DEVICE_KEY, DEVICE_SIGNATURE, TIMESTAMP};
+
+    sol! {
+        function verifyReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external;
+        function submitReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external returns (uint8);
+        error UnauthorizedHardware();
+        error ReplayDetected();
+        function purgeFirmware(bytes32 fw_hash) external;
+        function previewDigest(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter) external view returns (bytes32);
+        function anchorReceipt(bytes32 hw_id, bytes32 namespace, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 digest) external;
+    }
+
+    const ANCHOR: Address = Address::repeat_byte(0xa0);
+    const HW_ID: FixedBytes<32> = FixedBytes::repeat_byte(1);
+    const FW_HASH: FixedBytes<32> = FixedBytes::repeat_byte(2);
+    const EXEC_HASH: FixedBytes<32> = FixedBytes::repeat_byte(3);
+
+    fn gateway() -> AnchorGateway {
+        let mut gateway: AnchorGateway = test_vm::contract();
+        assert!(gateway.initialize(ANCHOR).is_ok());
+        gateway
+    }
+
+    fn emitted<E: SolEvent>(event: E) -> (Vec<FixedBytes<32>>, Vec<u8>) {
+        let topics = event.encode_topics().into_iter().map(|t| t.0).collect();
+        (topics, event.encode_data())
+    }
+
+    /// The anchor call forwarding a receipt for HW_ID.
+    fn forwarded(counter: u64, claimed_digest: FixedBytes<32>) -> (Address, Vec<u8>) {
+        let call = verifyReceiptCall {
+            hw_id: HW_ID,
+            fw_hash: FW_HASH,
+            exec_hash: EXEC_HASH,
+            counter,
+            claimed_digest,
+        };
+        (ANCHOR, call.abi_encode())
+    }
+
+    /// The anchor call previewing the digest of HW_ID's receipt at
+    /// `counter`.
+    fn previewed(counter: u64) -> (Address, Vec<u8>) {
+        let call = previewDigestCall {
+            hw_id: HW_ID,
+            fw_hash: FW_HASH,
+            exec_hash: EXEC_HASH,
+            counter,
+        };
+        (ANCHOR, call.abi_encode())
+    }
+
+    /// Answer the preview of HW_ID's receipt at `counter` with `digest`.
+    fn preview_as(counter: u64, digest: FixedBytes<32>) {
+        let returned = previewDigestCall::abi_encode_returns(&(digest,));
+        test_vm::respond_to(previewed(counter).1, true, returned);
+    }
+
+    /// The anchor call anchoring a signed receipt for HW_ID.
+    fn anchored(counter: u64, digest: FixedBytes<32>) -> (Address, Vec<u8>) {
+        let call = anchorReceiptCall {
+            hw_id: HW_ID,
+            namespace: FixedBytes::ZERO,
+            fw_hash: FW_HASH,
+            exec_hash: EXEC_HASH,
+            counter,
+            digest,
+        };
+        (ANCHOR, call.abi_encode())
+    }
+
+    #[test]
+    fn p256_receipts_need_the_registered_key_and_a_valid_signature() {
+        let mut gateway = gateway();
+        let ((x, y), (r, s)) = (DEVICE_KEY, DEVICE_SIGNATURE);
+        let verify = |gateway: &mut AnchorGateway, x, r, s| {
+            gateway.verify_receipt_p256(HW_ID, FW_HASH, EXEC_HASH, 1, DEVICE_DIGEST, x, y, r, s)
+        };
+
+        // Without a registered key nothing is forwarded.
+        let result = verify(&mut gateway, x, r, s);
+        assert_eq!(result, Err(P256KeyMismatch {}.abi_encode()));
+        assert!(gateway.register_p256_key(HW_ID, x, y).is_ok());
+        assert_eq!(gateway.get_p256_key(HW_ID), (x, y));
+        let result = verify(&mut gateway, FixedBytes::repeat_byte(9), r, s);
+        assert_eq!(result, Err(P256KeyMismatch {}.abi_encode()));
+
+        // A signature the verifier rejects is told apart from a missing
+        // verifier by the probe, and never reaches the anchor.
+        for (r, s) in [(s, r), (r, FixedBytes::repeat_byte(9))] {
+            let result = verify(&mut gateway, x, r, s);
+            assert_eq!(result, Err(InvalidP256Signature {}.abi_encode()));
+        }
+        assert!(test_vm::take_calls().is_empty());
+
+        // The signed digest is anchored as a verifier once the anchor
+        // previews the same digest for the receipt.
+        test_vm::respond_with(true, Vec::new());
+        preview_as(1, DEVICE_DIGEST);
+        assert_eq!(verify(&mut gateway, x, r, s), Ok(()));
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![previewed(1), anchored(1, DEVICE_DIGEST)]
+        );
+
+        // A signature over another receipt's digest is not anchored.
+        for (digest, error) in [
+            (FixedBytes::repeat_byte(9), DigestMismatch {}.abi_encode()),
+            (FixedBytes::ZERO, HashUnavailable {}.abi_encode()),
+        ] {
+            preview_as(1, digest);
+            assert_eq!(verify(&mut gateway, x, r, s), Err(error));
+            assert_eq!(test_vm::take_calls(), vec![previewed(1)]);
+        }
+
+        // The anchor's verdict on the receipt itself is bubbled up.
+        preview_as(1, DEVICE_DIGEST);
+        test_vm::respond_to(
+            anchored(1, DEVICE_DIGEST).1,
+            false,
+            ReplayDetected {}.abi_encode(),
+        );
+        let result = verify(&mut gateway, x, r, s);
+        assert_eq!(result, Err(ReplayDetected {}.abi_encode()));
+    }
+
+    #[test]
+    fn p256_receipts_without_a_verifier_are_unsupported() {
+        let mut gateway = gateway();
+        let ((x, y), (r, s)) = (DEVICE_KEY, DEVICE_SIGNATURE);
+        assert!(gateway.register_p256_key(HW_ID, x, y).is_ok());
+        test_vm::remove_p256_verifier();
+
+        // A valid signature and a bad one get the same empty answer from
+        // the empty account; both report the missing precompile.
+        for s in [s, FixedBytes::repeat_byte(9)] {
+            let result = gateway.verify_receipt_p256(
+                HW_ID,
+                FW_HASH,
+                EXEC_HASH,
+                1,
+                DEVICE_DIGEST,
+                x,
+                y,
+                r,
+                s,
+            );
+            assert_eq!(result, Err(P256Unsupported {}.abi_encode()));
+        }
+        assert!(test_vm::take_calls().is_empty());
+    }
+
+    #[test]
+    fn packed_receipts_round_trip_and_need_the_exact_length() {
+        let mut gateway = gateway();
+        let receipt = Receipt {
+            version: RECEIPT_VERSION,
+            hw_id: HW_ID,
+            fw_hash: FW_HASH,
+            exec_hash: EXEC_HASH,
+            counter: 7,
+            claimed_digest: FixedBytes::repeat_byte(0x44),
+        };
+        // A packed batch entry is the version byte, then the receipt.
+        let packed = receipt_codec::encode_packed(&[receipt]);
+
+        test_vm::respond_with(true, Vec::new());
+        assert_eq!(
+            gateway.verify_receipt_packed(Bytes(packed[1..].to_vec())),
+            Ok(())
+        );
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![forwarded(7, FixedBytes::repeat_byte(0x44))]
+        );
+
+        for len in [0, PACKED_RECEIPT_LEN - 2, PACKED_RECEIPT_LEN] {
+            let result = gateway.verify_receipt_packed(Bytes(packed[..len].to_vec()));
+            assert_eq!(result, Err(MalformedReceipt {}.abi_encode()));
+        }
+        assert!(test_vm::take_calls().is_empty());
+    }
+
+    #[test]
+    fn sweeps_purge_expired_firmware_and_keep_live_firmware() {
+        let mut gateway = gateway();
+        let expired = [FixedBytes::repeat_byte(0xe1), FixedBytes::repeat_byte(0xe2)];
+        let live = FixedBytes::repeat_byte(0x1e);
+        let purge = |fw_hash| purgeFirmwareCall { fw_hash }.abi_encode();
+        test_vm::take_logs();
+
+        // The anchor lets anyone purge an expired hash and refuses the
+        // gateway a live one.
+        test_vm::respond_with(true, Vec::new());
+        test_vm::respond_to(purge(live), false, UnauthorizedCaller {}.abi_encode());
+        assert_eq!(
+            gateway.sweep_firmware(vec![expired[0], live, expired[1]]),
+            2
+        );
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![
+                (ANCHOR, purge(expired[0])),
+                (ANCHOR, purge(live)),
+                (ANCHOR, purge(expired[1])),
+            ]
+        );
+        assert_eq!(
+            test_vm::take_logs(),
+            vec![emitted(FirmwareSwept { seq: 1, count: 2 })]
+        );
+
+        assert_eq!(gateway.sweep_firmware(vec![live]), 0);
+        assert_eq!(
+            test_vm::take_logs(),
+            vec![emitted(FirmwareSwept { seq: 2, count: 0 })]
+        );
+    }
+
+    #[test]
+    fn delegated_receipts_need_both_signatures() {
+        use k256::ecdsa::SigningKey;
+
+        fn address_of(key: &SigningKey) -> Address {
+            let point = key.verifying_key().to_encoded_point(false);
+            Address::from_slice(&keccak256(&point.as_bytes()[1..])[12..])
+        }
+        fn sign(key: &SigningKey, struct_hash: FixedBytes<32>) -> Bytes {
+            let mut message = [0u8; 66];
+            message[0..2].copy_from_slice(&[0x19, 0x01]);
+            message[2..34].copy_from_slice(eip712::domain_separator().as_slice());
+            message[34..66].copy_from_slice(struct_hash.as_slice());
+            let (sig, id) = key
+                .sign_prehash_recoverable(keccak256(message).as_slice())
+                .unwrap();
+            let mut out = sig.to_bytes().to_vec();
+            out.push(27 + id.to_byte());
+            Bytes::from(out)
+        }
+
+        let mut gateway = gateway();
+        let device = SigningKey::from_slice(&[0x0d; 32]).unwrap();
+        let session = SigningKey::from_slice(&[0x5e; 32]).unwrap();
+        let session_key = address_of(&session);
+        let expiry = TIMESTAMP + 60;
+        let digest = FixedBytes::<32>::repeat_byte(0x44);
+
+        let delegation = |chain_id: u64, expiry: u64| {
+            let mut fields = [0u8; 160];
+            fields[0..32].copy_from_slice(keccak256(DELEGATION_TYPE).as_slice());
+            fields[32..64].copy_from_slice(HW_ID.as_slice());
+            fields[76..96].copy_from_slice(session_key.as_slice());
+            fields[120..128].copy_from_slice(&expiry.to_be_bytes());
+            fields[152..160].copy_from_slice(&chain_id.to_be_bytes());
+            keccak256(fields)
+        };
+        let session_receipt = {
+            let mut fields = [0u8; 96];
+            fields[0..32].copy_from_slice(keccak256(SESSION_RECEIPT_TYPE).as_slice());
+            fields[32..64].copy_from_slice(HW_ID.as_slice());
+            fields[64..96].copy_from_slice(digest.as_slice());
+            keccak256(fields)
+        };
+        let delegation_sig = sign(&device, delegation(CHAIN_ID, expiry));
+        let session_sig = sign(&session, session_receipt);
+        let verify = |gateway: &mut AnchorGateway,
+                      expiry,
+                      chain_id,
+                      delegation_sig: &Bytes,
+                      session_sig: &Bytes| {
+            gateway.verify_receipt_delegated(
+                HW_ID,
+                FW_HASH,
+                EXEC_HASH,
+                1,
+                digest,
+                session_key,
+                expiry,
+                chain_id,
+                delegation_sig.clone(),
+                session_sig.clone(),
+            )
+        };
+
+        // Without a registered signer nothing verifies.
+        let result = verify(
+            &mut gateway,
+            expiry,
+            CHAIN_ID,
+            &delegation_sig,
+            &session_sig,
+        );
+        assert_eq!(result, Err(NodeSignerNotSet {}.abi_encode()));
+        assert!(gateway.set_node_signer(HW_ID, address_of(&device)).is_ok());
+        assert_eq!(gateway.get_node_signer(HW_ID), address_of(&device));
+
+        let result = verify(
+            &mut gateway,
+            TIMESTAMP,
+            CHAIN_ID,
+            &delegation_sig,
+            &session_sig,
+        );
+        assert_eq!(result, Err(DelegationExpired {}.abi_encode()));
+        let result = verify(
+            &mut gateway,
+            expiry,
+            CHAIN_ID + 1,
+            &delegation_sig,
+            &session_sig,
+        );
+        assert_eq!(result, Err(DelegationChainMismatch {}.abi_encode()));
+        // The session key cannot delegate to itself, the device's
+        // delegation does not pass as a receipt signature, and a delegation
+        // for another expiry does not verify.
+        for (delegation_sig, session_sig) in [
+            (
+                sign(&session, delegation(CHAIN_ID, expiry)),
+                session_sig.clone(),
+            ),
+            (delegation_sig.clone(), delegation_sig.clone()),
+            (
+                sign(&device, delegation(CHAIN_ID, expiry + 1)),
+                session_sig.clone(),
+            ),
+            (delegation_sig.clone(), sign(&device, session_receipt)),
+            (delegation_sig.clone(), Bytes::from(vec![0u8; 64])),
+        ] {
+            let result = verify(
+                &mut gateway,
+                expiry,
+                CHAIN_ID,
+                &delegation_sig,
+                &session_sig,
+            );
+            assert_eq!(result, Err(InvalidSignature {}.abi_encode()));
+        }
+        assert!(test_vm::take_calls().is_empty());
+
+        test_vm::respond_with(true, Vec::new());
+        preview_as(1, digest);
+        let result = verify(
+            &mut gateway,
+            expiry,
+            CHAIN_ID,
+            &delegation_sig,
+            &session_sig,
+        );
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![previewed(1), anchored(1, digest)]
+        );
+
+        // The session key signed a digest the receipt does not have.
+        preview_as(1, FixedBytes::repeat_byte(9));
+        let result = verify(
+            &mut gateway,
+            expiry,
+            CHAIN_ID,
+            &delegation_sig,
+            &session_sig,
+        );
+        assert_eq!(result, Err(DigestMismatch {}.abi_encode()));
+        assert_eq!(test_vm::take_calls(), vec![previewed(1)]);
+    }
+
+    #[test]
+    fn batch_verification_atomic_and_best_effort() {
+        let mut gateway = gateway();
+        let receipt = |counter| Receipt {
+            version: RECEIPT_VERSION,
+            hw_id: HW_ID,
+            fw_hash: FW_HASH,
+            exec_hash: EXEC_HASH,
+            counter,
+            claimed_digest: FixedBytes::repeat_byte(0x44),
+        };
+        let unknown = Receipt {
+            version: RECEIPT_VERSION + 1,
+            ..receipt(9)
+        };
+        let packed = |receipts: &[Receipt]| Bytes::from(receipt_codec::encode_packed(receipts));
+        let submitted = |counter| {
+            let call = submitReceiptCall {
+                hw_id: HW_ID,
+                fw_hash: FW_HASH,
+                exec_hash: EXEC_HASH,
+                counter,
+                claimed_digest: FixedBytes::repeat_byte(0x44),
+            };
+            (ANCHOR, call.abi_encode())
+        };
+
+        // Best effort: each known receipt is submitted and flagged by the
+        // anchor's status; unknown versions are skipped.
+        test_vm::respond_with(true, submitReceiptCall::abi_encode_returns(&(0,)));
+        let result = gateway.verify_receipts(packed(&[receipt(1), unknown, receipt(2)]), false);
+        assert_eq!(result, Ok(vec![true, false, true]));
+        assert_eq!(test_vm::take_calls(), vec![submitted(1), submitted(2)]);
+        // STATUS_REPLAY_DETECTED
+        test_vm::respond_with(true, submitReceiptCall::abi_encode_returns(&(3,)));
+        let result = gateway.verify_receipts(packed(&[receipt(1)]), false);
+        assert_eq!(result, Ok(vec![false]));
+
+        // Atomic: every receipt goes through verify_receipt, and the first
+        // rejection reverts with the anchor's error.
+        test_vm::take_calls();
+        test_vm::respond_with(true, Vec::new());
+        let result = gateway.verify_receipts(packed(&[receipt(3), receipt(4)]), true);
+        assert_eq!(result, Ok(vec![true, true]));
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![
+                forwarded(3, FixedBytes::repeat_byte(0x44)),
+                forwarded(4, FixedBytes::repeat_byte(0x44))
+            ]
+        );
+        test_vm::respond_with(false, UnauthorizedHardware {}.abi_encode());
+        let result = gateway.verify_receipts(packed(&[receipt(5)]), true);
+        assert_eq!(result, Err(UnauthorizedHardware {}.abi_encode()));
+        let result = gateway.verify_receipts(packed(&[unknown]), true);
+        assert_eq!(result, Err(MalformedReceipt {}.abi_encode()));
+
+        for atomic in [false, true] {
+            let truncated = Bytes::from(vec![0u8; PACKED_RECEIPT_LEN - 1]);
+            let result = gateway.verify_receipts(truncated, atomic);
+            assert_eq!(result, Err(MalformedReceipt {}.abi_encode()));
+        }
+    }
+
+    #[test]
+    fn node_registry_is_owner_managed_and_purged() {
+        let mut gateway = gateway();
+        let [model_id, location_hash] = [5u8, 6].map(FixedBytes::<32>::repeat_byte);
+        let signer = Address::repeat_byte(7);
+        assert!(gateway
+            .register_p256_key(
+                HW_ID,
+                FixedBytes::repeat_byte(5),
+                FixedBytes::repeat_byte(6)
+            )
+            .is_ok());
+        assert!(gateway.set_node_signer(HW_ID, signer).is_ok());
+        test_vm::take_logs();
+
+        assert!(gateway
+            .set_node_meta(HW_ID, model_id, location_hash)
+            .is_ok());
+        assert_eq!(gateway.get_node_meta(HW_ID), (model_id, location_hash));
+        assert_eq!(
+            test_vm::take_logs(),
+            vec![emitted(NodeMetaSet {
+                seq: 3,
+                node_id: HW_ID,
+                model_id,
+                location_hash,
+            })]
+        );
+
+        assert!(gateway.purge_node(HW_ID).is_ok());
+        let zero = (FixedBytes::ZERO, FixedBytes::ZERO);
+        assert_eq!(gateway.get_p256_key(HW_ID), zero);
+        assert_eq!(gateway.get_node_signer(HW_ID), Address::ZERO);
+        assert_eq!(gateway.get_node_meta(HW_ID), zero);
+        assert_eq!(
+            test_vm::take_logs(),
+            vec![emitted(NodePurged {
+                seq: 4,
+                node_id: HW_ID
+            })]
+        );
+
+        // Once ownership moves on, the registry is closed to the sender.
+        assert!(gateway.transfer_ownership(signer).is_ok());
+        assert_eq!(gateway.get_owner(), signer);
+        assert!(matches!(
+            gateway.set_node_meta(HW_ID, model_id, location_hash),
+            Err(GatewayError::UnauthorizedCaller(_))
+        ));
+        assert!(matches!(
+            gateway.purge_node(HW_ID),
+            Err(GatewayError::UnauthorizedCaller(_))
+        ));
+        assert!(matches!(
+            gateway.initialize(ANCHOR),
+            Err(GatewayError::AlreadyInitialized(_))
+        ));
+    }
+}
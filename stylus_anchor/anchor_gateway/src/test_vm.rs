@@ -0,0 +1,256 @@
+//! Native stand-ins for the hostios AnchorGateway uses, so unit tests can
+//! drive the gateway against in-memory storage.
+//!
+//! Each test thread has its own storage and log. Calls and static calls
+//! to the anchor are recorded and answered with the outcome set by
+//! `respond_to` for their calldata, or else by `respond_with`; static
+//! calls to the ecrecover precompile are served with k256, those to the
+//! P-256 verifier from a table of known signatures (see p256_verify), and
+//! delegate calls fail with no return data. The SDK caches
+//! msg::sender, block::timestamp and the other environment reads for the
+//! whole process, so every call comes from SENDER at a fixed block.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use stylus_sdk::alloy_primitives::{address, b256, keccak256, Address, B256, U256};
+use stylus_sdk::storage::StorageType;
+
+pub const SENDER: Address = address!("00000000000000000000000000000000000a11ce");
+pub const CONTRACT: Address = address!("000000000000000000000000000000000000c0de");
+pub const CHAIN_ID: u64 = 42161;
+pub const TIMESTAMP: u64 = 1_700_000_000;
+
+const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+const P256_VERIFIER: Address = address!("0000000000000000000000000000000000000100");
+
+/// A device's P-256 signature over DEVICE_DIGEST, produced and checked
+/// with pyca/cryptography.
+pub const DEVICE_DIGEST: B256 = B256::repeat_byte(0x44);
+pub const DEVICE_KEY: (B256, B256) = (
+    b256!("c54d5f4084598166f67919714a317f276ca04b68d348fa37c337ad8c52335b94"),
+    b256!("7807ca483400342dc5a94acc16eeaf2c8895729eae79f3b59e0aca7c834b23f3"),
+);
+pub const DEVICE_SIGNATURE: (B256, B256) = (
+    b256!("94c50484983435d0be0e43b97f001034c1e5e66034b854e39e11576fbf6a5032"),
+    b256!("4c60d5a247303a706081cafa67eb2ff99cdf80d5af578fa07576ae78bfd5d28f"),
+);
+
+/// An emitted log: topics, then ABI-encoded data.
+pub type Log = (Vec<B256>, Vec<u8>);
+
+/// A canned call outcome: success, then return or revert data.
+type Response = (bool, Vec<u8>);
+
+thread_local! {
+    static STORAGE: RefCell<HashMap<B256, B256>> = RefCell::new(HashMap::new());
+    static LOGS: RefCell<Vec<Log>> = const { RefCell::new(Vec::new()) };
+    static CALLS: RefCell<Vec<(Address, Vec<u8>)>> = const { RefCell::new(Vec::new()) };
+    static RESPONSE: RefCell<Response> = const { RefCell::new((true, Vec::new())) };
+    static RESPONSES: RefCell<HashMap<Vec<u8>, Response>> = RefCell::new(HashMap::new());
+    static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static P256_DEPLOYED: RefCell<bool> = const { RefCell::new(true) };
+}
+
+/// A contract rooted at slot zero of this thread's storage.
+pub fn contract<T: StorageType>() -> T {
+    unsafe { T::new(U256::ZERO, 0) }
+}
+
+/// Logs emitted on this thread since the last call.
+pub fn take_logs() -> Vec<Log> {
+    LOGS.with(|logs| logs.take())
+}
+
+/// External calls made on this thread since the last call: target, then
+/// calldata.
+pub fn take_calls() -> Vec<(Address, Vec<u8>)> {
+    CALLS.with(|calls| calls.take())
+}
+
+/// Answer later external calls with `data`, as return data when `success`
+/// and as revert data otherwise.
+pub fn respond_with(success: bool, data: Vec<u8>) {
+    RESPONSE.with(|response| *response.borrow_mut() = (success, data));
+}
+
+/// Remove the P-256 verifier from this thread's chain: calls to its
+/// address then succeed with empty output, like any call to an empty
+/// account.
+pub fn remove_p256_verifier() {
+    P256_DEPLOYED.with(|deployed| *deployed.borrow_mut() = false);
+}
+
+/// Answer later external calls with exactly `calldata` with `data`,
+/// overriding respond_with for them.
+pub fn respond_to(calldata: Vec<u8>, success: bool, data: Vec<u8>) {
+    RESPONSES.with(|responses| responses.borrow_mut().insert(calldata, (success, data)));
+}
+
+unsafe fn read<const N: usize>(ptr: *const u8) -> [u8; N] {
+    let mut out = [0u8; N];
+    std::ptr::copy_nonoverlapping(ptr, out.as_mut_ptr(), N);
+    out
+}
+
+unsafe fn write(ptr: *mut u8, bytes: &[u8]) {
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+}
+
+#[no_mangle]
+unsafe extern "C" fn storage_load_bytes32(key: *const u8, dest: *mut u8) {
+    let key = B256::from(read::<32>(key));
+    let value = STORAGE.with(|s| s.borrow().get(&key).copied().unwrap_or_default());
+    write(dest, value.as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn storage_cache_bytes32(key: *const u8, value: *const u8) {
+    let (key, value) = (B256::from(read::<32>(key)), B256::from(read::<32>(value)));
+    STORAGE.with(|s| s.borrow_mut().insert(key, value));
+}
+
+#[no_mangle]
+extern "C" fn storage_flush_cache(_clear: bool) {}
+
+#[no_mangle]
+unsafe extern "C" fn emit_log(data: *const u8, len: usize, topics: usize) {
+    let bytes = std::slice::from_raw_parts(data, len);
+    let (topic_bytes, data) = bytes.split_at(topics * 32);
+    let topics = topic_bytes.chunks(32).map(B256::from_slice).collect();
+    LOGS.with(|logs| logs.borrow_mut().push((topics, data.to_vec())));
+}
+
+#[no_mangle]
+unsafe extern "C" fn msg_sender(sender: *mut u8) {
+    write(sender, SENDER.as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn contract_address(address: *mut u8) {
+    write(address, CONTRACT.as_slice());
+}
+
+#[no_mangle]
+extern "C" fn chainid() -> u64 {
+    CHAIN_ID
+}
+
+#[no_mangle]
+extern "C" fn block_timestamp() -> u64 {
+    TIMESTAMP
+}
+
+#[no_mangle]
+unsafe extern "C" fn call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    _value: *const u8,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    let target = Address::from(read::<20>(contract));
+    let calldata = std::slice::from_raw_parts(calldata, calldata_len).to_vec();
+    answer(target, calldata, return_data_len)
+}
+
+/// Record a call to the anchor and answer it from RESPONSES or RESPONSE.
+unsafe fn answer(target: Address, calldata: Vec<u8>, return_data_len: *mut usize) -> u8 {
+    let (success, output) = RESPONSES
+        .with(|responses| responses.borrow().get(&calldata).cloned())
+        .unwrap_or_else(|| RESPONSE.with(|response| response.borrow().clone()));
+    CALLS.with(|calls| calls.borrow_mut().push((target, calldata)));
+    *return_data_len = output.len();
+    RETURN_DATA.with(|data| *data.borrow_mut() = output);
+    u8::from(!success)
+}
+
+#[no_mangle]
+unsafe extern "C" fn delegate_call_contract(
+    _contract: *const u8,
+    _calldata: *const u8,
+    _calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    *return_data_len = 0;
+    1
+}
+
+/// The ecrecover precompile: hash || v || r || s in, the signer
+/// left-padded to 32 bytes out, nothing for an invalid signature.
+fn ecrecover(input: &[u8]) -> Vec<u8> {
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    let mut padded = [0u8; 128];
+    let len = input.len().min(128);
+    padded[..len].copy_from_slice(&input[..len]);
+    let v = padded[63];
+    if padded[32..63].iter().any(|b| *b != 0) || !(v == 27 || v == 28) {
+        return Vec::new();
+    }
+    let Ok(signature) = Signature::from_slice(&padded[64..128]) else {
+        return Vec::new();
+    };
+    let recovery_id = RecoveryId::from_byte(v - 27).unwrap();
+    let Ok(key) = VerifyingKey::recover_from_prehash(&padded[..32], &signature, recovery_id) else {
+        return Vec::new();
+    };
+    let point = key.to_encoded_point(false);
+    let mut output = vec![0u8; 32];
+    output[12..].copy_from_slice(&keccak256(&point.as_bytes()[1..])[12..]);
+    output
+}
+
+/// The RIP-7212 verifier, without the curve arithmetic: it accepts the
+/// gateway's probe and the device signature, in its hash || r || s || x
+/// || y layout, and answers everything else with empty output.
+fn p256_verify(input: &[u8]) -> Vec<u8> {
+    let mut device = [0u8; 160];
+    device[0..32].copy_from_slice(DEVICE_DIGEST.as_slice());
+    device[32..64].copy_from_slice(DEVICE_SIGNATURE.0.as_slice());
+    device[64..96].copy_from_slice(DEVICE_SIGNATURE.1.as_slice());
+    device[96..128].copy_from_slice(DEVICE_KEY.0.as_slice());
+    device[128..160].copy_from_slice(DEVICE_KEY.1.as_slice());
+
+    let deployed = P256_DEPLOYED.with(|deployed| *deployed.borrow());
+    if deployed && (input == crate::P256_PROBE || input == device) {
+        return U256::from(1).to_be_bytes::<32>().to_vec();
+    }
+    Vec::new()
+}
+
+#[no_mangle]
+unsafe extern "C" fn static_call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    let input = std::slice::from_raw_parts(calldata, calldata_len);
+    let output = match Address::from(read::<20>(contract)) {
+        ECRECOVER => ecrecover(input),
+        P256_VERIFIER => p256_verify(input),
+        target => return answer(target, input.to_vec(), return_data_len),
+    };
+    *return_data_len = output.len();
+    RETURN_DATA.with(|data| *data.borrow_mut() = output);
+    0
+}
+
+#[no_mangle]
+unsafe extern "C" fn read_return_data(dest: *mut u8, offset: usize, size: usize) -> usize {
+    RETURN_DATA.with(|data| {
+        let data = data.borrow();
+        let start = offset.min(data.len());
+        let end = offset.saturating_add(size).min(data.len());
+        write(dest, &data[start..end]);
+        end - start
+    })
+}
+
+#[no_mangle]
+extern "C" fn return_data_size() -> usize {
+    RETURN_DATA.with(|data| data.borrow().len())
+}
@@ -0,0 +1,1159 @@
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(feature = "export-abi")), no_std)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, sol_data, SolType};
+use anchor_interface::{eip712, IHardwareAnchor};
+use stylus_sdk::abi::Bytes;
+use stylus_sdk::alloy_primitives::{keccak256, Address, FixedBytes, Uint, U256};
+use stylus_sdk::call::{call, Call};
+use stylus_sdk::function_selector;
+use stylus_sdk::{block, evm, msg, prelude::*};
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod test_vm;
+
+// Deployed builds leave out std: a panic traps, which reverts the call.
+#[cfg(all(target_arch = "wasm32", not(feature = "export-abi")))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+type U8 = Uint<8, 1>;
+type U64 = Uint<64, 1>;
+
+// Anchor calls that execute only relays through gate_action: handing the
+// anchor to another owner would bypass every check made here.
+const GATED_ANCHOR_SELECTORS: [[u8; 4]; 1] = [function_selector!("transferOwnership", Address)];
+
+// Most entries import_firmware_manifest takes in one call; each entry is
+// up to two anchor calls, so larger manifests are split by the caller.
+const MAX_FIRMWARE_MANIFEST: usize = 64;
+
+// EIP-712 struct type for permit_authorize_node (see eip712 for the domain).
+const AUTHORIZE_NODE_TYPE: &[u8] = b"AuthorizeNode(bytes32 node_id,uint256 nonce,uint256 deadline)";
+
+// Reserved ids selftest seeds on the anchor, as keccak256 of these tags.
+// Real ids are device keys and firmware image hashes, so they cannot land
+// on these.
+const SELFTEST_HW_ID_TAG: &[u8] = b"StylusHardwareAnchor.selftest.hw_id";
+const SELFTEST_FW_HASH_TAG: &[u8] = b"StylusHardwareAnchor.selftest.fw_hash";
+const SELFTEST_EXEC_HASH_TAG: &[u8] = b"StylusHardwareAnchor.selftest.exec_hash";
+
+// Status the anchor's check_receipt returns for an acceptable receipt.
+const STATUS_OK: u8 = 0;
+
+sol! {
+    error AlreadyInitialized();
+    error UnauthorizedCaller();
+    error InvalidOwner();
+    error NotAnOwner();
+    error InvalidThreshold();
+    error InvalidDeadline();
+    error ProposalNotFound();
+    error ProposalPending();
+    error ProposalExpired();
+    error AlreadyApproved();
+    error ThresholdNotMet();
+    error RecoveryNotAvailable();
+    error OwnerStillActive();
+    error PermitExpired();
+    error InvalidSignature();
+    error NoPendingApproval();
+    error ManifestTooLarge();
+    error MalformedCall();
+
+    event ActionProposed(uint64 seq, bytes32 indexed action_hash, address proposer, uint64 deadline);
+    event ActionApproved(uint64 seq, bytes32 indexed action_hash, address approver, uint8 approvals);
+    event ActionExecuted(uint64 seq, bytes32 indexed action_hash);
+    event OwnershipRecovered(uint64 seq, address indexed previous_owner, address indexed new_owner);
+    event FirmwareApprovalRequested(uint64 seq, bytes32 indexed fw_hash, bytes32 commitment, address requester);
+    event FirmwareApprovalFinalized(uint64 seq, bytes32 indexed fw_hash, bytes32 commitment);
+    event FirmwareManifestImported(uint64 seq, uint64 count);
+    event ConfigChanged(uint64 seq, bytes4 indexed selector, address indexed by);
+}
+
+sol_storage! {
+    /// Owner of a StylusHardwareAnchor. Relays the operator's admin calls
+    /// to the anchor and adds what a single owner key cannot provide:
+    /// M-of-N approval of ownership changes, break-glass recovery,
+    /// owner-signed permits and vendor firmware requests.
+    #[entrypoint]
+    pub struct AnchorGovernor {
+        // The anchor whose ownership this contract holds.
+        address anchor;
+        // Operator: relays ungated calls, signs permits and finalizes
+        // vendor requests.
+        address owner;
+        // M-of-N approval for gated actions (threshold 0 = disabled, gated
+        // actions fall back to the operator). Proposals are keyed by
+        // action hash, see gate_action; proposal ids below min_proposal_id
+        // predate the current owner set and are void.
+        address[] owners;
+        uint8 threshold;
+        mapping(bytes32 => Proposal) proposals;
+        mapping(bytes32 => mapping(address => uint64)) proposal_approvals;
+        uint64 proposal_count;
+        uint64 min_proposal_id;
+        // Break-glass recovery: recovery_owner may claim the operator role
+        // once the operator has been inactive (no gated or operator-only
+        // call) for recovery_delay seconds. A zero delay or recovery owner
+        // disables recovery.
+        address recovery_owner;
+        uint64 last_owner_activity;
+        uint64 recovery_delay;
+        // Nonce the next operator-signed AuthorizeNode permit must carry.
+        uint256 permit_nonce;
+        // Requester of each vendor-submitted commitment awaiting operator
+        // finalization, keyed by firmware_request_id(fw_hash, commitment).
+        mapping(bytes32 => address) pending_firmware_requests;
+        // Sequence number of the last emitted event; every event carries
+        // the next one as its first field.
+        uint64 event_seq;
+    }
+
+    pub struct Proposal {
+        uint64 id;
+        uint64 deadline;
+        uint8 approvals;
+        bool executed;
+    }
+}
+
+#[derive(SolidityError)]
+pub enum GovernorError {
+    AlreadyInitialized(AlreadyInitialized),
+    UnauthorizedCaller(UnauthorizedCaller),
+    InvalidOwner(InvalidOwner),
+    NotAnOwner(NotAnOwner),
+    InvalidThreshold(InvalidThreshold),
+    InvalidDeadline(InvalidDeadline),
+    ProposalNotFound(ProposalNotFound),
+    ProposalPending(ProposalPending),
+    ProposalExpired(ProposalExpired),
+    AlreadyApproved(AlreadyApproved),
+    ThresholdNotMet(ThresholdNotMet),
+    RecoveryNotAvailable(RecoveryNotAvailable),
+    OwnerStillActive(OwnerStillActive),
+    PermitExpired(PermitExpired),
+    InvalidSignature(InvalidSignature),
+    NoPendingApproval(NoPendingApproval),
+    ManifestTooLarge(ManifestTooLarge),
+    MalformedCall(MalformedCall),
+}
+
+/// Key of a pending firmware approval request:
+/// keccak256(fw_hash || commitment).
+fn firmware_request_id(fw_hash: FixedBytes<32>, commitment: FixedBytes<32>) -> FixedBytes<32> {
+    let mut key = [0u8; 64];
+    key[..32].copy_from_slice(fw_hash.as_slice());
+    key[32..].copy_from_slice(commitment.as_slice());
+    keccak256(key)
+}
+
+#[public]
+impl AnchorGovernor {
+    /// One-time initialization: the sender becomes operator of a governor
+    /// for `anchor`. Hand the anchor over with its transfer_ownership (or
+    /// initialize_with) afterwards.
+    pub fn initialize(&mut self, anchor: Address) -> Result<(), GovernorError> {
+        if self.owner.get() != Address::ZERO {
+            return Err(GovernorError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        self.owner.set(msg::sender());
+        self.anchor.set(anchor);
+        self.last_owner_activity.set(U64::from(block::timestamp()));
+        Ok(())
+    }
+
+    /// Relay `calldata` to the anchor as its owner and return its output;
+    /// the anchor's revert data is bubbled up unchanged. Operator-only,
+    /// except that the anchor's transferOwnership is a gated action (see
+    /// gate_action) whose action hash covers this execute call.
+    pub fn execute(&mut self, calldata: Bytes) -> Result<Bytes, Vec<u8>> {
+        let Some(selector) = calldata.get(..4) else {
+            return Err(GovernorError::MalformedCall(MalformedCall {}).into());
+        };
+        if GATED_ANCHOR_SELECTORS.iter().any(|gated| gated == selector) {
+            self.gate_action(
+                function_selector!("execute", Bytes),
+                <(sol_data::Bytes,)>::abi_encode_params(&(calldata.to_vec(),)),
+            )?;
+        } else {
+            self.only_owner()?;
+        }
+        let anchor = self.anchor.get();
+        let output = call(Call::new_in(self), anchor, &calldata)?;
+        Ok(Bytes(output))
+    }
+
+    /// authorize_node relayed by anyone holding an operator-signed EIP-712
+    /// `AuthorizeNode(bytes32 node_id,uint256 nonce,uint256 deadline)`
+    /// (see eip712 for the domain). The permit must carry the current
+    /// permit_nonce, which it consumes, and is void once the block
+    /// timestamp passes `deadline`. The anchor's NodeAuthorized names this
+    /// governor as the caller.
+    pub fn permit_authorize_node(
+        &mut self,
+        node_id: FixedBytes<32>,
+        deadline: U256,
+        v: u8,
+        r: FixedBytes<32>,
+        s: FixedBytes<32>,
+    ) -> Result<(), Vec<u8>> {
+        if U256::from(block::timestamp()) > deadline {
+            return Err(GovernorError::PermitExpired(PermitExpired {}).into());
+        }
+
+        let nonce = self.permit_nonce.get();
+        let mut fields = [0u8; 128];
+        fields[0..32].copy_from_slice(keccak256(AUTHORIZE_NODE_TYPE).as_slice());
+        fields[32..64].copy_from_slice(node_id.as_slice());
+        fields[64..96].copy_from_slice(&nonce.to_be_bytes::<32>());
+        fields[96..128].copy_from_slice(&deadline.to_be_bytes::<32>());
+
+        let mut signature = [0u8; 65];
+        signature[0..32].copy_from_slice(r.as_slice());
+        signature[32..64].copy_from_slice(s.as_slice());
+        signature[64] = v;
+        if eip712::recover_signer(keccak256(fields), &signature) != Some(self.owner.get()) {
+            return Err(GovernorError::InvalidSignature(InvalidSignature {}).into());
+        }
+
+        self.permit_nonce.set(nonce + U256::from(1));
+        IHardwareAnchor::new(self.anchor.get()).authorize_node(Call::new_in(self), node_id)?;
+        Ok(())
+    }
+
+    /// Step one of vendor self-registration: anyone may commit to a
+    /// firmware image with `commitment = keccak256(preimage)`. Requests
+    /// for the same hash with different commitments are held side by
+    /// side, so a later request cannot displace an earlier one; repeating
+    /// a pending commitment is a no-op that keeps the first requester.
+    pub fn request_firmware_approval(
+        &mut self,
+        fw_hash: FixedBytes<32>,
+        commitment: FixedBytes<32>,
+    ) {
+        let request_id = firmware_request_id(fw_hash, commitment);
+        if self.pending_firmware_requests.get(request_id) != Address::ZERO {
+            return;
+        }
+        self.pending_firmware_requests
+            .insert(request_id, msg::sender());
+        evm::log(FirmwareApprovalRequested {
+            seq: self.next_event_seq(),
+            fw_hash,
+            commitment,
+            requester: msg::sender(),
+        });
+    }
+
+    /// Step two: the operator approves the firmware on the anchor only
+    /// after checking the vendor-supplied preimage against a pending
+    /// commitment for the hash, failing with NoPendingApproval if none
+    /// matches. A frozen anchor refuses the approval and the request stays
+    /// pending.
+    pub fn finalize_firmware_approval(
+        &mut self,
+        fw_hash: FixedBytes<32>,
+        preimage: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        let commitment = keccak256(preimage.as_slice());
+        let request_id = firmware_request_id(fw_hash, commitment);
+        if self.pending_firmware_requests.get(request_id) == Address::ZERO {
+            return Err(GovernorError::NoPendingApproval(NoPendingApproval {}).into());
+        }
+        self.pending_firmware_requests.delete(request_id);
+        IHardwareAnchor::new(self.anchor.get()).approve_firmware(Call::new_in(self), fw_hash)?;
+        evm::log(FirmwareApprovalFinalized {
+            seq: self.next_event_seq(),
+            fw_hash,
+            commitment,
+        });
+        Ok(())
+    }
+
+    /// Approve a release manifest in one call: each (fw_hash, version,
+    /// expiry) entry is upserted on the anchor with
+    /// approve_firmware_versioned, then approve_firmware_until unless
+    /// `expiry` is 0 (never). The anchor logs each approval; this logs one
+    /// FirmwareManifestImported. Manifests over MAX_FIRMWARE_MANIFEST (64)
+    /// entries revert with ManifestTooLarge.
+    pub fn import_firmware_manifest(
+        &mut self,
+        entries: Vec<(FixedBytes<32>, u32, u64)>,
+    ) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if entries.len() > MAX_FIRMWARE_MANIFEST {
+            return Err(GovernorError::ManifestTooLarge(ManifestTooLarge {}).into());
+        }
+        let anchor = IHardwareAnchor::new(self.anchor.get());
+        for &(fw_hash, version, expiry) in &entries {
+            anchor.approve_firmware_versioned(Call::new_in(self), fw_hash, version)?;
+            if expiry != 0 {
+                anchor.approve_firmware_until(Call::new_in(self), fw_hash, expiry)?;
+            }
+        }
+        evm::log(FirmwareManifestImported {
+            seq: self.next_event_seq(),
+            count: entries.len() as u64,
+        });
+        Ok(())
+    }
+
+    /// Operator-only liveness check for block explorers: authorizes a
+    /// reserved node, firmware and exec hash on the anchor, has the anchor
+    /// check a receipt it builds for the node's next counter, then revokes
+    /// the reserved ids again. The anchor logs each of those calls, but
+    /// counters and the receipt root are not touched. Returns whether the
+    /// receipt verified under the anchor's current configuration (domain
+    /// tag, digest_algo); a node cap or firmware freeze that refuses the
+    /// reserved ids reverts with the anchor's error.
+    pub fn selftest(&mut self) -> Result<bool, Vec<u8>> {
+        self.only_owner()?;
+        let hw_id = keccak256(SELFTEST_HW_ID_TAG);
+        let fw_hash = keccak256(SELFTEST_FW_HASH_TAG);
+        let exec_hash = keccak256(SELFTEST_EXEC_HASH_TAG);
+
+        let anchor = IHardwareAnchor::new(self.anchor.get());
+        anchor.authorize_node(Call::new_in(self), hw_id)?;
+        anchor.approve_firmware(Call::new_in(self), fw_hash)?;
+        anchor.approve_exec(Call::new_in(self), exec_hash)?;
+
+        let counter = anchor.next_valid_counter(Call::new(), hw_id)?;
+        let digest = anchor.preview_digest(Call::new(), hw_id, fw_hash, exec_hash, counter)?;
+        let status =
+            anchor.check_receipt(Call::new(), hw_id, fw_hash, exec_hash, counter, digest)?;
+
+        anchor.revoke_node(Call::new_in(self), hw_id)?;
+        anchor.revoke_firmware(Call::new_in(self), fw_hash)?;
+        anchor.revoke_exec(Call::new_in(self), exec_hash)?;
+        Ok(status == STATUS_OK)
+    }
+
+    /// Hand the operator role to `new_owner`. Gated action: operator-only
+    /// until a threshold is set, then requires an approved proposal for
+    /// the action hash (see gate_action).
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), GovernorError> {
+        self.gate_action(
+            function_selector!("transferOwnership", Address),
+            <(sol_data::Address,)>::abi_encode_params(&(new_owner,)),
+        )?;
+        if new_owner == Address::ZERO {
+            return Err(GovernorError::InvalidOwner(InvalidOwner {}));
+        }
+        self.owner.set(new_owner);
+        self.last_owner_activity.set(U64::from(block::timestamp()));
+        self.config_changed(function_selector!("transferOwnership", Address));
+        Ok(())
+    }
+
+    /// Name the break-glass recovery owner (see
+    /// claim_ownership_after_inactivity). A gated action, so a single
+    /// operator cannot name themselves to sidestep the M-of-N threshold.
+    pub fn set_recovery_owner(&mut self, recovery_owner: Address) -> Result<(), GovernorError> {
+        let selector = function_selector!("setRecoveryOwner", Address);
+        self.gate_action(
+            selector,
+            <(sol_data::Address,)>::abi_encode_params(&(recovery_owner,)),
+        )?;
+        if recovery_owner == Address::ZERO {
+            return Err(GovernorError::InvalidOwner(InvalidOwner {}));
+        }
+        self.recovery_owner.set(recovery_owner);
+        self.config_changed(selector);
+        Ok(())
+    }
+
+    /// Seconds of operator inactivity after which the recovery owner may
+    /// claim the operator role. 0 disables recovery. Gated like
+    /// set_recovery_owner.
+    pub fn set_recovery_delay(&mut self, seconds: u64) -> Result<(), GovernorError> {
+        let selector = function_selector!("setRecoveryDelay", u64);
+        self.gate_action(
+            selector,
+            <(sol_data::Uint<64>,)>::abi_encode_params(&(seconds,)),
+        )?;
+        self.recovery_delay.set(U64::from(seconds));
+        self.config_changed(selector);
+        Ok(())
+    }
+
+    /// Recovery owner becomes operator once the operator has made no
+    /// operator-only or gated call (including an executed proposal) for
+    /// recovery_delay seconds. The recovery slot is cleared, so the new
+    /// operator must name a fresh recovery owner.
+    pub fn claim_ownership_after_inactivity(&mut self) -> Result<(), GovernorError> {
+        let recovery_owner = self.recovery_owner.get();
+        let delay: u64 = self.recovery_delay.get().to();
+        if recovery_owner == Address::ZERO || delay == 0 {
+            return Err(GovernorError::RecoveryNotAvailable(RecoveryNotAvailable {}));
+        }
+        if msg::sender() != recovery_owner {
+            return Err(GovernorError::UnauthorizedCaller(UnauthorizedCaller {}));
+        }
+        let last: u64 = self.last_owner_activity.get().to();
+        if block::timestamp() < last.saturating_add(delay) {
+            return Err(GovernorError::OwnerStillActive(OwnerStillActive {}));
+        }
+
+        let previous_owner = self.owner.get();
+        self.owner.set(recovery_owner);
+        self.recovery_owner.set(Address::ZERO);
+        self.last_owner_activity.set(U64::from(block::timestamp()));
+        evm::log(OwnershipRecovered {
+            seq: self.next_event_seq(),
+            previous_owner,
+            new_owner: recovery_owner,
+        });
+        Ok(())
+    }
+
+    /// Replace the approver set and threshold for gated actions; threshold
+    /// 0 disables M-of-N and clears the set. Itself a gated action. Pending
+    /// proposals are voided so approvals from the old set cannot carry over.
+    /// At most 255 owners, so a proposal's u8 approval count cannot wrap.
+    pub fn set_owners(&mut self, owners: Vec<Address>, threshold: u8) -> Result<(), GovernorError> {
+        self.gate_action(
+            function_selector!("setOwners", Vec<Address>, u8),
+            <(sol_data::Array<sol_data::Address>, sol_data::Uint<8>)>::abi_encode_params(&(
+                owners.clone(),
+                threshold,
+            )),
+        )?;
+        let distinct = owners
+            .iter()
+            .enumerate()
+            .all(|(i, o)| *o != Address::ZERO && !owners[..i].contains(o));
+        if !distinct || owners.len() > usize::from(u8::MAX) || usize::from(threshold) > owners.len()
+        {
+            return Err(GovernorError::InvalidThreshold(InvalidThreshold {}));
+        }
+
+        while self.owners.pop().is_some() {}
+        if threshold != 0 {
+            for o in owners {
+                self.owners.push(o);
+            }
+        }
+        self.threshold.set(U8::from(threshold));
+        self.min_proposal_id
+            .set(self.proposal_count.get() + U64::from(1));
+        self.config_changed(function_selector!("setOwners", Vec<Address>, u8));
+        Ok(())
+    }
+
+    /// Open a proposal for `action_hash`, counting the proposer's approval.
+    /// Replaces an earlier proposal for the same hash once it has expired,
+    /// executed or been voided.
+    pub fn propose(
+        &mut self,
+        action_hash: FixedBytes<32>,
+        deadline: u64,
+    ) -> Result<(), GovernorError> {
+        self.require_approver()?;
+        if deadline <= block::timestamp() {
+            return Err(GovernorError::InvalidDeadline(InvalidDeadline {}));
+        }
+        if self.open_proposal(action_hash).is_ok() {
+            return Err(GovernorError::ProposalPending(ProposalPending {}));
+        }
+
+        let id = self.proposal_count.get() + U64::from(1);
+        self.proposal_count.set(id);
+        let mut proposal = self.proposals.setter(action_hash);
+        proposal.id.set(id);
+        proposal.deadline.set(U64::from(deadline));
+        proposal.approvals.set(U8::ZERO);
+        proposal.executed.set(false);
+        evm::log(ActionProposed {
+            seq: self.next_event_seq(),
+            action_hash,
+            proposer: msg::sender(),
+            deadline,
+        });
+        self.approve(action_hash)
+    }
+
+    /// Add the caller's approval to the open proposal for `action_hash`.
+    pub fn approve(&mut self, action_hash: FixedBytes<32>) -> Result<(), GovernorError> {
+        self.require_approver()?;
+        let id = self.open_proposal(action_hash)?;
+        let approver = msg::sender();
+        if self.proposal_approvals.getter(action_hash).get(approver) == id {
+            return Err(GovernorError::AlreadyApproved(AlreadyApproved {}));
+        }
+        self.proposal_approvals
+            .setter(action_hash)
+            .insert(approver, id);
+
+        let mut proposal = self.proposals.setter(action_hash);
+        let approvals = proposal.approvals.get() + U8::from(1);
+        proposal.approvals.set(approvals);
+        evm::log(ActionApproved {
+            seq: self.next_event_seq(),
+            action_hash,
+            approver,
+            approvals: approvals.to(),
+        });
+        Ok(())
+    }
+
+    /// Hash a gated call must be proposed and approved under:
+    /// keccak256(selector || abi-encoded arguments), i.e. the keccak256 of
+    /// the calldata sent to this governor. For the anchor's
+    /// transferOwnership that is the execute call relaying it.
+    pub fn action_hash(&self, selector: FixedBytes<4>, args: Bytes) -> FixedBytes<32> {
+        Self::hash_action(selector.0, args.as_slice())
+    }
+
+    pub fn get_owners(&self) -> Vec<Address> {
+        (0..self.owners.len())
+            .filter_map(|i| self.owners.get(i))
+            .collect()
+    }
+
+    pub fn get_threshold(&self) -> u8 {
+        self.threshold.get().to()
+    }
+
+    /// (deadline, approvals, executed) of the live proposal for the hash;
+    /// zeros when there is none or it was voided by set_owners.
+    pub fn get_proposal(&self, action_hash: FixedBytes<32>) -> (u64, u8, bool) {
+        let proposal = self.proposals.get(action_hash);
+        if proposal.id.get() < self.min_proposal_id.get() {
+            return (0, 0, false);
+        }
+        (
+            proposal.deadline.get().to(),
+            proposal.approvals.get().to(),
+            proposal.executed.get(),
+        )
+    }
+
+    pub fn get_anchor(&self) -> Address {
+        self.anchor.get()
+    }
+
+    pub fn get_owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    pub fn get_recovery_owner(&self) -> Address {
+        self.recovery_owner.get()
+    }
+
+    pub fn get_recovery_delay(&self) -> u64 {
+        self.recovery_delay.get().to()
+    }
+
+    pub fn get_last_owner_activity(&self) -> u64 {
+        self.last_owner_activity.get().to()
+    }
+
+    pub fn get_permit_nonce(&self) -> U256 {
+        self.permit_nonce.get()
+    }
+
+    /// EIP-712 domain separator permits must be signed under.
+    pub fn get_permit_domain_separator(&self) -> FixedBytes<32> {
+        eip712::domain_separator()
+    }
+
+    /// Account that requested approval of `fw_hash` under `commitment`
+    /// (zero = no such pending request).
+    pub fn get_pending_firmware_requester(
+        &self,
+        fw_hash: FixedBytes<32>,
+        commitment: FixedBytes<32>,
+    ) -> Address {
+        self.pending_firmware_requests
+            .get(firmware_request_id(fw_hash, commitment))
+    }
+
+    /// Sequence number of the last event emitted (0 = none yet).
+    pub fn get_event_seq(&self) -> u64 {
+        self.event_seq.get().to()
+    }
+}
+
+impl AnchorGovernor {
+    /// Operator check for relayed and admin calls; also records the
+    /// operator as active for the recovery dead-man timer.
+    fn only_owner(&mut self) -> Result<(), GovernorError> {
+        if msg::sender() != self.owner.get() {
+            return Err(GovernorError::UnauthorizedCaller(UnauthorizedCaller {}));
+        }
+        self.last_owner_activity.set(U64::from(block::timestamp()));
+        Ok(())
+    }
+
+    /// Authorize a gated action. With no threshold set this is the usual
+    /// operator check. Otherwise the call must match a live proposal for
+    /// hash_action(selector, args) holding at least `threshold` approvals;
+    /// any caller may then execute it, once. Either way the action counts
+    /// as operator activity for the recovery timer. New gated actions only
+    /// need to call this with their own selector and arguments.
+    fn gate_action(&mut self, selector: [u8; 4], args: Vec<u8>) -> Result<(), GovernorError> {
+        let threshold = self.threshold.get();
+        if threshold == U8::ZERO {
+            return self.only_owner();
+        }
+
+        let action_hash = Self::hash_action(selector, &args);
+        self.open_proposal(action_hash)?;
+        let mut proposal = self.proposals.setter(action_hash);
+        if proposal.approvals.get() < threshold {
+            return Err(GovernorError::ThresholdNotMet(ThresholdNotMet {}));
+        }
+        proposal.executed.set(true);
+        self.last_owner_activity.set(U64::from(block::timestamp()));
+        evm::log(ActionExecuted {
+            seq: self.next_event_seq(),
+            action_hash,
+        });
+        Ok(())
+    }
+
+    /// Id of the unexpired, unexecuted proposal for the hash from the
+    /// current owner set.
+    fn open_proposal(&self, action_hash: FixedBytes<32>) -> Result<U64, GovernorError> {
+        let proposal = self.proposals.get(action_hash);
+        let id = proposal.id.get();
+        if id == U64::ZERO || id < self.min_proposal_id.get() || proposal.executed.get() {
+            return Err(GovernorError::ProposalNotFound(ProposalNotFound {}));
+        }
+        if block::timestamp() >= proposal.deadline.get().to::<u64>() {
+            return Err(GovernorError::ProposalExpired(ProposalExpired {}));
+        }
+        Ok(id)
+    }
+
+    fn require_approver(&self) -> Result<(), GovernorError> {
+        let sender = msg::sender();
+        if self.threshold.get() == U8::ZERO
+            || !(0..self.owners.len()).any(|i| self.owners.get(i) == Some(sender))
+        {
+            return Err(GovernorError::NotAnOwner(NotAnOwner {}));
+        }
+        Ok(())
+    }
+
+    fn hash_action(selector: [u8; 4], args: &[u8]) -> FixedBytes<32> {
+        let mut calldata = Vec::with_capacity(4 + args.len());
+        calldata.extend_from_slice(&selector);
+        calldata.extend_from_slice(args);
+        keccak256(calldata)
+    }
+
+    fn next_event_seq(&mut self) -> u64 {
+        let seq = self.event_seq.get() + U64::from(1);
+        self.event_seq.set(seq);
+        seq.to()
+    }
+
+    /// Logs a mutation that has no dedicated event, keyed by the selector
+    /// of the public method that made it.
+    fn config_changed(&mut self, selector: [u8; 4]) {
+        evm::log(ConfigChanged {
+            seq: self.next_event_seq(),
+            selector: selector.into(),
+            by: msg::sender(),
+        });
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::{eip712_domain, SolCall, SolError, SolEvent, SolStruct};
+    use anchor_interface::authorizeNodeCall;
+    use k256::ecdsa::SigningKey;
+    use stylus_sdk::alloy_primitives::fixed_bytes;
+    use test_vm::{CHAIN_ID, CONTRACT, SENDER, TIMESTAMP};
+
+    sol! {
+        struct AuthorizeNode {
+            bytes32 node_id;
+            uint256 nonce;
+            uint256 deadline;
+        }
+
+        function transferOwnership(address new_owner) external;
+        function revokeNode(bytes32 node_id) external;
+        function approveFirmware(bytes32 fw_hash) external;
+        function approveFirmwareVersioned(bytes32 fw_hash, uint32 version) external;
+        function approveFirmwareUntil(bytes32 fw_hash, uint64 expiry) external;
+        error FirmwareApprovalsFrozen();
+    }
+
+    const ANCHOR: Address = Address::repeat_byte(0xa0);
+
+    fn governor() -> AnchorGovernor {
+        let mut governor: AnchorGovernor = test_vm::contract();
+        assert!(governor.initialize(ANCHOR).is_ok());
+        governor
+    }
+
+    fn emitted<E: SolEvent>(event: E) -> (Vec<FixedBytes<32>>, Vec<u8>) {
+        let topics = event.encode_topics().into_iter().map(|t| t.0).collect();
+        (topics, event.encode_data())
+    }
+
+    #[test]
+    fn operator_calls_are_relayed_and_reverts_bubble_up() {
+        let mut governor = governor();
+        assert!(matches!(
+            governor.initialize(ANCHOR),
+            Err(GovernorError::AlreadyInitialized(_))
+        ));
+        let node_id = FixedBytes::<32>::repeat_byte(0x11);
+        let calldata = revokeNodeCall { node_id }.abi_encode();
+
+        test_vm::respond_with(true, vec![0xaa]);
+        let result = governor.execute(Bytes(calldata.clone()));
+        assert_eq!(result, Ok(Bytes(vec![0xaa])));
+        assert_eq!(test_vm::take_calls(), vec![(ANCHOR, calldata.clone())]);
+
+        test_vm::respond_with(false, FirmwareApprovalsFrozen {}.abi_encode());
+        let result = governor.execute(Bytes(calldata));
+        assert_eq!(result, Err(FirmwareApprovalsFrozen {}.abi_encode()));
+
+        let result = governor.execute(Bytes(vec![0x01, 0x02]));
+        assert_eq!(result, Err(MalformedCall {}.abi_encode()));
+        assert_eq!(test_vm::take_calls().len(), 1);
+    }
+
+    #[test]
+    fn anchor_ownership_transfers_need_the_owner_threshold() {
+        let mut governor = governor();
+        let co_owner = Address::repeat_byte(0xbb);
+        assert!(governor.set_owners(vec![SENDER, co_owner], 2).is_ok());
+        test_vm::respond_with(true, Vec::new());
+
+        // An operator-only relay cannot hand the anchor away.
+        let calldata = transferOwnershipCall {
+            new_owner: co_owner,
+        }
+        .abi_encode();
+        let result = governor.execute(Bytes(calldata.clone()));
+        assert_eq!(result, Err(ProposalNotFound {}.abi_encode()));
+        assert!(test_vm::take_calls().is_empty());
+
+        // The action hash is that of the execute call relaying it.
+        let args = <(sol_data::Bytes,)>::abi_encode_params(&(calldata.clone(),));
+        let action_hash = governor.action_hash(
+            FixedBytes(function_selector!("execute", Bytes)),
+            Bytes(args),
+        );
+        assert!(governor.propose(action_hash, TIMESTAMP + 60).is_ok());
+        let result = governor.execute(Bytes(calldata.clone()));
+        assert_eq!(result, Err(ThresholdNotMet {}.abi_encode()));
+
+        governor
+            .proposals
+            .setter(action_hash)
+            .approvals
+            .set(U8::from(2));
+        assert_eq!(
+            governor.execute(Bytes(calldata.clone())),
+            Ok(Bytes(Vec::new()))
+        );
+        assert_eq!(test_vm::take_calls(), vec![(ANCHOR, calldata.clone())]);
+        assert_eq!(
+            governor.get_proposal(action_hash),
+            (TIMESTAMP + 60, 2, true)
+        );
+
+        // Executed proposals are spent.
+        let result = governor.execute(Bytes(calldata));
+        assert_eq!(result, Err(ProposalNotFound {}.abi_encode()));
+    }
+
+    #[test]
+    fn later_firmware_requests_cannot_displace_pending_ones() {
+        let mut governor = governor();
+        let fw_hash = FixedBytes::<32>::repeat_byte(1);
+        let genuine = keccak256(b"firmware image");
+        let junk = keccak256(b"junk");
+
+        governor.request_firmware_approval(fw_hash, genuine);
+        governor.request_firmware_approval(fw_hash, junk);
+        for commitment in [genuine, junk] {
+            assert_eq!(
+                governor.get_pending_firmware_requester(fw_hash, commitment),
+                SENDER
+            );
+        }
+        test_vm::take_logs();
+        governor.request_firmware_approval(fw_hash, genuine);
+        assert!(test_vm::take_logs().is_empty());
+
+        let result = governor.finalize_firmware_approval(fw_hash, Bytes(b"other".to_vec()));
+        assert_eq!(result, Err(NoPendingApproval {}.abi_encode()));
+        assert!(test_vm::take_calls().is_empty());
+
+        test_vm::respond_with(true, Vec::new());
+        assert!(governor
+            .finalize_firmware_approval(fw_hash, Bytes(b"firmware image".to_vec()))
+            .is_ok());
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![(ANCHOR, approveFirmwareCall { fw_hash }.abi_encode())]
+        );
+        assert_eq!(
+            governor.get_pending_firmware_requester(fw_hash, genuine),
+            Address::ZERO
+        );
+        assert_eq!(
+            governor.get_pending_firmware_requester(fw_hash, junk),
+            SENDER
+        );
+    }
+
+    #[test]
+    fn firmware_manifests_are_upserted_entry_by_entry() {
+        let mut governor = governor();
+        let [old, fresh] = [1u8, 2].map(FixedBytes::<32>::repeat_byte);
+        test_vm::respond_with(true, Vec::new());
+        test_vm::take_logs();
+
+        let manifest = vec![(old, 1, TIMESTAMP + 3600), (fresh, 2, 0)];
+        assert!(governor.import_firmware_manifest(manifest).is_ok());
+        let expected = vec![
+            approveFirmwareVersionedCall {
+                fw_hash: old,
+                version: 1,
+            }
+            .abi_encode(),
+            approveFirmwareUntilCall {
+                fw_hash: old,
+                expiry: TIMESTAMP + 3600,
+            }
+            .abi_encode(),
+            approveFirmwareVersionedCall {
+                fw_hash: fresh,
+                version: 2,
+            }
+            .abi_encode(),
+        ];
+        let calls: Vec<_> = test_vm::take_calls().into_iter().map(|c| c.1).collect();
+        assert_eq!(calls, expected);
+        assert_eq!(
+            test_vm::take_logs(),
+            vec![emitted(FirmwareManifestImported { seq: 1, count: 2 })]
+        );
+
+        let oversized = vec![(fresh, 5, 0); MAX_FIRMWARE_MANIFEST + 1];
+        let result = governor.import_firmware_manifest(oversized);
+        assert_eq!(result, Err(ManifestTooLarge {}.abi_encode()));
+        assert!(test_vm::take_calls().is_empty());
+
+        test_vm::respond_with(false, FirmwareApprovalsFrozen {}.abi_encode());
+        let result = governor.import_firmware_manifest(vec![(fresh, 5, 0)]);
+        assert_eq!(result, Err(FirmwareApprovalsFrozen {}.abi_encode()));
+        assert!(test_vm::take_logs().is_empty());
+    }
+
+    #[test]
+    fn owner_set_is_capped_at_u8_max() {
+        let mut governor = governor();
+        let owners = |n: u16| -> Vec<Address> {
+            (1..=n)
+                .map(|i| Address::left_padding_from(&i.to_be_bytes()))
+                .collect()
+        };
+
+        assert!(matches!(
+            governor.set_owners(owners(256), 1),
+            Err(GovernorError::InvalidThreshold(_))
+        ));
+        assert!(governor.set_owners(owners(255), 1).is_ok());
+        assert_eq!(governor.get_owners().len(), 255);
+    }
+
+    #[test]
+    fn recovery_claims_only_once_the_owner_is_inactive_for_the_delay() {
+        let mut governor = governor();
+        let claim = |governor: &mut AnchorGovernor| governor.claim_ownership_after_inactivity();
+
+        // The test sender names itself recovery owner, then hands the
+        // operator role to an owner who goes quiet.
+        assert!(governor.set_recovery_owner(SENDER).is_ok());
+        assert!(matches!(
+            claim(&mut governor),
+            Err(GovernorError::RecoveryNotAvailable(_))
+        ));
+        assert!(governor.set_recovery_delay(3600).is_ok());
+        let absent = Address::repeat_byte(0xbb);
+        assert!(governor.transfer_ownership(absent).is_ok());
+
+        for last_active in [TIMESTAMP, TIMESTAMP - 3599] {
+            governor.last_owner_activity.set(U64::from(last_active));
+            assert!(matches!(
+                claim(&mut governor),
+                Err(GovernorError::OwnerStillActive(_))
+            ));
+        }
+        assert_eq!(governor.get_owner(), absent);
+
+        governor
+            .last_owner_activity
+            .set(U64::from(TIMESTAMP - 3600));
+        test_vm::take_logs();
+        assert!(claim(&mut governor).is_ok());
+        assert_eq!(governor.get_owner(), SENDER);
+        assert_eq!(governor.get_recovery_owner(), Address::ZERO);
+        let seq = governor.get_event_seq();
+        assert_eq!(
+            test_vm::take_logs(),
+            vec![emitted(OwnershipRecovered {
+                seq,
+                previous_owner: absent,
+                new_owner: SENDER,
+            })]
+        );
+    }
+
+    #[test]
+    fn recovery_cannot_sidestep_the_owner_threshold() {
+        let mut governor = governor();
+        let co_owner = Address::repeat_byte(0xbb);
+        assert!(governor.set_owners(vec![SENDER, co_owner], 2).is_ok());
+
+        let selector = function_selector!("setRecoveryOwner", Address);
+        let args = <(sol_data::Address,)>::abi_encode_params(&(SENDER,));
+        assert!(matches!(
+            governor.set_recovery_owner(SENDER),
+            Err(GovernorError::ProposalNotFound(_))
+        ));
+        let action_hash = AnchorGovernor::hash_action(selector, &args);
+        assert!(governor.propose(action_hash, TIMESTAMP + 60).is_ok());
+        assert!(matches!(
+            governor.set_recovery_owner(SENDER),
+            Err(GovernorError::ThresholdNotMet(_))
+        ));
+        assert!(matches!(
+            governor.set_recovery_delay(1),
+            Err(GovernorError::ProposalNotFound(_))
+        ));
+        assert_eq!(governor.get_recovery_owner(), Address::ZERO);
+        assert_eq!(governor.get_recovery_delay(), 0);
+
+        // With nothing configured there is no recovery path to take.
+        governor.last_owner_activity.set(U64::ZERO);
+        assert!(matches!(
+            governor.claim_ownership_after_inactivity(),
+            Err(GovernorError::RecoveryNotAvailable(_))
+        ));
+
+        // With the co-owner's approval counted, executing a proposal is
+        // owner activity, so recovery cannot take over an active multisig.
+        governor
+            .proposals
+            .setter(action_hash)
+            .approvals
+            .set(U8::from(2));
+        assert!(governor.set_recovery_owner(SENDER).is_ok());
+        assert_eq!(governor.get_last_owner_activity(), TIMESTAMP);
+
+        let delay_args = <(sol_data::Uint<64>,)>::abi_encode_params(&(60u64,));
+        let delay_hash =
+            AnchorGovernor::hash_action(function_selector!("setRecoveryDelay", u64), &delay_args);
+        assert!(governor.propose(delay_hash, TIMESTAMP + 60).is_ok());
+        governor
+            .proposals
+            .setter(delay_hash)
+            .approvals
+            .set(U8::from(2));
+        governor.last_owner_activity.set(U64::ZERO);
+        assert!(governor.set_recovery_delay(60).is_ok());
+        assert_eq!(governor.get_last_owner_activity(), TIMESTAMP);
+        assert!(matches!(
+            governor.claim_ownership_after_inactivity(),
+            Err(GovernorError::OwnerStillActive(_))
+        ));
+        assert_eq!(governor.get_owner(), SENDER);
+    }
+
+    #[test]
+    fn mutators_without_a_dedicated_event_still_consume_a_seq() {
+        let mut governor = governor();
+        test_vm::take_logs();
+        let by = SENDER;
+        assert!(governor.set_recovery_delay(60).is_ok());
+        assert!(governor.set_owners(Vec::new(), 0).is_ok());
+        assert_eq!(
+            test_vm::take_logs(),
+            vec![
+                emitted(ConfigChanged {
+                    seq: 1,
+                    selector: function_selector!("setRecoveryDelay", u64).into(),
+                    by,
+                }),
+                emitted(ConfigChanged {
+                    seq: 2,
+                    selector: function_selector!("setOwners", Vec<Address>, u8).into(),
+                    by,
+                }),
+            ]
+        );
+        assert_eq!(governor.get_event_seq(), 2);
+    }
+
+    fn signer_address(key: &SigningKey) -> Address {
+        let point = key.verifying_key().to_encoded_point(false);
+        Address::from_slice(&keccak256(&point.as_bytes()[1..])[12..])
+    }
+
+    /// Sign an AuthorizeNode permit with `key`, as (v, r, s).
+    fn sign_permit(
+        key: &SigningKey,
+        digest: FixedBytes<32>,
+    ) -> (u8, FixedBytes<32>, FixedBytes<32>) {
+        let (signature, recovery_id) = key.sign_prehash_recoverable(digest.as_slice()).unwrap();
+        let bytes = signature.to_bytes();
+        (
+            27 + recovery_id.to_byte(),
+            FixedBytes::from_slice(&bytes[..32]),
+            FixedBytes::from_slice(&bytes[32..]),
+        )
+    }
+
+    #[test]
+    fn permits_authorize_a_node_once_before_their_deadline() {
+        let mut governor = governor();
+        let operator = SigningKey::from_slice(&[0x42; 32]).unwrap();
+        governor.owner.set(signer_address(&operator));
+        let node_id = FixedBytes::<32>::repeat_byte(0x11);
+        let deadline = U256::from(TIMESTAMP + 3600);
+
+        let domain = eip712_domain! {
+            name: "StylusHardwareAnchor",
+            version: "1",
+            chain_id: CHAIN_ID,
+            verifying_contract: CONTRACT,
+        };
+        assert_eq!(governor.get_permit_domain_separator(), domain.separator());
+        let permit = AuthorizeNode {
+            node_id,
+            nonce: U256::ZERO,
+            deadline,
+        };
+        let digest = permit.eip712_signing_hash(&domain);
+        assert_eq!(
+            digest,
+            fixed_bytes!("a5b18515e7802ce7b2dd34688a4b38313f8e3aa0032d6e01011207514b4e1316")
+        );
+        let (v, r, s) = sign_permit(&operator, digest);
+
+        test_vm::respond_with(true, Vec::new());
+        assert_eq!(
+            governor.permit_authorize_node(node_id, deadline, v, r, s),
+            Ok(())
+        );
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![(ANCHOR, authorizeNodeCall { node_id }.abi_encode())]
+        );
+        assert_eq!(governor.get_permit_nonce(), U256::from(1));
+
+        // The nonce is consumed, so the same permit cannot be replayed.
+        assert_eq!(
+            governor.permit_authorize_node(node_id, deadline, v, r, s),
+            Err(InvalidSignature {}.abi_encode())
+        );
+
+        // A permit signed by anyone but the operator is rejected.
+        let stranger = SigningKey::from_slice(&[0x43; 32]).unwrap();
+        let permit = AuthorizeNode {
+            node_id,
+            nonce: U256::from(1),
+            deadline,
+        };
+        let (v, r, s) = sign_permit(&stranger, permit.eip712_signing_hash(&domain));
+        assert_eq!(
+            governor.permit_authorize_node(node_id, deadline, v, r, s),
+            Err(InvalidSignature {}.abi_encode())
+        );
+
+        // A correctly signed permit is void once its deadline has passed.
+        let deadline = U256::from(TIMESTAMP - 1);
+        let permit = AuthorizeNode {
+            node_id,
+            nonce: U256::from(1),
+            deadline,
+        };
+        let (v, r, s) = sign_permit(&operator, permit.eip712_signing_hash(&domain));
+        assert_eq!(
+            governor.permit_authorize_node(node_id, deadline, v, r, s),
+            Err(PermitExpired {}.abi_encode())
+        );
+        assert!(test_vm::take_calls().is_empty());
+        assert_eq!(governor.get_permit_nonce(), U256::from(1));
+    }
+
+    #[test]
+    fn selftest_checks_a_reserved_receipt_and_revokes_its_ids() {
+        use anchor_interface::{
+            approveExecCall, checkReceiptCall, nextValidCounterCall, previewDigestCall,
+            revokeExecCall, revokeFirmwareCall,
+        };
+
+        let mut governor = governor();
+        let selectors = |calls: Vec<(Address, Vec<u8>)>| {
+            calls
+                .into_iter()
+                .map(|(target, calldata)| {
+                    assert_eq!(target, ANCHOR);
+                    [calldata[0], calldata[1], calldata[2], calldata[3]]
+                })
+                .collect::<Vec<_>>()
+        };
+        let expected = vec![
+            authorizeNodeCall::SELECTOR,
+            approveFirmwareCall::SELECTOR,
+            approveExecCall::SELECTOR,
+            nextValidCounterCall::SELECTOR,
+            previewDigestCall::SELECTOR,
+            checkReceiptCall::SELECTOR,
+            revokeNodeCall::SELECTOR,
+            revokeFirmwareCall::SELECTOR,
+            revokeExecCall::SELECTOR,
+        ];
+
+        // The mock anchor answers every call with the same word: here
+        // counter 0, a zero digest and status 0.
+        test_vm::respond_with(true, U256::ZERO.to_be_bytes_vec());
+        assert_eq!(governor.selftest(), Ok(true));
+        let calls = test_vm::take_calls();
+        let hw_id = keccak256(SELFTEST_HW_ID_TAG);
+        assert_eq!(
+            calls[0].1,
+            authorizeNodeCall { node_id: hw_id }.abi_encode()
+        );
+        assert_eq!(selectors(calls), expected);
+
+        // Any other status fails the test; the reserved ids are still
+        // revoked.
+        test_vm::respond_with(true, U256::from(4).to_be_bytes_vec());
+        assert_eq!(governor.selftest(), Ok(false));
+        assert_eq!(selectors(test_vm::take_calls()), expected);
+
+        // A refused approval reverts with the anchor's error.
+        test_vm::respond_with(false, FirmwareApprovalsFrozen {}.abi_encode());
+        assert_eq!(
+            governor.selftest(),
+            Err(FirmwareApprovalsFrozen {}.abi_encode())
+        );
+        test_vm::take_calls();
+
+        governor.owner.set(Address::repeat_byte(0xbb));
+        assert_eq!(governor.selftest(), Err(UnauthorizedCaller {}.abi_encode()));
+        assert!(test_vm::take_calls().is_empty());
+    }
+}
@@ -0,0 +1,50 @@
+//! EIP-712 signatures checked by the contracts around an anchor
+//! (AnchorGovernor permits, AnchorGateway delegations), all under domain
+//! name "StylusHardwareAnchor", version "1", the current chain and the
+//! verifying contract's own address.
+
+use stylus_sdk::alloy_primitives::{address, keccak256, Address, FixedBytes, U256};
+use stylus_sdk::block;
+use stylus_sdk::call::{static_call, Call};
+use stylus_sdk::contract;
+
+const DOMAIN_TYPE: &[u8] =
+    b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const NAME: &[u8] = b"StylusHardwareAnchor";
+const VERSION: &[u8] = b"1";
+
+// ecrecover precompile: input is hash || v || r || s (each a 32-byte word),
+// output is the signer left-padded to 32 bytes and empty on failure.
+const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+
+/// Domain separator of the calling contract.
+pub fn domain_separator() -> FixedBytes<32> {
+    let mut fields = [0u8; 160];
+    fields[0..32].copy_from_slice(keccak256(DOMAIN_TYPE).as_slice());
+    fields[32..64].copy_from_slice(keccak256(NAME).as_slice());
+    fields[64..96].copy_from_slice(keccak256(VERSION).as_slice());
+    fields[96..128].copy_from_slice(&U256::from(block::chainid()).to_be_bytes::<32>());
+    fields[140..160].copy_from_slice(contract::address().as_slice());
+    keccak256(fields)
+}
+
+/// Signer of the message with struct hash `struct_hash` under
+/// domain_separator, from a 65-byte r || s || v signature. None for a
+/// malformed or invalid signature.
+pub fn recover_signer(struct_hash: FixedBytes<32>, signature: &[u8]) -> Option<Address> {
+    if signature.len() != 65 {
+        return None;
+    }
+    let mut message = [0u8; 66];
+    message[0..2].copy_from_slice(&[0x19, 0x01]);
+    message[2..34].copy_from_slice(domain_separator().as_slice());
+    message[34..66].copy_from_slice(struct_hash.as_slice());
+
+    let mut input = [0u8; 128];
+    input[0..32].copy_from_slice(keccak256(message).as_slice());
+    input[63] = signature[64];
+    input[64..128].copy_from_slice(&signature[0..64]);
+
+    let output = static_call(Call::new(), ECRECOVER, &input).ok()?;
+    (output.len() == 32).then(|| Address::from_slice(&output[12..32]))
+}
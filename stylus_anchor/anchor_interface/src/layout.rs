@@ -0,0 +1,98 @@
+//! Where StylusHardwareAnchor keeps the state AnchorLens reports, for
+//! reading it word by word through the anchor's read_slot instead of a
+//! getter per field.
+//!
+//! Slots follow the declaration order of the anchor's `sol_storage!`
+//! fields: small fields share a word, filling it from the low-order end.
+//! The anchor's own tests check every entry here against its storage, so
+//! reordering or inserting a field there fails until this table follows.
+
+use stylus_sdk::alloy_primitives::{keccak256, FixedBytes, U256};
+
+/// A value `len` bytes wide at byte `offset` of a big-endian storage word.
+/// For a mapping, `slot` is the mapping's own slot and each value sits in
+/// the word at `key_at(key)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub slot: u64,
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl Field {
+    const fn packed(slot: u64, offset: usize, len: usize) -> Self {
+        Self { slot, offset, len }
+    }
+
+    /// A mapping to `len`-byte values, which sit at the low-order end of
+    /// their word.
+    const fn mapping(slot: u64, len: usize) -> Self {
+        Self {
+            slot,
+            offset: 32 - len,
+            len,
+        }
+    }
+
+    /// Storage key of a scalar field.
+    pub fn key(&self) -> FixedBytes<32> {
+        U256::from(self.slot).into()
+    }
+
+    /// Storage key of a mapping field's value for `key`:
+    /// keccak256(key || slot).
+    pub fn key_at(&self, key: FixedBytes<32>) -> FixedBytes<32> {
+        let mut data = [0u8; 64];
+        data[..32].copy_from_slice(key.as_slice());
+        data[32..].copy_from_slice(self.key().as_slice());
+        keccak256(data)
+    }
+
+    /// This field's value within `word`.
+    pub fn decode(&self, word: FixedBytes<32>) -> U256 {
+        U256::from_be_slice(&word[self.offset..self.offset + self.len])
+    }
+}
+
+/// Storage key of element `index` of the array whose length is kept at
+/// `array_key`: keccak256(array_key) + index.
+pub fn element_key(array_key: FixedBytes<32>, index: u64) -> FixedBytes<32> {
+    (U256::from_be_bytes(keccak256(array_key).0) + U256::from(index)).into()
+}
+
+pub const COUNTERS: Field = Field::mapping(3, 8);
+pub const RECEIPT_ROOT: Field = Field::packed(4, 0, 32);
+pub const FIRMWARE_VERSION: Field = Field::mapping(5, 4);
+pub const MIN_FIRMWARE_VERSION: Field = Field::mapping(6, 4);
+pub const REPLAY_WINDOW: Field = Field::packed(7, 24, 8);
+/// Per counter id: the open replay-window bits above the 64-bit base
+/// counter they were recorded against.
+pub const SEEN_COUNTERS: Field = Field::mapping(8, 32);
+pub const ACCEPTED_COUNT: Field = Field::mapping(9, 8);
+pub const REJECTED_COUNT: Field = Field::mapping(10, 8);
+pub const MAX_AUTHORIZED_NODES: Field = Field::packed(11, 24, 8);
+pub const ACTIVE_NODE_COUNT: Field = Field::packed(11, 16, 8);
+pub const DOMAIN_TAG: Field = Field::packed(12, 0, 32);
+pub const DOMAIN_TAG_LEN: Field = Field::packed(13, 31, 1);
+pub const LAST_VERIFIED_AT: Field = Field::mapping(14, 8);
+pub const MIN_RECEIPT_INTERVAL: Field = Field::packed(15, 24, 8);
+pub const COUNTER_BITS: Field = Field::packed(15, 23, 1);
+pub const FIRMWARE_EXPIRY: Field = Field::mapping(16, 8);
+pub const FIRMWARE_FROZEN: Field = Field::packed(17, 31, 1);
+pub const EXEC_ALLOWLIST_ENABLED: Field = Field::packed(17, 30, 1);
+pub const APPROVED_EXEC: Field = Field::mapping(18, 1);
+pub const FIRMWARE_RECEIPT_COUNT: Field = Field::mapping(19, 8);
+pub const DIGEST_ALGO: Field = Field::packed(20, 31, 1);
+pub const APPROVED_FIRMWARE_COUNT: Field = Field::packed(24, 24, 8);
+pub const MAX_FIRMWARE: Field = Field::packed(24, 16, 8);
+pub const NODE_EXPIRY: Field = Field::mapping(25, 8);
+pub const COUNTER_FLOOR: Field = Field::mapping(26, 8);
+pub const EVENT_SEQ: Field = Field::packed(27, 24, 8);
+pub const ALLOW_REANCHOR: Field = Field::mapping(28, 1);
+pub const BLOCKED_NODES: Field = Field::mapping(29, 1);
+/// Per firmware, the word at `key_at(fw_hash)` holds the number of
+/// indexed nodes; node i is at `element_key(key_at(fw_hash), i)`.
+pub const FIRMWARE_NODES: Field = Field::mapping(30, 32);
+/// Keyed by the verifier's address as a left-padded word.
+pub const VERIFIERS: Field = Field::mapping(32, 1);
+pub const SIGNATURE_REQUIRED: Field = Field::mapping(33, 1);
@@ -0,0 +1,437 @@
+//! Client interface shared by every hardware anchor deployment.
+//!
+//! Contracts that route to or aggregate anchors call through
+//! `IHardwareAnchor::new(addr)` instead of depending on a concrete anchor
+//! crate, whose `#[entrypoint]` cannot be linked into another contract.
+//! StylusHardwareAnchor implements every function below with these exact
+//! signatures. The verify and read calls are part of its ERC-165 anchor
+//! interface id; the approval calls are owner-only, for contracts that
+//! hold the anchor's ownership (AnchorGovernor). submit_receipt and
+//! purge_firmware serve AnchorGateway's batches and sweeps; anchor_receipt
+//! serves AnchorVariants and AnchorGateway's signed receipts, and
+//! check_receipt and the digest and counter previews AnchorLens. Everything else the companions read is taken from
+//! storage through read_slot, at the places recorded in `layout`.
+//!
+//! The client is written out over `sol!` call types rather than generated
+//! by `sol_interface!`, which in this SDK hashes `bytes32` parameters as
+//! `bytes[32]` and so produces selectors no anchor implements.
+#![cfg_attr(not(test), no_std)]
+extern crate alloc;
+
+pub mod eip712;
+pub mod layout;
+pub mod receipt_codec;
+
+use alloc::vec::Vec;
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::alloy_primitives::{Address, FixedBytes, U256};
+use stylus_sdk::call::{call, static_call, Error, NonPayableCallContext, StaticCallContext};
+
+sol! {
+    function verifyReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external;
+    function submitReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external returns (uint8);
+    function isNodeAuthorized(bytes32 node_id) external view returns (bool);
+    function isFirmwareApproved(bytes32 fw_hash) external view returns (bool);
+    function getCounter(bytes32 node_id) external view returns (uint64);
+    function authorizeNode(bytes32 node_id) external;
+    function approveFirmware(bytes32 fw_hash) external;
+    function approveFirmwareVersioned(bytes32 fw_hash, uint32 version) external;
+    function approveFirmwareUntil(bytes32 fw_hash, uint64 expiry) external;
+    function purgeFirmware(bytes32 fw_hash) external;
+    function revokeNode(bytes32 node_id) external;
+    function revokeFirmware(bytes32 fw_hash) external;
+    function approveExec(bytes32 exec_hash) external;
+    function revokeExec(bytes32 exec_hash) external;
+    function anchorReceipt(bytes32 hw_id, bytes32 namespace, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 digest) external;
+    function checkReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external view returns (uint8);
+    function readSlot(bytes32 slot) external view returns (bytes32);
+    function previewDigest(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter) external view returns (bytes32);
+    function getReceiptRoot() external view returns (bytes32);
+    function nextValidCounter(bytes32 node_id) external view returns (uint64);
+    function wouldAcceptCounter(bytes32 node_id, uint64 counter) external view returns (bool);
+}
+
+/// Typed calls into the anchor deployed at `address`.
+pub struct IHardwareAnchor {
+    pub address: Address,
+}
+
+impl IHardwareAnchor {
+    pub fn new(address: Address) -> Self {
+        Self { address }
+    }
+
+    /// The anchor's revert data comes back as `Error::Revert`.
+    pub fn verify_receipt(
+        &self,
+        context: impl NonPayableCallContext,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<(), Error> {
+        let calldata = verifyReceiptCall {
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+            claimed_digest,
+        }
+        .abi_encode();
+        call(context, self.address, &calldata)?;
+        Ok(())
+    }
+
+    /// Non-reverting verify_receipt: the anchor's check_receipt status
+    /// code, 0 once the receipt is accepted.
+    pub fn submit_receipt(
+        &self,
+        context: impl NonPayableCallContext,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<u8, Error> {
+        let calldata = submitReceiptCall {
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+            claimed_digest,
+        }
+        .abi_encode();
+        let returned = call(context, self.address, &calldata)?;
+        Ok(submitReceiptCall::abi_decode_returns(&returned, true)?._0)
+    }
+
+    pub fn is_node_authorized(
+        &self,
+        context: impl StaticCallContext,
+        node_id: FixedBytes<32>,
+    ) -> Result<bool, Error> {
+        let calldata = isNodeAuthorizedCall { node_id }.abi_encode();
+        let returned = static_call(context, self.address, &calldata)?;
+        Ok(isNodeAuthorizedCall::abi_decode_returns(&returned, true)?._0)
+    }
+
+    pub fn is_firmware_approved(
+        &self,
+        context: impl StaticCallContext,
+        fw_hash: FixedBytes<32>,
+    ) -> Result<bool, Error> {
+        let calldata = isFirmwareApprovedCall { fw_hash }.abi_encode();
+        let returned = static_call(context, self.address, &calldata)?;
+        Ok(isFirmwareApprovedCall::abi_decode_returns(&returned, true)?._0)
+    }
+
+    pub fn get_counter(
+        &self,
+        context: impl StaticCallContext,
+        node_id: FixedBytes<32>,
+    ) -> Result<u64, Error> {
+        let calldata = getCounterCall { node_id }.abi_encode();
+        let returned = static_call(context, self.address, &calldata)?;
+        Ok(getCounterCall::abi_decode_returns(&returned, true)?._0)
+    }
+
+    pub fn authorize_node(
+        &self,
+        context: impl NonPayableCallContext,
+        node_id: FixedBytes<32>,
+    ) -> Result<(), Error> {
+        call(
+            context,
+            self.address,
+            &authorizeNodeCall { node_id }.abi_encode(),
+        )?;
+        Ok(())
+    }
+
+    pub fn approve_firmware(
+        &self,
+        context: impl NonPayableCallContext,
+        fw_hash: FixedBytes<32>,
+    ) -> Result<(), Error> {
+        call(
+            context,
+            self.address,
+            &approveFirmwareCall { fw_hash }.abi_encode(),
+        )?;
+        Ok(())
+    }
+
+    pub fn approve_firmware_versioned(
+        &self,
+        context: impl NonPayableCallContext,
+        fw_hash: FixedBytes<32>,
+        version: u32,
+    ) -> Result<(), Error> {
+        let calldata = approveFirmwareVersionedCall { fw_hash, version }.abi_encode();
+        call(context, self.address, &calldata)?;
+        Ok(())
+    }
+
+    pub fn approve_firmware_until(
+        &self,
+        context: impl NonPayableCallContext,
+        fw_hash: FixedBytes<32>,
+        expiry: u64,
+    ) -> Result<(), Error> {
+        let calldata = approveFirmwareUntilCall { fw_hash, expiry }.abi_encode();
+        call(context, self.address, &calldata)?;
+        Ok(())
+    }
+
+    pub fn revoke_node(
+        &self,
+        context: impl NonPayableCallContext,
+        node_id: FixedBytes<32>,
+    ) -> Result<(), Error> {
+        call(
+            context,
+            self.address,
+            &revokeNodeCall { node_id }.abi_encode(),
+        )?;
+        Ok(())
+    }
+
+    pub fn revoke_firmware(
+        &self,
+        context: impl NonPayableCallContext,
+        fw_hash: FixedBytes<32>,
+    ) -> Result<(), Error> {
+        call(
+            context,
+            self.address,
+            &revokeFirmwareCall { fw_hash }.abi_encode(),
+        )?;
+        Ok(())
+    }
+
+    pub fn approve_exec(
+        &self,
+        context: impl NonPayableCallContext,
+        exec_hash: FixedBytes<32>,
+    ) -> Result<(), Error> {
+        call(
+            context,
+            self.address,
+            &approveExecCall { exec_hash }.abi_encode(),
+        )?;
+        Ok(())
+    }
+
+    pub fn revoke_exec(
+        &self,
+        context: impl NonPayableCallContext,
+        exec_hash: FixedBytes<32>,
+    ) -> Result<(), Error> {
+        call(
+            context,
+            self.address,
+            &revokeExecCall { exec_hash }.abi_encode(),
+        )?;
+        Ok(())
+    }
+
+    /// Owner-only, except that anyone may purge a hash whose approval has
+    /// expired.
+    pub fn purge_firmware(
+        &self,
+        context: impl NonPayableCallContext,
+        fw_hash: FixedBytes<32>,
+    ) -> Result<(), Error> {
+        call(
+            context,
+            self.address,
+            &purgeFirmwareCall { fw_hash }.abi_encode(),
+        )?;
+        Ok(())
+    }
+
+    /// Anchor a receipt whose digest the caller, a registered verifier,
+    /// has already matched. The anchor's revert data comes back as
+    /// `Error::Revert`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn anchor_receipt(
+        &self,
+        context: impl NonPayableCallContext,
+        hw_id: FixedBytes<32>,
+        namespace: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        digest: FixedBytes<32>,
+    ) -> Result<(), Error> {
+        let calldata = anchorReceiptCall {
+            hw_id,
+            namespace,
+            fw_hash,
+            exec_hash,
+            counter,
+            digest,
+        }
+        .abi_encode();
+        call(context, self.address, &calldata)?;
+        Ok(())
+    }
+
+    /// Dry run of verify_receipt: the anchor's check_receipt status code.
+    pub fn check_receipt(
+        &self,
+        context: impl StaticCallContext,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<u8, Error> {
+        let calldata = checkReceiptCall {
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+            claimed_digest,
+        }
+        .abi_encode();
+        let returned = static_call(context, self.address, &calldata)?;
+        Ok(checkReceiptCall::abi_decode_returns(&returned, true)?._0)
+    }
+
+    /// Raw storage word at `slot` (see `layout`).
+    pub fn read_slot(
+        &self,
+        context: impl StaticCallContext,
+        slot: FixedBytes<32>,
+    ) -> Result<FixedBytes<32>, Error> {
+        let returned = static_call(context, self.address, &readSlotCall { slot }.abi_encode())?;
+        Ok(readSlotCall::abi_decode_returns(&returned, true)?._0)
+    }
+
+    /// Digest verify_receipt expects for these inputs on the current chain
+    /// (zero when the anchor's sha256 precompile is missing).
+    pub fn preview_digest(
+        &self,
+        context: impl StaticCallContext,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+    ) -> Result<FixedBytes<32>, Error> {
+        let calldata = previewDigestCall {
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+        }
+        .abi_encode();
+        let returned = static_call(context, self.address, &calldata)?;
+        Ok(previewDigestCall::abi_decode_returns(&returned, true)?._0)
+    }
+
+    pub fn get_receipt_root(
+        &self,
+        context: impl StaticCallContext,
+    ) -> Result<FixedBytes<32>, Error> {
+        let returned = static_call(context, self.address, &getReceiptRootCall {}.abi_encode())?;
+        Ok(getReceiptRootCall::abi_decode_returns(&returned, true)?._0)
+    }
+
+    pub fn next_valid_counter(
+        &self,
+        context: impl StaticCallContext,
+        node_id: FixedBytes<32>,
+    ) -> Result<u64, Error> {
+        let calldata = nextValidCounterCall { node_id }.abi_encode();
+        let returned = static_call(context, self.address, &calldata)?;
+        Ok(nextValidCounterCall::abi_decode_returns(&returned, true)?._0)
+    }
+
+    /// Whether the anchor's counter-width and replay policy would accept
+    /// `counter` from `node_id`.
+    pub fn would_accept_counter(
+        &self,
+        context: impl StaticCallContext,
+        node_id: FixedBytes<32>,
+        counter: u64,
+    ) -> Result<bool, Error> {
+        let calldata = wouldAcceptCounterCall { node_id, counter }.abi_encode();
+        let returned = static_call(context, self.address, &calldata)?;
+        Ok(wouldAcceptCounterCall::abi_decode_returns(&returned, true)?._0)
+    }
+
+    /// A scalar `field` of the anchor's storage.
+    pub fn read_field(
+        &self,
+        context: impl StaticCallContext,
+        field: layout::Field,
+    ) -> Result<U256, Error> {
+        Ok(field.decode(self.read_slot(context, field.key())?))
+    }
+
+    /// The value of mapping `field` under `key`.
+    pub fn read_field_at(
+        &self,
+        context: impl StaticCallContext,
+        field: layout::Field,
+        key: FixedBytes<32>,
+    ) -> Result<U256, Error> {
+        Ok(field.decode(self.read_slot(context, field.key_at(key))?))
+    }
+
+    /// Domain tag prefixed to the digest material (DOMAIN when unset).
+    pub fn get_domain_tag(
+        &self,
+        context: impl StaticCallContext + Clone,
+    ) -> Result<Vec<u8>, Error> {
+        let len: usize = self
+            .read_field(context.clone(), layout::DOMAIN_TAG_LEN)?
+            .to();
+        if len == 0 {
+            return Ok(receipt_codec::DOMAIN.to_vec());
+        }
+        let tag = self.read_slot(context, layout::DOMAIN_TAG.key())?;
+        Ok(tag[..len.min(32)].to_vec())
+    }
+
+    pub fn get_digest_algo(&self, context: impl StaticCallContext) -> Result<u8, Error> {
+        Ok(self.read_field(context, layout::DIGEST_ALGO)?.to())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selectors_hash_canonical_signatures() {
+        // First four bytes of keccak256 over each canonical signature, as
+        // routed by StylusHardwareAnchor.
+        assert_eq!(verifyReceiptCall::SELECTOR, [0xd1, 0xe9, 0x98, 0xe6]);
+        assert_eq!(submitReceiptCall::SELECTOR, [0x25, 0x7c, 0x00, 0xe3]);
+        assert_eq!(isNodeAuthorizedCall::SELECTOR, [0x38, 0x12, 0x6a, 0x9e]);
+        assert_eq!(isFirmwareApprovedCall::SELECTOR, [0x64, 0x56, 0x7f, 0x36]);
+        assert_eq!(getCounterCall::SELECTOR, [0x83, 0x6e, 0x09, 0x01]);
+        assert_eq!(authorizeNodeCall::SELECTOR, [0x68, 0xd2, 0x20, 0xf7]);
+        assert_eq!(approveFirmwareCall::SELECTOR, [0xd3, 0x5b, 0xd0, 0x2e]);
+        assert_eq!(
+            approveFirmwareVersionedCall::SELECTOR,
+            [0xe9, 0xd6, 0x97, 0x4f]
+        );
+        assert_eq!(approveFirmwareUntilCall::SELECTOR, [0xcb, 0xf1, 0xea, 0x11]);
+        assert_eq!(purgeFirmwareCall::SELECTOR, [0x1d, 0xeb, 0xa7, 0x72]);
+        assert_eq!(revokeNodeCall::SELECTOR, [0x6d, 0x7a, 0x40, 0xc8]);
+        assert_eq!(revokeFirmwareCall::SELECTOR, [0x29, 0x28, 0xcb, 0x4f]);
+        assert_eq!(approveExecCall::SELECTOR, [0xda, 0xbe, 0xbf, 0xca]);
+        assert_eq!(revokeExecCall::SELECTOR, [0x6f, 0x74, 0x8e, 0xb8]);
+        assert_eq!(anchorReceiptCall::SELECTOR, [0xda, 0x61, 0x8d, 0xf4]);
+        assert_eq!(checkReceiptCall::SELECTOR, [0xbe, 0xcf, 0x68, 0x78]);
+        assert_eq!(readSlotCall::SELECTOR, [0xe8, 0xe8, 0x34, 0xa9]);
+        assert_eq!(previewDigestCall::SELECTOR, [0xc5, 0xea, 0x63, 0x0d]);
+        assert_eq!(getReceiptRootCall::SELECTOR, [0x42, 0x13, 0x15, 0x5f]);
+        assert_eq!(nextValidCounterCall::SELECTOR, [0x17, 0xb1, 0x61, 0xb7]);
+        assert_eq!(wouldAcceptCounterCall::SELECTOR, [0xe1, 0x78, 0x03, 0x0a]);
+    }
+}
@@ -0,0 +1,326 @@
+//! Wire formats shared by the anchor, the contracts around it and their
+//! tests: the 125-byte digest material (116 bytes under
+//! `legacy-nochainid`) and the packed batch encodings.
+
+use alloc::vec::Vec;
+use stylus_sdk::alloy_primitives::FixedBytes;
+
+#[cfg(not(feature = "legacy-nochainid"))]
+pub const DOMAIN: &[u8; 13] = b"anchor_RCT_V1";
+#[cfg(feature = "legacy-nochainid")]
+pub const DOMAIN: &[u8; 12] = b"NEXUS_RCT_V1";
+
+// Default digest material layout:
+// DOMAIN || chain_id_be8 || hw_id || fw_hash || exec_hash || counter_be8.
+// Every deployed device hashes exactly these offsets. Devices built
+// against the NEXUS_RCT_V1 layout (the legacy-nochainid feature) hash
+// DOMAIN || hw_id || fw_hash || exec_hash || counter_be8 instead: the
+// chain id field is empty.
+pub const DOMAIN_LEN: usize = DOMAIN.len();
+pub const CHAIN_ID_LEN: usize = if cfg!(feature = "legacy-nochainid") {
+    0
+} else {
+    8
+};
+pub const CHAINID_OFF: usize = DOMAIN_LEN;
+pub const HWID_OFF: usize = CHAINID_OFF + CHAIN_ID_LEN;
+pub const FWHASH_OFF: usize = HWID_OFF + 32;
+pub const EXECHASH_OFF: usize = FWHASH_OFF + 32;
+pub const COUNTER_OFF: usize = EXECHASH_OFF + 32;
+pub const DIGEST_MATERIAL_LEN: usize = COUNTER_OFF + 8;
+#[cfg(not(feature = "legacy-nochainid"))]
+const _: () = assert!(
+    DOMAIN_LEN == 13
+        && CHAINID_OFF == 13
+        && HWID_OFF == 21
+        && FWHASH_OFF == 53
+        && EXECHASH_OFF == 85
+        && COUNTER_OFF == 117
+        && DIGEST_MATERIAL_LEN == 125
+);
+#[cfg(feature = "legacy-nochainid")]
+const _: () = assert!(
+    DOMAIN_LEN == 12
+        && HWID_OFF == 12
+        && FWHASH_OFF == 44
+        && EXECHASH_OFF == 76
+        && COUNTER_OFF == 108
+        && DIGEST_MATERIAL_LEN == 116
+);
+
+// Packed batch receipt: version || hw_id || fw_hash || exec_hash ||
+// counter_be8 || claimed_digest. The unsigned form used for digest
+// computation stops after the counter.
+pub const RECEIPT_VERSION: u8 = 1;
+pub const PACKED_RECEIPT_LEN: usize = 137;
+pub const PACKED_RECEIPT_V2_LEN: usize = 105;
+const _: () = assert!(PACKED_RECEIPT_V2_LEN == 1 + 32 * 3 + 8);
+const _: () = assert!(PACKED_RECEIPT_LEN == PACKED_RECEIPT_V2_LEN + 32);
+
+// Single receipt for verify_receipt_packed: the packed batch layout
+// without the version byte.
+pub const RECEIPT_LEN: usize = PACKED_RECEIPT_LEN - 1;
+
+/// One receipt of a packed batch. `version` is carried as decoded; callers
+/// decide what to do with versions they do not understand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Receipt {
+    pub version: u8,
+    pub hw_id: FixedBytes<32>,
+    pub fw_hash: FixedBytes<32>,
+    pub exec_hash: FixedBytes<32>,
+    pub counter: u64,
+    pub claimed_digest: FixedBytes<32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodecError {
+    /// Blob length is not a whole number of receipts.
+    BadLength,
+}
+
+/// Digest material in the default layout. `chain_id` is not part of the
+/// legacy-nochainid layout and is ignored there.
+pub fn encode_material(
+    chain_id: u64,
+    hw_id: FixedBytes<32>,
+    fw_hash: FixedBytes<32>,
+    exec_hash: FixedBytes<32>,
+    counter: u64,
+) -> [u8; DIGEST_MATERIAL_LEN] {
+    let mut material = [0u8; DIGEST_MATERIAL_LEN];
+    material[..CHAINID_OFF].copy_from_slice(DOMAIN);
+    material[CHAINID_OFF..HWID_OFF].copy_from_slice(&chain_id.to_be_bytes()[8 - CHAIN_ID_LEN..]);
+    material[HWID_OFF..FWHASH_OFF].copy_from_slice(hw_id.as_slice());
+    material[FWHASH_OFF..EXECHASH_OFF].copy_from_slice(fw_hash.as_slice());
+    material[EXECHASH_OFF..COUNTER_OFF].copy_from_slice(exec_hash.as_slice());
+    material[COUNTER_OFF..].copy_from_slice(&counter.to_be_bytes());
+    material
+}
+
+/// Digest material under a custom domain tag:
+/// tag || chain_id_be8 || hw_id || fw_hash || exec_hash || counter_be8.
+/// The chain id is kept under legacy-nochainid too.
+pub fn encode_tagged_material(
+    tag: &[u8],
+    chain_id: u64,
+    hw_id: FixedBytes<32>,
+    fw_hash: FixedBytes<32>,
+    exec_hash: FixedBytes<32>,
+    counter: u64,
+) -> Vec<u8> {
+    let mut material = Vec::with_capacity(tag.len() + 112);
+    material.extend_from_slice(tag);
+    material.extend_from_slice(&chain_id.to_be_bytes());
+    material.extend_from_slice(hw_id.as_slice());
+    material.extend_from_slice(fw_hash.as_slice());
+    material.extend_from_slice(exec_hash.as_slice());
+    material.extend_from_slice(&counter.to_be_bytes());
+    material
+}
+
+/// Split a blob of PACKED_RECEIPT_LEN-byte receipts. An empty blob is an
+/// empty batch.
+pub fn decode_packed(blob: &[u8]) -> Result<Vec<Receipt>, CodecError> {
+    decode_with_stride(blob, PACKED_RECEIPT_LEN)
+}
+
+/// Split a blob of unsigned PACKED_RECEIPT_V2_LEN-byte receipts; each
+/// claimed_digest is zero.
+pub fn decode_packed_unsigned(blob: &[u8]) -> Result<Vec<Receipt>, CodecError> {
+    decode_with_stride(blob, PACKED_RECEIPT_V2_LEN)
+}
+
+/// Decode one RECEIPT_LEN-byte receipt; `version` is RECEIPT_VERSION.
+pub fn decode_receipt(bytes: &[u8]) -> Result<Receipt, CodecError> {
+    if bytes.len() != RECEIPT_LEN {
+        return Err(CodecError::BadLength);
+    }
+    Ok(Receipt {
+        version: RECEIPT_VERSION,
+        hw_id: FixedBytes::from_slice(&bytes[0..32]),
+        fw_hash: FixedBytes::from_slice(&bytes[32..64]),
+        exec_hash: FixedBytes::from_slice(&bytes[64..96]),
+        counter: u64::from_be_bytes(bytes[96..104].try_into().unwrap()),
+        claimed_digest: FixedBytes::from_slice(&bytes[104..136]),
+    })
+}
+
+pub fn encode_packed(receipts: &[Receipt]) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(receipts.len() * PACKED_RECEIPT_LEN);
+    for receipt in receipts {
+        encode_unsigned_into(&mut blob, receipt);
+        blob.extend_from_slice(receipt.claimed_digest.as_slice());
+    }
+    blob
+}
+
+pub fn encode_packed_unsigned(receipts: &[Receipt]) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(receipts.len() * PACKED_RECEIPT_V2_LEN);
+    for receipt in receipts {
+        encode_unsigned_into(&mut blob, receipt);
+    }
+    blob
+}
+
+fn encode_unsigned_into(blob: &mut Vec<u8>, receipt: &Receipt) {
+    blob.push(receipt.version);
+    blob.extend_from_slice(receipt.hw_id.as_slice());
+    blob.extend_from_slice(receipt.fw_hash.as_slice());
+    blob.extend_from_slice(receipt.exec_hash.as_slice());
+    blob.extend_from_slice(&receipt.counter.to_be_bytes());
+}
+
+fn decode_with_stride(blob: &[u8], stride: usize) -> Result<Vec<Receipt>, CodecError> {
+    if !blob.len().is_multiple_of(stride) {
+        return Err(CodecError::BadLength);
+    }
+    Ok(blob
+        .chunks_exact(stride)
+        .map(|r| Receipt {
+            version: r[0],
+            hw_id: FixedBytes::from_slice(&r[1..33]),
+            fw_hash: FixedBytes::from_slice(&r[33..65]),
+            exec_hash: FixedBytes::from_slice(&r[65..97]),
+            counter: u64::from_be_bytes(r[97..105].try_into().unwrap()),
+            claimed_digest: if stride == PACKED_RECEIPT_LEN {
+                FixedBytes::from_slice(&r[105..137])
+            } else {
+                FixedBytes::ZERO
+            },
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // xorshift64*: deterministic inputs for the round-trip properties
+    // without pulling in a property-testing crate.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        }
+
+        fn bytes32(&mut self) -> FixedBytes<32> {
+            let mut out = [0u8; 32];
+            for chunk in out.chunks_mut(8) {
+                chunk.copy_from_slice(&self.next().to_be_bytes());
+            }
+            FixedBytes(out)
+        }
+
+        fn receipt(&mut self) -> Receipt {
+            Receipt {
+                version: self.next() as u8,
+                hw_id: self.bytes32(),
+                fw_hash: self.bytes32(),
+                exec_hash: self.bytes32(),
+                counter: self.next(),
+                claimed_digest: self.bytes32(),
+            }
+        }
+    }
+
+    #[test]
+    fn packed_round_trip() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for len in 0..32 {
+            let receipts: Vec<Receipt> = (0..len).map(|_| rng.receipt()).collect();
+            let blob = encode_packed(&receipts);
+            assert_eq!(blob.len(), len * PACKED_RECEIPT_LEN);
+            assert_eq!(decode_packed(&blob), Ok(receipts));
+        }
+    }
+
+    #[test]
+    fn unsigned_round_trip_drops_digest() {
+        let mut rng = Rng(0x0123_4567_89ab_cdef);
+        for len in 0..32 {
+            let receipts: Vec<Receipt> = (0..len).map(|_| rng.receipt()).collect();
+            let blob = encode_packed_unsigned(&receipts);
+            assert_eq!(blob.len(), len * PACKED_RECEIPT_V2_LEN);
+            let expected: Vec<Receipt> = receipts
+                .into_iter()
+                .map(|r| Receipt {
+                    claimed_digest: FixedBytes::ZERO,
+                    ..r
+                })
+                .collect();
+            assert_eq!(decode_packed_unsigned(&blob), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn single_receipt_is_a_packed_receipt_without_version() {
+        let mut rng = Rng(0xfeed);
+        for _ in 0..64 {
+            let receipt = Receipt {
+                version: RECEIPT_VERSION,
+                ..rng.receipt()
+            };
+            let packed = encode_packed(&[receipt]);
+            assert_eq!(decode_receipt(&packed[1..]), Ok(receipt));
+            assert_eq!(decode_receipt(&packed), Err(CodecError::BadLength));
+            assert_eq!(decode_receipt(&packed[2..]), Err(CodecError::BadLength));
+        }
+    }
+
+    #[test]
+    fn partial_receipts_are_rejected() {
+        let mut rng = Rng(42);
+        let blob = encode_packed(&[rng.receipt(), rng.receipt()]);
+        for cut in 1..PACKED_RECEIPT_LEN {
+            assert_eq!(
+                decode_packed(&blob[..blob.len() - cut]),
+                Err(CodecError::BadLength)
+            );
+        }
+    }
+
+    #[test]
+    fn material_fields_sit_at_their_offsets() {
+        let mut rng = Rng(7);
+        for _ in 0..64 {
+            let (chain_id, counter) = (rng.next(), rng.next());
+            let (hw_id, fw_hash, exec_hash) = (rng.bytes32(), rng.bytes32(), rng.bytes32());
+            let m = encode_material(chain_id, hw_id, fw_hash, exec_hash, counter);
+            assert_eq!(&m[..CHAINID_OFF], DOMAIN);
+            assert_eq!(
+                m[CHAINID_OFF..HWID_OFF],
+                chain_id.to_be_bytes()[8 - CHAIN_ID_LEN..]
+            );
+            assert_eq!(m[HWID_OFF..FWHASH_OFF], hw_id[..]);
+            assert_eq!(m[FWHASH_OFF..EXECHASH_OFF], fw_hash[..]);
+            assert_eq!(m[EXECHASH_OFF..COUNTER_OFF], exec_hash[..]);
+            assert_eq!(m[COUNTER_OFF..], counter.to_be_bytes());
+        }
+    }
+
+    #[cfg(not(feature = "legacy-nochainid"))]
+    #[test]
+    fn default_domain_is_a_tagged_material() {
+        let mut rng = Rng(7);
+        for _ in 0..16 {
+            let r = rng.receipt();
+            let chain_id = rng.next();
+            assert_eq!(
+                encode_tagged_material(
+                    DOMAIN,
+                    chain_id,
+                    r.hw_id,
+                    r.fw_hash,
+                    r.exec_hash,
+                    r.counter
+                ),
+                encode_material(chain_id, r.hw_id, r.fw_hash, r.exec_hash, r.counter)
+            );
+        }
+    }
+}
@@ -0,0 +1,808 @@
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(feature = "export-abi")), no_std)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use anchor_interface::layout::{self, Field};
+use anchor_interface::receipt_codec::{self, Receipt, DOMAIN, RECEIPT_VERSION};
+use anchor_interface::IHardwareAnchor;
+use stylus_sdk::abi::Bytes;
+use stylus_sdk::alloy_primitives::{address, keccak256, Address, FixedBytes, U256};
+use stylus_sdk::call::{static_call, Call};
+use stylus_sdk::{block, prelude::*};
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod test_vm;
+
+// Deployed builds leave out std: a panic traps, which reverts the call.
+#[cfg(all(target_arch = "wasm32", not(feature = "export-abi")))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+// Most ids a batch status view answers in one call; longer inputs return
+// an empty vector, like malformed batches elsewhere.
+const MAX_BATCH_QUERY: usize = 512;
+
+// The anchor's full counter width, which a counter_bits of 0 stands for.
+const MAX_COUNTER_BITS: u8 = 64;
+
+// Status the anchor's check_receipt returns for an acceptable receipt.
+const STATUS_OK: u8 = 0;
+
+// The anchor's digest_algo for keccak256; its other value selects sha256.
+const DIGEST_KECCAK256: u8 = 0;
+
+// sha256 precompile: input is the message, output the 32-byte hash.
+const SHA256: Address = address!("0000000000000000000000000000000000000002");
+
+sol! {
+    error AlreadyInitialized();
+    error HashUnavailable();
+    error IndexOutOfRange();
+}
+
+sol_storage! {
+    /// Read-only views over a StylusHardwareAnchor: its configuration and
+    /// per-node state, batch status queries and digest previews. Plain
+    /// state is read word by word through the anchor's read_slot at the
+    /// places anchor_interface::layout records; receipt checks go through
+    /// the anchor's own check_receipt, so they cannot drift from
+    /// verify_receipt. Nothing here writes to the anchor.
+    #[entrypoint]
+    pub struct AnchorLens {
+        // The anchor read from.
+        address anchor;
+    }
+}
+
+#[derive(SolidityError)]
+pub enum LensError {
+    AlreadyInitialized(AlreadyInitialized),
+    HashUnavailable(HashUnavailable),
+    IndexOutOfRange(IndexOutOfRange),
+}
+
+/// Counter id the anchor keeps a tenant domain's counters under.
+fn domain_counter_id(namespace: FixedBytes<32>, hw_id: FixedBytes<32>) -> FixedBytes<32> {
+    let mut key = [0u8; 64];
+    key[..32].copy_from_slice(namespace.as_slice());
+    key[32..].copy_from_slice(hw_id.as_slice());
+    keccak256(key)
+}
+
+/// Material the anchor's verify_receipt hashes under `tag`: the fixed
+/// layout for the default tag, otherwise the same fields after the custom
+/// tag.
+fn receipt_material(
+    tag: &[u8],
+    chain_id: u64,
+    hw_id: FixedBytes<32>,
+    fw_hash: FixedBytes<32>,
+    exec_hash: FixedBytes<32>,
+    counter: u64,
+) -> Vec<u8> {
+    if tag == DOMAIN.as_slice() {
+        return receipt_codec::encode_material(chain_id, hw_id, fw_hash, exec_hash, counter)
+            .to_vec();
+    }
+    receipt_codec::encode_tagged_material(tag, chain_id, hw_id, fw_hash, exec_hash, counter)
+}
+
+/// Hash digest material the way the anchor does under `algo`.
+fn hash_material(algo: u8, material: &[u8]) -> Result<FixedBytes<32>, LensError> {
+    if algo == DIGEST_KECCAK256 {
+        return Ok(keccak256(material));
+    }
+    let output = static_call(Call::new(), SHA256, material)
+        .map_err(|_| LensError::HashUnavailable(HashUnavailable {}))?;
+    if output.len() != 32 {
+        return Err(LensError::HashUnavailable(HashUnavailable {}));
+    }
+    Ok(FixedBytes::from_slice(&output))
+}
+
+#[public]
+impl AnchorLens {
+    /// One-time initialization with the anchor to read. The lens holds no
+    /// other state, so there is no owner.
+    pub fn initialize(&mut self, anchor: Address) -> Result<(), LensError> {
+        if self.anchor.get() != Address::ZERO {
+            return Err(LensError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        self.anchor.set(anchor);
+        Ok(())
+    }
+
+    pub fn get_anchor(&self) -> Address {
+        self.anchor.get()
+    }
+
+    /// verify_receipt as a view, for eth_call gas estimation and for other
+    /// contracts to staticcall: every check, digest reconstruction and the
+    /// replay comparison against the stored counter, reported as a status
+    /// code. Being a view it cannot advance the counter, so two simulations
+    /// of the same receipt both report STATUS_OK. Same result as the
+    /// anchor's check_receipt.
+    pub fn simulate_verify_receipt(
+        &self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<u8, Vec<u8>> {
+        Ok(self.anchor().check_receipt(
+            Call::new(),
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+            claimed_digest,
+        )?)
+    }
+
+    pub fn verify_receipts_batch(&self, packed: Vec<u8>) -> Result<Vec<bool>, Vec<u8>> {
+        let Ok(receipts) = receipt_codec::decode_packed(&packed) else {
+            return Ok(Vec::new());
+        };
+
+        receipts
+            .iter()
+            .map(|receipt| self.check_packed_receipt(receipt))
+            .collect()
+    }
+
+    pub fn verify_receipts_batch_bytes(&self, packed: Bytes) -> Result<Vec<bool>, Vec<u8>> {
+        self.verify_receipts_batch(packed.to_vec())
+    }
+
+    pub fn verify_receipts_batch_bitset(&self, packed: Vec<u8>) -> Result<FixedBytes<32>, Vec<u8>> {
+        let Ok(receipts) = receipt_codec::decode_packed(&packed) else {
+            return Ok(FixedBytes::<32>::ZERO);
+        };
+        if receipts.len() > 256 {
+            return Ok(FixedBytes::<32>::ZERO);
+        }
+
+        let mut bits = [0u8; 32];
+
+        for (i, receipt) in receipts.iter().enumerate() {
+            if self.check_packed_receipt(receipt)? {
+                let byte_index = i / 8;
+                let bit_index = i % 8;
+                bits[byte_index] |= 1u8 << bit_index;
+            }
+        }
+
+        Ok(FixedBytes::<32>::from(bits))
+    }
+
+    pub fn verify_receipts_batch_bitset_bytes(
+        &self,
+        packed: Bytes,
+    ) -> Result<FixedBytes<32>, Vec<u8>> {
+        self.verify_receipts_batch_bitset(packed.to_vec())
+    }
+
+    pub fn compute_receipt_digests_batch(
+        &self,
+        packed: Vec<u8>,
+    ) -> Result<Vec<FixedBytes<32>>, Vec<u8>> {
+        let Ok(receipts) = receipt_codec::decode_packed_unsigned(&packed) else {
+            return Ok(Vec::new());
+        };
+
+        let (tag, algo) = (self.domain_tag()?, self.digest_algo()?);
+        let chain_id = block::chainid();
+        Ok(receipts
+            .iter()
+            .map(|r| {
+                let material =
+                    receipt_material(&tag, chain_id, r.hw_id, r.fw_hash, r.exec_hash, r.counter);
+                hash_material(algo, &material)
+            })
+            .collect::<Result<_, _>>()
+            .unwrap_or_default())
+    }
+
+    /// is_node_authorized for up to MAX_BATCH_QUERY (512) ids, in order.
+    pub fn are_nodes_authorized(
+        &self,
+        node_ids: Vec<FixedBytes<32>>,
+    ) -> Result<Vec<bool>, Vec<u8>> {
+        if node_ids.len() > MAX_BATCH_QUERY {
+            return Ok(Vec::new());
+        }
+        let anchor = self.anchor();
+        node_ids
+            .into_iter()
+            .map(|id| Ok(anchor.is_node_authorized(Call::new(), id)?))
+            .collect()
+    }
+
+    /// is_firmware_approved for up to MAX_BATCH_QUERY (512) hashes, in order.
+    pub fn are_firmwares_approved(
+        &self,
+        fw_hashes: Vec<FixedBytes<32>>,
+    ) -> Result<Vec<bool>, Vec<u8>> {
+        if fw_hashes.len() > MAX_BATCH_QUERY {
+            return Ok(Vec::new());
+        }
+        let anchor = self.anchor();
+        fw_hashes
+            .into_iter()
+            .map(|hash| Ok(anchor.is_firmware_approved(Call::new(), hash)?))
+            .collect()
+    }
+
+    /// get_counter for up to MAX_BATCH_QUERY (512) ids, in order.
+    pub fn get_counters(&self, node_ids: Vec<FixedBytes<32>>) -> Result<Vec<u64>, Vec<u8>> {
+        if node_ids.len() > MAX_BATCH_QUERY {
+            return Ok(Vec::new());
+        }
+        let anchor = self.anchor();
+        node_ids
+            .into_iter()
+            .map(|id| Ok(anchor.get_counter(Call::new(), id)?))
+            .collect()
+    }
+
+    pub fn get_max_authorized_nodes(&self) -> Result<u64, Vec<u8>> {
+        Ok(self.read(layout::MAX_AUTHORIZED_NODES)?.to())
+    }
+
+    pub fn get_active_node_count(&self) -> Result<u64, Vec<u8>> {
+        Ok(self.read(layout::ACTIVE_NODE_COUNT)?.to())
+    }
+
+    pub fn is_firmware_frozen(&self) -> Result<bool, Vec<u8>> {
+        Ok(self.read(layout::FIRMWARE_FROZEN)? != U256::ZERO)
+    }
+
+    /// Sequence number of the anchor's last event (0 = none yet).
+    pub fn get_event_seq(&self) -> Result<u64, Vec<u8>> {
+        Ok(self.read(layout::EVENT_SEQ)?.to())
+    }
+
+    /// Block timestamp at which the node's authorization lapses
+    /// (u64::MAX = never, 0 = not authorized).
+    pub fn get_node_expiry(&self, node_id: FixedBytes<32>) -> Result<u64, Vec<u8>> {
+        Ok(self.read_at(layout::NODE_EXPIRY, node_id)?.to())
+    }
+
+    /// Block timestamp at which the approval lapses (0 = never).
+    pub fn get_firmware_expiry(&self, fw_hash: FixedBytes<32>) -> Result<u64, Vec<u8>> {
+        Ok(self.read_at(layout::FIRMWARE_EXPIRY, fw_hash)?.to())
+    }
+
+    pub fn is_exec_allowlist_enabled(&self) -> Result<bool, Vec<u8>> {
+        Ok(self.read(layout::EXEC_ALLOWLIST_ENABLED)? != U256::ZERO)
+    }
+
+    pub fn is_exec_approved(&self, exec_hash: FixedBytes<32>) -> Result<bool, Vec<u8>> {
+        Ok(self.read_at(layout::APPROVED_EXEC, exec_hash)? != U256::ZERO)
+    }
+
+    /// Whether `verifier` may call the anchor's anchor_receipt.
+    pub fn is_verifier(&self, verifier: Address) -> Result<bool, Vec<u8>> {
+        Ok(self.read_at(layout::VERIFIERS, verifier.into_word())? != U256::ZERO)
+    }
+
+    pub fn get_firmware_version(&self, fw_hash: FixedBytes<32>) -> Result<u32, Vec<u8>> {
+        Ok(self.read_at(layout::FIRMWARE_VERSION, fw_hash)?.to())
+    }
+
+    pub fn get_min_firmware_version(&self, node_id: FixedBytes<32>) -> Result<u32, Vec<u8>> {
+        Ok(self.read_at(layout::MIN_FIRMWARE_VERSION, node_id)?.to())
+    }
+
+    pub fn get_accepted_count(&self, node_id: FixedBytes<32>) -> Result<u64, Vec<u8>> {
+        Ok(self.read_at(layout::ACCEPTED_COUNT, node_id)?.to())
+    }
+
+    pub fn get_rejected_count(&self, node_id: FixedBytes<32>) -> Result<u64, Vec<u8>> {
+        Ok(self.read_at(layout::REJECTED_COUNT, node_id)?.to())
+    }
+
+    pub fn get_max_firmware(&self) -> Result<u64, Vec<u8>> {
+        Ok(self.read(layout::MAX_FIRMWARE)?.to())
+    }
+
+    pub fn get_approved_firmware_count(&self) -> Result<u64, Vec<u8>> {
+        Ok(self.read(layout::APPROVED_FIRMWARE_COUNT)?.to())
+    }
+
+    pub fn get_digest_algo(&self) -> Result<u8, Vec<u8>> {
+        self.digest_algo()
+    }
+
+    /// Receipts accepted under `fw_hash`, across all nodes.
+    pub fn get_firmware_receipt_count(&self, fw_hash: FixedBytes<32>) -> Result<u64, Vec<u8>> {
+        Ok(self.read_at(layout::FIRMWARE_RECEIPT_COUNT, fw_hash)?.to())
+    }
+
+    /// Distinct nodes with an accepted receipt under `fw_hash`. The index
+    /// is append-once: a node is added on its first accepted receipt under
+    /// the firmware and never removed, so it still lists nodes that have
+    /// since moved to other firmware or been revoked, and survives
+    /// purge_firmware.
+    pub fn get_firmware_node_count(&self, fw_hash: FixedBytes<32>) -> Result<u64, Vec<u8>> {
+        Ok(self.read_at(layout::FIRMWARE_NODES, fw_hash)?.to())
+    }
+
+    /// Node at `index` (first-use order) in the get_firmware_node_count
+    /// index of `fw_hash`; reverts with IndexOutOfRange past the end.
+    pub fn get_firmware_node_at(
+        &self,
+        fw_hash: FixedBytes<32>,
+        index: u64,
+    ) -> Result<FixedBytes<32>, Vec<u8>> {
+        if index >= self.get_firmware_node_count(fw_hash)? {
+            return Err(LensError::IndexOutOfRange(IndexOutOfRange {}).into());
+        }
+        let nodes_key = layout::FIRMWARE_NODES.key_at(fw_hash);
+        Ok(self
+            .anchor()
+            .read_slot(Call::new(), layout::element_key(nodes_key, index))?)
+    }
+
+    pub fn get_replay_window(&self) -> Result<u64, Vec<u8>> {
+        Ok(self.read(layout::REPLAY_WINDOW)?.to())
+    }
+
+    pub fn get_min_receipt_interval(&self) -> Result<u64, Vec<u8>> {
+        Ok(self.read(layout::MIN_RECEIPT_INTERVAL)?.to())
+    }
+
+    /// Block timestamp of the node's last accepted receipt (0 = none yet).
+    pub fn get_last_verified_at(&self, node_id: FixedBytes<32>) -> Result<u64, Vec<u8>> {
+        Ok(self.read_at(layout::LAST_VERIFIED_AT, node_id)?.to())
+    }
+
+    pub fn get_counter_bits(&self) -> Result<u8, Vec<u8>> {
+        Ok(match self.read(layout::COUNTER_BITS)?.to::<u8>() {
+            0 => MAX_COUNTER_BITS,
+            bits => bits,
+        })
+    }
+
+    /// Last counter the anchor accepted from `node_id` under the tenant
+    /// `domain_tag` (AnchorVariants' verify_receipt_domain).
+    pub fn get_domain_counter(
+        &self,
+        domain_tag: Bytes,
+        node_id: FixedBytes<32>,
+    ) -> Result<u64, Vec<u8>> {
+        let counter_id = domain_counter_id(keccak256(domain_tag.as_slice()), node_id);
+        Ok(self.read_at(layout::COUNTERS, counter_id)?.to())
+    }
+
+    /// Lowest counter above the node's last accepted one and its counter
+    /// floor, for a device resyncing after losing its counter.
+    pub fn next_valid_counter(&self, node_id: FixedBytes<32>) -> Result<u64, Vec<u8>> {
+        Ok(self.anchor().next_valid_counter(Call::new(), node_id)?)
+    }
+
+    pub fn get_counter_floor(&self, node_id: FixedBytes<32>) -> Result<u64, Vec<u8>> {
+        Ok(self.read_at(layout::COUNTER_FLOOR, node_id)?.to())
+    }
+
+    pub fn is_reanchor_allowed(&self, node_id: FixedBytes<32>) -> Result<bool, Vec<u8>> {
+        Ok(self.read_at(layout::ALLOW_REANCHOR, node_id)? != U256::ZERO)
+    }
+
+    pub fn is_node_blocked(&self, node_id: FixedBytes<32>) -> Result<bool, Vec<u8>> {
+        Ok(self.read_at(layout::BLOCKED_NODES, node_id)? != U256::ZERO)
+    }
+
+    pub fn is_signature_required(&self, node_id: FixedBytes<32>) -> Result<bool, Vec<u8>> {
+        Ok(self.read_at(layout::SIGNATURE_REQUIRED, node_id)? != U256::ZERO)
+    }
+
+    /// Whether `counter` passes the anchor's current counter-width and
+    /// replay policy (including late counters open in the replay window),
+    /// as the anchor's would_accept_counter answers it. The other
+    /// verify_receipt checks are not applied; is_anchorable on the anchor
+    /// applies them all.
+    pub fn would_accept_counter(
+        &self,
+        node_id: FixedBytes<32>,
+        counter: u64,
+    ) -> Result<bool, Vec<u8>> {
+        Ok(self
+            .anchor()
+            .would_accept_counter(Call::new(), node_id, counter)?)
+    }
+
+    /// Digest verify_receipt expects for these inputs on the current chain,
+    /// as the anchor computes it.
+    pub fn preview_digest(
+        &self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+    ) -> Result<FixedBytes<32>, Vec<u8>> {
+        let digest =
+            self.anchor()
+                .preview_digest(Call::new(), hw_id, fw_hash, exec_hash, counter)?;
+        // The anchor answers zero when its sha256 precompile is missing.
+        if digest == FixedBytes::ZERO {
+            return Err(LensError::HashUnavailable(HashUnavailable {}).into());
+        }
+        Ok(digest)
+    }
+
+    /// Debug aid: the exact bytes preview_digest hashes for these inputs on
+    /// the current chain (the 125-byte layout under the default domain
+    /// tag), for byte-diffing against a device's own buffer.
+    pub fn preview_material(
+        &self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+    ) -> Result<Bytes, Vec<u8>> {
+        Ok(Bytes(receipt_material(
+            &self.domain_tag()?,
+            block::chainid(),
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+        )))
+    }
+
+    /// Length in bytes of the material verify_receipt hashes (and
+    /// preview_material returns): DIGEST_MATERIAL_LEN (125, or 116 with
+    /// legacy-nochainid) under the default domain tag, otherwise the
+    /// custom tag's length plus 112. verify_receipt_domain hashes its own
+    /// tag's length plus 112.
+    pub fn material_length(&self) -> Result<u32, Vec<u8>> {
+        let tag_len: usize = self.read(layout::DOMAIN_TAG_LEN)?.to();
+        if tag_len == 0 {
+            return Ok(receipt_codec::DIGEST_MATERIAL_LEN as u32);
+        }
+        Ok((tag_len + 112) as u32)
+    }
+
+    /// The anchor's derive_hw_id, for cross-checking provisioning tools
+    /// on-chain: keccak256(efuse || salt).
+    pub fn preview_hw_id(&self, efuse: FixedBytes<16>, salt: FixedBytes<32>) -> FixedBytes<32> {
+        let mut material = [0u8; 48];
+        material[..16].copy_from_slice(efuse.as_slice());
+        material[16..].copy_from_slice(salt.as_slice());
+        keccak256(material)
+    }
+
+    pub fn get_domain_tag(&self) -> Result<Bytes, Vec<u8>> {
+        Ok(Bytes(self.domain_tag()?))
+    }
+
+    pub fn get_receipt_root(&self) -> Result<FixedBytes<32>, Vec<u8>> {
+        Ok(self.anchor().get_receipt_root(Call::new())?)
+    }
+}
+
+impl AnchorLens {
+    fn anchor(&self) -> IHardwareAnchor {
+        IHardwareAnchor::new(self.anchor.get())
+    }
+
+    fn read(&self, field: Field) -> Result<U256, Vec<u8>> {
+        Ok(self.anchor().read_field(Call::new(), field)?)
+    }
+
+    fn read_at(&self, field: Field, key: FixedBytes<32>) -> Result<U256, Vec<u8>> {
+        Ok(self.anchor().read_field_at(Call::new(), field, key)?)
+    }
+
+    /// The anchor's domain tag (DOMAIN when it has none of its own).
+    fn domain_tag(&self) -> Result<Vec<u8>, Vec<u8>> {
+        Ok(self.anchor().get_domain_tag(Call::new())?)
+    }
+
+    fn digest_algo(&self) -> Result<u8, Vec<u8>> {
+        Ok(self.anchor().get_digest_algo(Call::new())?)
+    }
+
+    /// Whether the anchor would accept a packed batch entry right now.
+    fn check_packed_receipt(&self, receipt: &Receipt) -> Result<bool, Vec<u8>> {
+        if receipt.version != RECEIPT_VERSION {
+            return Ok(false);
+        }
+        let status = self.simulate_verify_receipt(
+            receipt.hw_id,
+            receipt.fw_hash,
+            receipt.exec_hash,
+            receipt.counter,
+            receipt.claimed_digest,
+        )?;
+        Ok(status == STATUS_OK)
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolCall;
+    use stylus_sdk::alloy_primitives::B256;
+    use test_vm::CHAIN_ID;
+
+    const ANCHOR: Address = Address::repeat_byte(0xa0);
+    const HW_ID: FixedBytes<32> = FixedBytes::repeat_byte(1);
+    const FW_HASH: FixedBytes<32> = FixedBytes::repeat_byte(2);
+    const EXEC_HASH: FixedBytes<32> = FixedBytes::repeat_byte(3);
+
+    fn lens() -> AnchorLens {
+        let mut lens: AnchorLens = test_vm::contract();
+        assert!(lens.initialize(ANCHOR).is_ok());
+        lens
+    }
+
+    /// Set scalar `field` of the mock anchor.
+    fn set(field: Field, value: u64) {
+        test_vm::set_anchor_field(field.key(), field, U256::from(value));
+    }
+
+    /// Set mapping `field` of the mock anchor under `key`.
+    fn set_at(field: Field, key: B256, value: U256) {
+        test_vm::set_anchor_field(field.key_at(key), field, value);
+    }
+
+    #[test]
+    fn initializes_once() {
+        let mut lens = lens();
+        assert_eq!(lens.get_anchor(), ANCHOR);
+        assert!(matches!(
+            lens.initialize(Address::repeat_byte(0xbb)),
+            Err(LensError::AlreadyInitialized(_))
+        ));
+    }
+
+    #[test]
+    fn getters_decode_packed_anchor_words() {
+        let lens = lens();
+        // Neighbours packed into one word read independently.
+        set(layout::MAX_AUTHORIZED_NODES, 8);
+        set(layout::ACTIVE_NODE_COUNT, 3);
+        set(layout::FIRMWARE_FROZEN, 1);
+        set(layout::MAX_FIRMWARE, 40);
+        set(layout::APPROVED_FIRMWARE_COUNT, 12);
+        assert_eq!(lens.get_max_authorized_nodes(), Ok(8));
+        assert_eq!(lens.get_active_node_count(), Ok(3));
+        assert_eq!(lens.is_firmware_frozen(), Ok(true));
+        assert_eq!(lens.is_exec_allowlist_enabled(), Ok(false));
+        assert_eq!(lens.get_max_firmware(), Ok(40));
+        assert_eq!(lens.get_approved_firmware_count(), Ok(12));
+
+        // An unset counter width is the full 64 bits.
+        assert_eq!(lens.get_counter_bits(), Ok(64));
+        set(layout::COUNTER_BITS, 32);
+        assert_eq!(lens.get_counter_bits(), Ok(32));
+
+        set_at(layout::NODE_EXPIRY, HW_ID, U256::from(u64::MAX));
+        set_at(layout::BLOCKED_NODES, HW_ID, U256::from(1));
+        assert_eq!(lens.get_node_expiry(HW_ID), Ok(u64::MAX));
+        assert_eq!(lens.is_node_blocked(HW_ID), Ok(true));
+        assert_eq!(lens.is_node_blocked(FW_HASH), Ok(false));
+        set_at(layout::SIGNATURE_REQUIRED, HW_ID, U256::from(1));
+        assert_eq!(lens.is_signature_required(HW_ID), Ok(true));
+        assert_eq!(lens.is_signature_required(FW_HASH), Ok(false));
+
+        let verifier = Address::repeat_byte(0xcc);
+        set_at(layout::VERIFIERS, verifier.into_word(), U256::from(1));
+        assert_eq!(lens.is_verifier(verifier), Ok(true));
+        assert_eq!(lens.is_verifier(ANCHOR), Ok(false));
+    }
+
+    #[test]
+    fn firmware_node_index_reads_the_anchor_array() {
+        let lens = lens();
+        let nodes_key = layout::FIRMWARE_NODES.key_at(FW_HASH);
+        let nodes = [0x61, 0x62].map(FixedBytes::<32>::repeat_byte);
+        set_at(layout::FIRMWARE_NODES, FW_HASH, U256::from(nodes.len()));
+        for (i, node) in (0..).zip(nodes) {
+            test_vm::set_anchor_word(layout::element_key(nodes_key, i), node);
+        }
+
+        assert_eq!(lens.get_firmware_node_count(FW_HASH), Ok(2));
+        assert_eq!(lens.get_firmware_node_at(FW_HASH, 0), Ok(nodes[0]));
+        assert_eq!(lens.get_firmware_node_at(FW_HASH, 1), Ok(nodes[1]));
+        assert_eq!(
+            lens.get_firmware_node_at(FW_HASH, 2),
+            Err(LensError::IndexOutOfRange(IndexOutOfRange {}).into())
+        );
+        assert_eq!(lens.get_firmware_node_count(HW_ID), Ok(0));
+    }
+
+    #[test]
+    fn counter_and_digest_previews_ask_the_anchor() {
+        let lens = lens();
+        let word = |value: u64| U256::from(value).to_be_bytes_vec();
+        test_vm::respond_to_view(
+            anchor_interface::nextValidCounterCall { node_id: HW_ID }.abi_encode(),
+            word(101),
+        );
+        for (counter, accepted) in [(100, false), (101, true)] {
+            test_vm::respond_to_view(
+                anchor_interface::wouldAcceptCounterCall {
+                    node_id: HW_ID,
+                    counter,
+                }
+                .abi_encode(),
+                word(accepted as u64),
+            );
+        }
+        let root = FixedBytes::<32>::repeat_byte(0x55);
+        test_vm::respond_to_view(
+            anchor_interface::getReceiptRootCall {}.abi_encode(),
+            root.to_vec(),
+        );
+        let digest = FixedBytes::<32>::repeat_byte(0x44);
+        for (counter, returned) in [(1, digest), (2, FixedBytes::ZERO)] {
+            test_vm::respond_to_view(
+                anchor_interface::previewDigestCall {
+                    hw_id: HW_ID,
+                    fw_hash: FW_HASH,
+                    exec_hash: EXEC_HASH,
+                    counter,
+                }
+                .abi_encode(),
+                returned.to_vec(),
+            );
+        }
+
+        assert_eq!(lens.next_valid_counter(HW_ID), Ok(101));
+        assert_eq!(lens.would_accept_counter(HW_ID, 100), Ok(false));
+        assert_eq!(lens.would_accept_counter(HW_ID, 101), Ok(true));
+        assert_eq!(lens.get_receipt_root(), Ok(root));
+        assert_eq!(
+            lens.preview_digest(HW_ID, FW_HASH, EXEC_HASH, 1),
+            Ok(digest)
+        );
+        // A zero digest is the anchor's answer when sha256 is unavailable.
+        assert_eq!(
+            lens.preview_digest(HW_ID, FW_HASH, EXEC_HASH, 2),
+            Err(LensError::HashUnavailable(HashUnavailable {}).into())
+        );
+        // An anchor that does not answer reverts the view.
+        assert!(lens.would_accept_counter(HW_ID, 102).is_err());
+    }
+
+    #[test]
+    fn simulation_reports_the_anchor_check() {
+        let lens = lens();
+        let digest = FixedBytes::<32>::repeat_byte(0x44);
+        let check = |claimed_digest| {
+            anchor_interface::checkReceiptCall {
+                hw_id: HW_ID,
+                fw_hash: FW_HASH,
+                exec_hash: EXEC_HASH,
+                counter: 1,
+                claimed_digest,
+            }
+            .abi_encode()
+        };
+        test_vm::respond_to_view(check(digest), U256::from(0).to_be_bytes_vec());
+        test_vm::respond_to_view(check(FixedBytes::ZERO), U256::from(4).to_be_bytes_vec());
+
+        assert_eq!(
+            lens.simulate_verify_receipt(HW_ID, FW_HASH, EXEC_HASH, 1, digest),
+            Ok(STATUS_OK)
+        );
+        assert_eq!(
+            lens.simulate_verify_receipt(HW_ID, FW_HASH, EXEC_HASH, 1, FixedBytes::ZERO),
+            Ok(4)
+        );
+        // An anchor that does not answer reverts the view.
+        assert!(lens
+            .simulate_verify_receipt(HW_ID, FW_HASH, EXEC_HASH, 2, digest)
+            .is_err());
+    }
+
+    #[test]
+    fn material_length_matches_preview_material() {
+        let lens = lens();
+        assert_eq!(
+            lens.material_length(),
+            Ok(receipt_codec::DIGEST_MATERIAL_LEN as u32)
+        );
+        let material = lens.preview_material(HW_ID, FW_HASH, EXEC_HASH, 1).unwrap();
+        assert_eq!(material.len(), receipt_codec::DIGEST_MATERIAL_LEN);
+        assert_eq!(
+            material.0,
+            receipt_codec::encode_material(CHAIN_ID, HW_ID, FW_HASH, EXEC_HASH, 1).to_vec()
+        );
+    }
+
+    #[test]
+    fn material_length_follows_custom_domain_tag() {
+        let lens = lens();
+        let tag = b"TENANT_DOMAIN_V2";
+        let mut tag_word = B256::ZERO;
+        tag_word[..tag.len()].copy_from_slice(tag);
+        test_vm::set_anchor_word(layout::DOMAIN_TAG.key(), tag_word);
+        set(layout::DOMAIN_TAG_LEN, tag.len() as u64);
+
+        assert_eq!(lens.get_domain_tag(), Ok(Bytes(tag.to_vec())));
+        assert_eq!(lens.material_length(), Ok(16 + 112));
+        let material = lens.preview_material(HW_ID, FW_HASH, EXEC_HASH, 1).unwrap();
+        assert_eq!(material.len(), 16 + 112);
+    }
+
+    #[test]
+    fn batch_status_views_answer_in_order_up_to_the_cap() {
+        let lens = lens();
+        let [known, unknown] = [0x0a, 0x0b].map(FixedBytes::<32>::repeat_byte);
+        for (id, status) in [(known, 1u8), (unknown, 0)] {
+            let returned = U256::from(status).to_be_bytes_vec();
+            test_vm::respond_to_view(
+                anchor_interface::isNodeAuthorizedCall { node_id: id }.abi_encode(),
+                returned.clone(),
+            );
+            test_vm::respond_to_view(
+                anchor_interface::isFirmwareApprovedCall { fw_hash: id }.abi_encode(),
+                returned,
+            );
+        }
+
+        let ids = vec![unknown, known, unknown, known];
+        let expected = vec![false, true, false, true];
+        assert_eq!(lens.are_nodes_authorized(ids.clone()), Ok(expected.clone()));
+        assert_eq!(lens.are_firmwares_approved(ids), Ok(expected));
+        assert_eq!(lens.are_nodes_authorized(Vec::new()), Ok(Vec::new()));
+
+        // Past MAX_BATCH_QUERY the answer is empty, without asking the
+        // anchor: the mock fails every call about these ids.
+        let ids = vec![FixedBytes::repeat_byte(0x0c); MAX_BATCH_QUERY + 1];
+        assert_eq!(lens.are_nodes_authorized(ids.clone()), Ok(Vec::new()));
+        assert_eq!(lens.are_firmwares_approved(ids), Ok(Vec::new()));
+        let ids = vec![known; MAX_BATCH_QUERY];
+        assert_eq!(lens.are_nodes_authorized(ids).map(|v| v.len()), Ok(512));
+    }
+
+    #[test]
+    fn get_counters_answers_in_order_up_to_the_cap() {
+        let lens = lens();
+        let [seen, unseen] = [0x0a, 0x0b].map(FixedBytes::<32>::repeat_byte);
+        for (id, counter) in [(seen, 41u64), (unseen, 0)] {
+            test_vm::respond_to_view(
+                anchor_interface::getCounterCall { node_id: id }.abi_encode(),
+                U256::from(counter).to_be_bytes_vec(),
+            );
+        }
+
+        assert_eq!(
+            lens.get_counters(vec![seen, unseen, seen]),
+            Ok(vec![41, 0, 41])
+        );
+        let ids = vec![FixedBytes::repeat_byte(0x0c); MAX_BATCH_QUERY + 1];
+        assert_eq!(lens.get_counters(ids), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn preview_hw_id_matches_the_anchor_derive_hw_id_vector() {
+        use stylus_sdk::alloy_primitives::fixed_bytes;
+
+        let efuse = FixedBytes::<16>::from(core::array::from_fn(|i| i as u8));
+        assert_eq!(
+            lens().preview_hw_id(efuse, FixedBytes::repeat_byte(0xaa)),
+            fixed_bytes!("5c6761fb3a25a437482a0fa9d857af8f41ac7fc92dccc8f8eac74779ba69f88a")
+        );
+    }
+
+    #[test]
+    fn firmware_receipt_count_reads_the_anchor_tally() {
+        let lens = lens();
+        set_at(layout::FIRMWARE_RECEIPT_COUNT, FW_HASH, U256::from(3));
+        assert_eq!(lens.get_firmware_receipt_count(FW_HASH), Ok(3));
+        assert_eq!(
+            lens.get_firmware_receipt_count(FixedBytes::repeat_byte(9)),
+            Ok(0)
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Native stand-ins for the hostios AnchorLens uses, so unit tests can
+//! drive the contract against in-memory storage.
+//!
+//! Each test thread has its own storage, standing in for the lens, and a
+//! mock anchor: its read_slot is answered from the words set with
+//! `set_anchor_word`, and its other views from the calldata registered
+//! with `respond_to_view`. The sha256 precompile is served with sha2, and
+//! every other call fails with no return data. The SDK caches msg::sender
+//! and the chain id for the whole process, so every call comes from SENDER
+//! on CHAIN_ID.
+
+use alloy_sol_types::SolCall;
+use anchor_interface::layout::Field;
+use anchor_interface::readSlotCall;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use stylus_sdk::alloy_primitives::{address, Address, B256, U256};
+use stylus_sdk::storage::StorageType;
+
+pub const SENDER: Address = address!("00000000000000000000000000000000000a11ce");
+pub const CONTRACT: Address = address!("000000000000000000000000000000000000c0de");
+pub const CHAIN_ID: u64 = 42161;
+
+const SHA256: Address = address!("0000000000000000000000000000000000000002");
+
+thread_local! {
+    static STORAGE: RefCell<HashMap<B256, B256>> = RefCell::new(HashMap::new());
+    static VIEWS: RefCell<HashMap<Vec<u8>, Vec<u8>>> = RefCell::new(HashMap::new());
+    static ANCHOR_STORAGE: RefCell<HashMap<B256, B256>> = RefCell::new(HashMap::new());
+    static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A contract rooted at slot zero of this thread's storage.
+pub fn contract<T: StorageType>() -> T {
+    unsafe { T::new(U256::ZERO, 0) }
+}
+
+/// Answer later static calls with exactly `calldata` with `returned`.
+pub fn respond_to_view(calldata: Vec<u8>, returned: Vec<u8>) {
+    VIEWS.with(|views| views.borrow_mut().insert(calldata, returned));
+}
+
+/// Have the anchor's read_slot return `word` for `slot`; unset slots
+/// read as zero.
+pub fn set_anchor_word(slot: B256, word: B256) {
+    ANCHOR_STORAGE.with(|storage| storage.borrow_mut().insert(slot, word));
+}
+
+/// Store `value` as `field` of the anchor's word at `slot`, keeping the
+/// other fields packed into that word.
+pub fn set_anchor_field(slot: B256, field: Field, value: U256) {
+    let bytes = value.to_be_bytes::<32>();
+    ANCHOR_STORAGE.with(|storage| {
+        let mut storage = storage.borrow_mut();
+        let word = storage.entry(slot).or_default();
+        word[field.offset..field.offset + field.len].copy_from_slice(&bytes[32 - field.len..]);
+    });
+}
+
+unsafe fn read<const N: usize>(ptr: *const u8) -> [u8; N] {
+    let mut out = [0u8; N];
+    std::ptr::copy_nonoverlapping(ptr, out.as_mut_ptr(), N);
+    out
+}
+
+unsafe fn write(ptr: *mut u8, bytes: &[u8]) {
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+}
+
+#[no_mangle]
+unsafe extern "C" fn storage_load_bytes32(key: *const u8, dest: *mut u8) {
+    let key = B256::from(read::<32>(key));
+    let value = STORAGE.with(|s| s.borrow().get(&key).copied().unwrap_or_default());
+    write(dest, value.as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn storage_cache_bytes32(key: *const u8, value: *const u8) {
+    let (key, value) = (B256::from(read::<32>(key)), B256::from(read::<32>(value)));
+    STORAGE.with(|s| s.borrow_mut().insert(key, value));
+}
+
+#[no_mangle]
+extern "C" fn storage_flush_cache(_clear: bool) {}
+
+#[no_mangle]
+unsafe extern "C" fn msg_sender(sender: *mut u8) {
+    write(sender, SENDER.as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn contract_address(address: *mut u8) {
+    write(address, CONTRACT.as_slice());
+}
+
+#[no_mangle]
+extern "C" fn chainid() -> u64 {
+    CHAIN_ID
+}
+
+#[no_mangle]
+unsafe extern "C" fn call_contract(
+    _contract: *const u8,
+    _calldata: *const u8,
+    _calldata_len: usize,
+    _value: *const u8,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    *return_data_len = 0;
+    1
+}
+
+#[no_mangle]
+unsafe extern "C" fn delegate_call_contract(
+    _contract: *const u8,
+    _calldata: *const u8,
+    _calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    *return_data_len = 0;
+    1
+}
+
+#[no_mangle]
+unsafe extern "C" fn static_call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    use sha2::{Digest, Sha256};
+
+    let calldata = std::slice::from_raw_parts(calldata, calldata_len);
+    let output = if Address::from(read::<20>(contract)) == SHA256 {
+        Some(Sha256::digest(calldata).to_vec())
+    } else {
+        match readSlotCall::abi_decode(calldata, true) {
+            Ok(call) => {
+                let word = ANCHOR_STORAGE.with(|s| s.borrow().get(&call.slot).copied());
+                Some(readSlotCall::abi_encode_returns(&(
+                    word.unwrap_or_default(),
+                )))
+            }
+            Err(_) => VIEWS.with(|views| views.borrow().get(calldata).cloned()),
+        }
+    };
+    let Some(output) = output else {
+        *return_data_len = 0;
+        return 1;
+    };
+    *return_data_len = output.len();
+    RETURN_DATA.with(|data| *data.borrow_mut() = output);
+    0
+}
+
+#[no_mangle]
+unsafe extern "C" fn read_return_data(dest: *mut u8, offset: usize, size: usize) -> usize {
+    RETURN_DATA.with(|data| {
+        let data = data.borrow();
+        let start = offset.min(data.len());
+        let end = offset.saturating_add(size).min(data.len());
+        write(dest, &data[start..end]);
+        end - start
+    })
+}
+
+#[no_mangle]
+extern "C" fn return_data_size() -> usize {
+    RETURN_DATA.with(|data| data.borrow().len())
+}
@@ -0,0 +1,257 @@
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(feature = "export-abi")), no_std)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use anchor_interface::IHardwareAnchor;
+use stylus_sdk::alloy_primitives::{Address, FixedBytes};
+use stylus_sdk::call::Call;
+use stylus_sdk::{evm, msg, prelude::*};
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod test_vm;
+
+// Deployed builds leave out std: a panic traps, which reverts the call.
+#[cfg(all(target_arch = "wasm32", not(feature = "export-abi")))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+sol! {
+    error AlreadyInitialized();
+    error UnauthorizedCaller();
+    error InvalidOwner();
+    error UnknownRegion();
+
+    event RegionRouted(bytes1 indexed region, address anchor);
+}
+
+sol_storage! {
+    /// Front door for per-region anchors: routes each receipt to the anchor
+    /// registered for the first byte of its hw_id.
+    #[entrypoint]
+    pub struct AnchorRouter {
+        address owner;
+        // Anchor serving each hw_id prefix (zero = unrouted).
+        mapping(bytes1 => address) region_anchor;
+    }
+}
+
+#[derive(SolidityError)]
+pub enum RouterError {
+    AlreadyInitialized(AlreadyInitialized),
+    UnauthorizedCaller(UnauthorizedCaller),
+    InvalidOwner(InvalidOwner),
+    UnknownRegion(UnknownRegion),
+}
+
+#[public]
+impl AnchorRouter {
+    pub fn initialize(&mut self) -> Result<(), RouterError> {
+        if self.owner.get() != Address::ZERO {
+            return Err(RouterError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        self.owner.set(msg::sender());
+        Ok(())
+    }
+
+    /// Route hw_ids starting with `region` to `anchor`; the zero address
+    /// removes the route.
+    pub fn set_region_anchor(
+        &mut self,
+        region: FixedBytes<1>,
+        anchor: Address,
+    ) -> Result<(), RouterError> {
+        if msg::sender() != self.owner.get() {
+            return Err(RouterError::UnauthorizedCaller(UnauthorizedCaller {}));
+        }
+        self.region_anchor.insert(region, anchor);
+        evm::log(RegionRouted { region, anchor });
+        Ok(())
+    }
+
+    /// Forward to the regional anchor's verify_receipt. The anchor's revert
+    /// data (e.g. ReplayDetected) is bubbled up unchanged; an unrouted
+    /// prefix reverts with UnknownRegion.
+    pub fn verify_receipt(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<(), Vec<u8>> {
+        let anchor = self.region_anchor.get(Self::region_of(hw_id));
+        if anchor == Address::ZERO {
+            return Err(RouterError::UnknownRegion(UnknownRegion {}).into());
+        }
+        IHardwareAnchor::new(anchor).verify_receipt(
+            Call::new_in(self),
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+            claimed_digest,
+        )?;
+        Ok(())
+    }
+
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), RouterError> {
+        if msg::sender() != self.owner.get() {
+            return Err(RouterError::UnauthorizedCaller(UnauthorizedCaller {}));
+        }
+        if new_owner == Address::ZERO {
+            return Err(RouterError::InvalidOwner(InvalidOwner {}));
+        }
+        self.owner.set(new_owner);
+        Ok(())
+    }
+
+    pub fn get_owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    pub fn get_region_anchor(&self, region: FixedBytes<1>) -> Address {
+        self.region_anchor.get(region)
+    }
+
+    /// Anchor a receipt for `hw_id` would be routed to (zero if unrouted).
+    pub fn anchor_for(&self, hw_id: FixedBytes<32>) -> Address {
+        self.region_anchor.get(Self::region_of(hw_id))
+    }
+}
+
+impl AnchorRouter {
+    fn region_of(hw_id: FixedBytes<32>) -> FixedBytes<1> {
+        FixedBytes([hw_id[0]])
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::{SolCall, SolError, SolEvent};
+
+    sol! {
+        function verifyReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external;
+    }
+
+    fn router() -> AnchorRouter {
+        let mut router: AnchorRouter = test_vm::contract();
+        assert!(router.initialize().is_ok());
+        router
+    }
+
+    #[test]
+    fn regions_route_by_the_first_hw_id_byte() {
+        let mut router = router();
+        let (eu, us) = (FixedBytes([0x01]), FixedBytes([0x02]));
+        let eu_anchor = Address::repeat_byte(0xe0);
+        let hw_id = FixedBytes::<32>::left_padding_from(&[0x01, 0xff]);
+        assert_eq!(AnchorRouter::region_of(hw_id), FixedBytes([0x00]));
+        let hw_id = FixedBytes::<32>::right_padding_from(&[0x01, 0xff]);
+        assert_eq!(AnchorRouter::region_of(hw_id), eu);
+
+        assert_eq!(router.anchor_for(hw_id), Address::ZERO);
+        test_vm::take_logs();
+        assert!(router.set_region_anchor(eu, eu_anchor).is_ok());
+        assert_eq!(router.get_region_anchor(eu), eu_anchor);
+        assert_eq!(router.get_region_anchor(us), Address::ZERO);
+        assert_eq!(router.anchor_for(hw_id), eu_anchor);
+        let event = RegionRouted {
+            region: eu,
+            anchor: eu_anchor,
+        };
+        let topics: Vec<_> = event.encode_topics().into_iter().map(|t| t.0).collect();
+        assert_eq!(test_vm::take_logs(), vec![(topics, event.encode_data())]);
+
+        // The zero address removes the route again.
+        assert!(router.set_region_anchor(eu, Address::ZERO).is_ok());
+        assert_eq!(router.anchor_for(hw_id), Address::ZERO);
+    }
+
+    #[test]
+    fn unrouted_prefixes_revert_with_unknown_region() {
+        let mut router = router();
+        let hw_id = FixedBytes::<32>::repeat_byte(0x07);
+        let result = router.verify_receipt(hw_id, hw_id, hw_id, 1, hw_id);
+        assert_eq!(result, Err(UnknownRegion {}.abi_encode()));
+        assert!(test_vm::take_calls().is_empty());
+    }
+
+    #[test]
+    fn receipts_are_forwarded_and_reverts_bubble_up() {
+        let mut router = router();
+        let (eu_anchor, us_anchor) = (Address::repeat_byte(0xe0), Address::repeat_byte(0xa0));
+        assert!(router
+            .set_region_anchor(FixedBytes([0x01]), eu_anchor)
+            .is_ok());
+        assert!(router
+            .set_region_anchor(FixedBytes([0x02]), us_anchor)
+            .is_ok());
+        let [eu_hw_id, us_hw_id, fw_hash, exec_hash, digest] =
+            [0x01u8, 0x02, 0x03, 0x04, 0x05].map(FixedBytes::<32>::repeat_byte);
+
+        // Each hw_id prefix reaches its own regional anchor.
+        test_vm::respond_with(true, Vec::new());
+        for (hw_id, counter) in [(eu_hw_id, 9), (us_hw_id, 3), (eu_hw_id, 10)] {
+            assert!(router
+                .verify_receipt(hw_id, fw_hash, exec_hash, counter, digest)
+                .is_ok());
+        }
+        let call = |hw_id, counter| {
+            verifyReceiptCall {
+                hw_id,
+                fw_hash,
+                exec_hash,
+                counter,
+                claimed_digest: digest,
+            }
+            .abi_encode()
+        };
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![
+                (eu_anchor, call(eu_hw_id, 9)),
+                (us_anchor, call(us_hw_id, 3)),
+                (eu_anchor, call(eu_hw_id, 10)),
+            ]
+        );
+
+        // The anchor's revert data reaches the caller unchanged.
+        let replay = [0x5a, 0x1e, 0xc7, 0xed].to_vec();
+        test_vm::respond_with(false, replay.clone());
+        assert_eq!(
+            router.verify_receipt(us_hw_id, fw_hash, exec_hash, 3, digest),
+            Err(replay)
+        );
+        assert_eq!(test_vm::take_calls(), vec![(us_anchor, call(us_hw_id, 3))]);
+    }
+
+    #[test]
+    fn only_the_owner_manages_routes() {
+        let mut router = router();
+        assert_eq!(router.get_owner(), test_vm::SENDER);
+        assert!(matches!(
+            router.initialize(),
+            Err(RouterError::AlreadyInitialized(_))
+        ));
+        assert!(matches!(
+            router.transfer_ownership(Address::ZERO),
+            Err(RouterError::InvalidOwner(_))
+        ));
+        assert!(router
+            .transfer_ownership(Address::repeat_byte(0xbb))
+            .is_ok());
+        assert!(matches!(
+            router.set_region_anchor(FixedBytes([0x01]), Address::repeat_byte(0xe0)),
+            Err(RouterError::UnauthorizedCaller(_))
+        ));
+        assert!(matches!(
+            router.transfer_ownership(test_vm::SENDER),
+            Err(RouterError::UnauthorizedCaller(_))
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+//! Native stand-ins for the hostios AnchorRouter uses, so unit tests can
+//! drive the router against in-memory storage.
+//!
+//! Each test thread has its own storage and log. Calls through
+//! call_contract are recorded and answered with the outcome set by
+//! `respond_with`, standing in for the regional anchor; static and
+//! delegate calls fail with no return data. The SDK caches msg::sender for
+//! the whole process, so every call comes from SENDER.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use stylus_sdk::alloy_primitives::{address, Address, B256, U256};
+use stylus_sdk::storage::StorageType;
+
+pub const SENDER: Address = address!("00000000000000000000000000000000000a11ce");
+pub const CONTRACT: Address = address!("000000000000000000000000000000000000c0de");
+
+/// An emitted log: topics, then ABI-encoded data.
+pub type Log = (Vec<B256>, Vec<u8>);
+
+thread_local! {
+    static STORAGE: RefCell<HashMap<B256, B256>> = RefCell::new(HashMap::new());
+    static LOGS: RefCell<Vec<Log>> = const { RefCell::new(Vec::new()) };
+    static CALLS: RefCell<Vec<(Address, Vec<u8>)>> = const { RefCell::new(Vec::new()) };
+    static RESPONSE: RefCell<(bool, Vec<u8>)> = const { RefCell::new((true, Vec::new())) };
+    static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A contract rooted at slot zero of this thread's storage.
+pub fn contract<T: StorageType>() -> T {
+    unsafe { T::new(U256::ZERO, 0) }
+}
+
+/// Logs emitted on this thread since the last call.
+pub fn take_logs() -> Vec<Log> {
+    LOGS.with(|logs| logs.take())
+}
+
+/// External calls made on this thread since the last call: target, then
+/// calldata.
+pub fn take_calls() -> Vec<(Address, Vec<u8>)> {
+    CALLS.with(|calls| calls.take())
+}
+
+/// Answer later external calls with `data`, as return data when `success`
+/// and as revert data otherwise.
+pub fn respond_with(success: bool, data: Vec<u8>) {
+    RESPONSE.with(|response| *response.borrow_mut() = (success, data));
+}
+
+unsafe fn read<const N: usize>(ptr: *const u8) -> [u8; N] {
+    let mut out = [0u8; N];
+    std::ptr::copy_nonoverlapping(ptr, out.as_mut_ptr(), N);
+    out
+}
+
+unsafe fn write(ptr: *mut u8, bytes: &[u8]) {
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+}
+
+#[no_mangle]
+unsafe extern "C" fn storage_load_bytes32(key: *const u8, dest: *mut u8) {
+    let key = B256::from(read::<32>(key));
+    let value = STORAGE.with(|s| s.borrow().get(&key).copied().unwrap_or_default());
+    write(dest, value.as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn storage_cache_bytes32(key: *const u8, value: *const u8) {
+    let (key, value) = (B256::from(read::<32>(key)), B256::from(read::<32>(value)));
+    STORAGE.with(|s| s.borrow_mut().insert(key, value));
+}
+
+#[no_mangle]
+extern "C" fn storage_flush_cache(_clear: bool) {}
+
+#[no_mangle]
+unsafe extern "C" fn emit_log(data: *const u8, len: usize, topics: usize) {
+    let bytes = std::slice::from_raw_parts(data, len);
+    let (topic_bytes, data) = bytes.split_at(topics * 32);
+    let topics = topic_bytes.chunks(32).map(B256::from_slice).collect();
+    LOGS.with(|logs| logs.borrow_mut().push((topics, data.to_vec())));
+}
+
+#[no_mangle]
+unsafe extern "C" fn msg_sender(sender: *mut u8) {
+    write(sender, SENDER.as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn contract_address(address: *mut u8) {
+    write(address, CONTRACT.as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    _value: *const u8,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    let target = Address::from(read::<20>(contract));
+    let calldata = std::slice::from_raw_parts(calldata, calldata_len).to_vec();
+    CALLS.with(|calls| calls.borrow_mut().push((target, calldata)));
+    let (success, output) = RESPONSE.with(|response| response.borrow().clone());
+    *return_data_len = output.len();
+    RETURN_DATA.with(|data| *data.borrow_mut() = output);
+    u8::from(!success)
+}
+
+#[no_mangle]
+unsafe extern "C" fn delegate_call_contract(
+    _contract: *const u8,
+    _calldata: *const u8,
+    _calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    *return_data_len = 0;
+    1
+}
+
+#[no_mangle]
+unsafe extern "C" fn static_call_contract(
+    _contract: *const u8,
+    _calldata: *const u8,
+    _calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    *return_data_len = 0;
+    1
+}
+
+#[no_mangle]
+unsafe extern "C" fn read_return_data(dest: *mut u8, offset: usize, size: usize) -> usize {
+    RETURN_DATA.with(|data| {
+        let data = data.borrow();
+        let start = offset.min(data.len());
+        let end = offset.saturating_add(size).min(data.len());
+        write(dest, &data[start..end]);
+        end - start
+    })
+}
+
+#[no_mangle]
+extern "C" fn return_data_size() -> usize {
+    RETURN_DATA.with(|data| data.borrow().len())
+}
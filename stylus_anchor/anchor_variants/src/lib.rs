@@ -0,0 +1,816 @@
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(all(target_arch = "wasm32", not(feature = "export-abi")), no_std)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_sol_types::sol;
+use anchor_interface::receipt_codec::{self, DOMAIN};
+use anchor_interface::IHardwareAnchor;
+use stylus_sdk::abi::Bytes;
+use stylus_sdk::alloy_primitives::{address, keccak256, Address, FixedBytes, Uint};
+use stylus_sdk::call::{static_call, Call};
+use stylus_sdk::function_selector;
+use stylus_sdk::{block, evm, msg, prelude::*};
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod test_vm;
+
+// Deployed builds leave out std: a panic traps, which reverts the call.
+#[cfg(all(target_arch = "wasm32", not(feature = "export-abi")))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+type U8 = Uint<8, 1>;
+type U64 = Uint<64, 1>;
+
+// Digest material layouts selectable through verify_receipt_v; only
+// layout 1 is accepted until the owner widens the mask.
+const LAYOUT_V1: u8 = 1;
+const LAYOUT_V2: u8 = 2;
+const DEFAULT_ACCEPTED_LAYOUTS: u8 = 1 << (LAYOUT_V1 - 1);
+
+// The anchor's digest_algo for keccak256; its other value selects sha256.
+const DIGEST_KECCAK256: u8 = 0;
+
+// sha256 precompile: input is the message, output the 32-byte hash.
+const SHA256: Address = address!("0000000000000000000000000000000000000002");
+
+sol! {
+    error AlreadyInitialized();
+    error UnauthorizedCaller();
+    error InvalidOwner();
+    error DigestMismatch();
+    error HashUnavailable();
+    error DomainNotApproved();
+    error DomainTagReserved();
+    error ChainIdNotAccepted();
+    error LayoutNotAccepted();
+    error StaleReceipt();
+    error FutureBlockRef();
+
+    event ConfigChanged(uint64 seq, bytes4 indexed selector, address indexed by);
+}
+
+sol_storage! {
+    /// Receipt formats beyond a StylusHardwareAnchor's own verify_receipt:
+    /// tenant domains, foreign chain ids, alternative material layouts and
+    /// block-stamped receipts. Each path rebuilds the digest under the
+    /// anchor's domain tag and digest_algo and matches it here, then hands
+    /// the receipt to the anchor's anchor_receipt, which applies every
+    /// node, firmware, replay and rate-limit check and records it. The
+    /// anchor's owner must first register this contract with
+    /// set_verifier.
+    #[entrypoint]
+    pub struct AnchorVariants {
+        // The anchor receipts are handed to.
+        address anchor;
+        // Manages the configuration below.
+        address owner;
+        // Tenant domain tags verify_receipt_domain accepts, keyed by
+        // keccak256(tag).
+        mapping(bytes32 => bool) approved_domains;
+        // Chain ids verify_receipt_for_chain accepts besides the current
+        // one, e.g. the old chain of a device mid-migration.
+        mapping(uint64 => bool) accepted_chain_ids;
+        // Layouts accepted by verify_receipt_v; 0 = DEFAULT_ACCEPTED_LAYOUTS.
+        uint8 accepted_layouts;
+        // Blocks a verify_receipt_fresh block_ref may trail the current
+        // block by (0 = no limit).
+        uint64 max_block_staleness;
+        // Sequence number of the last emitted event; every event carries
+        // the next one as its first field.
+        uint64 event_seq;
+    }
+}
+
+#[derive(SolidityError)]
+pub enum VariantsError {
+    AlreadyInitialized(AlreadyInitialized),
+    UnauthorizedCaller(UnauthorizedCaller),
+    InvalidOwner(InvalidOwner),
+    DigestMismatch(DigestMismatch),
+    HashUnavailable(HashUnavailable),
+    DomainNotApproved(DomainNotApproved),
+    DomainTagReserved(DomainTagReserved),
+    ChainIdNotAccepted(ChainIdNotAccepted),
+    LayoutNotAccepted(LayoutNotAccepted),
+    StaleReceipt(StaleReceipt),
+    FutureBlockRef(FutureBlockRef),
+}
+
+/// Material the anchor's verify_receipt hashes under `tag` for a receipt
+/// on `chain_id`: the fixed layout for the default tag, otherwise the same
+/// fields after the custom tag.
+fn receipt_material(
+    tag: &[u8],
+    chain_id: u64,
+    hw_id: FixedBytes<32>,
+    fw_hash: FixedBytes<32>,
+    exec_hash: FixedBytes<32>,
+    counter: u64,
+) -> Vec<u8> {
+    if tag == DOMAIN.as_slice() {
+        return receipt_codec::encode_material(chain_id, hw_id, fw_hash, exec_hash, counter)
+            .to_vec();
+    }
+    receipt_codec::encode_tagged_material(tag, chain_id, hw_id, fw_hash, exec_hash, counter)
+}
+
+/// Hash digest material the way the anchor does under `algo`.
+fn hash_material(algo: u8, material: &[u8]) -> Result<FixedBytes<32>, VariantsError> {
+    if algo == DIGEST_KECCAK256 {
+        return Ok(keccak256(material));
+    }
+    let output = static_call(Call::new(), SHA256, material)
+        .map_err(|_| VariantsError::HashUnavailable(HashUnavailable {}))?;
+    if output.len() != 32 {
+        return Err(VariantsError::HashUnavailable(HashUnavailable {}));
+    }
+    Ok(FixedBytes::from_slice(&output))
+}
+
+#[public]
+impl AnchorVariants {
+    /// One-time initialization: the sender becomes owner of the variants
+    /// in front of `anchor`.
+    pub fn initialize(&mut self, anchor: Address) -> Result<(), VariantsError> {
+        if self.owner.get() != Address::ZERO {
+            return Err(VariantsError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        self.owner.set(msg::sender());
+        self.anchor.set(anchor);
+        Ok(())
+    }
+
+    /// verify_receipt for a tenant of a shared deployment: the digest
+    /// material is `domain_tag` || chain_id_be8 || hw_id || fw_hash ||
+    /// exec_hash || counter_be8, hashed with the anchor's digest_algo, and
+    /// the tag must be owner-approved (approve_domain) or the call reverts
+    /// with DomainNotApproved. Replay counters and the replay window are
+    /// kept per (domain, hw_id) in the anchor, so tenants sharing a node do
+    /// not consume each other's counters, and are separate from
+    /// verify_receipt's; the anchor's own tag cannot be approved (see
+    /// approve_domain).
+    /// Authorization, firmware, blocklist, counter floor, re-anchor flag
+    /// and rate limit stay per node.
+    pub fn verify_receipt_domain(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+        domain_tag: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        let namespace = keccak256(domain_tag.as_slice());
+        if !self.approved_domains.get(namespace) {
+            return Err(VariantsError::DomainNotApproved(DomainNotApproved {}).into());
+        }
+        let material = receipt_codec::encode_tagged_material(
+            &domain_tag,
+            block::chainid(),
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+        );
+        let digest = self.match_digest(&material, claimed_digest)?;
+
+        self.anchor_receipt(hw_id, namespace, fw_hash, exec_hash, counter, digest)
+    }
+
+    /// verify_receipt for a device programmed with another chain id:
+    /// `chain_id` replaces block::chainid() in the digest material and
+    /// must be the current chain or one the owner has accepted.
+    pub fn verify_receipt_for_chain(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+        chain_id: u64,
+    ) -> Result<(), Vec<u8>> {
+        if chain_id != block::chainid() && !self.accepted_chain_ids.get(U64::from(chain_id)) {
+            return Err(VariantsError::ChainIdNotAccepted(ChainIdNotAccepted {}).into());
+        }
+        let tag = self.domain_tag()?;
+        let material = receipt_material(&tag, chain_id, hw_id, fw_hash, exec_hash, counter);
+        let digest = self.match_digest(&material, claimed_digest)?;
+
+        self.anchor_receipt(hw_id, FixedBytes::ZERO, fw_hash, exec_hash, counter, digest)
+    }
+
+    /// verify_receipt with an explicit digest material layout, for
+    /// migrating fleets between layouts. Layout 1 is verify_receipt's own
+    /// material; layout 2 moves the counter forward:
+    /// tag || chain_id_be8 || counter_be8 || hw_id || fw_hash || exec_hash.
+    /// The layout must be in the owner-managed accepted set.
+    pub fn verify_receipt_v(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+        layout_version: u8,
+    ) -> Result<(), Vec<u8>> {
+        if !(LAYOUT_V1..=LAYOUT_V2).contains(&layout_version)
+            || self.get_accepted_layouts() & (1 << (layout_version - 1)) == 0
+        {
+            return Err(VariantsError::LayoutNotAccepted(LayoutNotAccepted {}).into());
+        }
+
+        let tag = self.domain_tag()?;
+        let chain_id = block::chainid();
+        let material = if layout_version == LAYOUT_V1 {
+            receipt_material(&tag, chain_id, hw_id, fw_hash, exec_hash, counter)
+        } else {
+            let mut material = Vec::with_capacity(tag.len() + 112);
+            material.extend_from_slice(&tag);
+            material.extend_from_slice(&chain_id.to_be_bytes());
+            material.extend_from_slice(&counter.to_be_bytes());
+            material.extend_from_slice(hw_id.as_slice());
+            material.extend_from_slice(fw_hash.as_slice());
+            material.extend_from_slice(exec_hash.as_slice());
+            material
+        };
+        let digest = self.match_digest(&material, claimed_digest)?;
+
+        self.anchor_receipt(hw_id, FixedBytes::ZERO, fw_hash, exec_hash, counter, digest)
+    }
+
+    /// verify_receipt for receipts stamped with the block they were produced
+    /// near. `block_ref` is appended to the tagged digest material (after
+    /// the counter, as 8 big-endian bytes) so it cannot be altered, and the
+    /// receipt is rejected if it trails the current block by more than
+    /// max_block_staleness or references a future block.
+    pub fn verify_receipt_fresh(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        block_ref: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<(), Vec<u8>> {
+        let current = block::number();
+        if block_ref > current {
+            return Err(VariantsError::FutureBlockRef(FutureBlockRef {}).into());
+        }
+        let max_staleness = self.get_max_block_staleness();
+        if max_staleness != 0 && current - block_ref > max_staleness {
+            return Err(VariantsError::StaleReceipt(StaleReceipt {}).into());
+        }
+
+        let mut material = receipt_codec::encode_tagged_material(
+            &self.domain_tag()?,
+            block::chainid(),
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+        );
+        material.extend_from_slice(&block_ref.to_be_bytes());
+        let digest = self.match_digest(&material, claimed_digest)?;
+
+        self.anchor_receipt(hw_id, FixedBytes::ZERO, fw_hash, exec_hash, counter, digest)
+    }
+
+    /// Let verify_receipt_domain accept `domain_tag`. Revoking a tag keeps
+    /// its counters, so re-approving it cannot reopen replays. The anchor's
+    /// own tag reverts with DomainTagReserved: its receipts carry the same
+    /// digests verify_receipt accepts, and under their own namespace each
+    /// could be replayed on the other path.
+    pub fn approve_domain(&mut self, domain_tag: Bytes) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        if domain_tag.0 == self.domain_tag()? {
+            return Err(VariantsError::DomainTagReserved(DomainTagReserved {}).into());
+        }
+        self.approved_domains
+            .insert(keccak256(domain_tag.as_slice()), true);
+        self.config_changed(function_selector!("approveDomain", Bytes));
+        Ok(())
+    }
+
+    pub fn revoke_domain(&mut self, domain_tag: Bytes) -> Result<(), VariantsError> {
+        self.only_owner()?;
+        self.approved_domains
+            .delete(keccak256(domain_tag.as_slice()));
+        self.config_changed(function_selector!("revokeDomain", Bytes));
+        Ok(())
+    }
+
+    pub fn accept_chain_id(&mut self, chain_id: u64) -> Result<(), VariantsError> {
+        self.only_owner()?;
+        self.accepted_chain_ids.insert(U64::from(chain_id), true);
+        self.config_changed(function_selector!("acceptChainId", u64));
+        Ok(())
+    }
+
+    pub fn revoke_chain_id(&mut self, chain_id: u64) -> Result<(), VariantsError> {
+        self.only_owner()?;
+        self.accepted_chain_ids.delete(U64::from(chain_id));
+        self.config_changed(function_selector!("revokeChainId", u64));
+        Ok(())
+    }
+
+    /// Set the layouts verify_receipt_v accepts (bit v - 1 = layout v).
+    /// 0 restores the default of layout 1 only.
+    pub fn set_accepted_layouts(&mut self, mask: u8) -> Result<(), VariantsError> {
+        self.only_owner()?;
+        self.accepted_layouts.set(U8::from(mask));
+        self.config_changed(function_selector!("setAcceptedLayouts", u8));
+        Ok(())
+    }
+
+    /// Set how many blocks a verify_receipt_fresh block_ref may trail the
+    /// current block by. 0 disables the staleness limit.
+    pub fn set_max_block_staleness(&mut self, blocks: u64) -> Result<(), VariantsError> {
+        self.only_owner()?;
+        self.max_block_staleness.set(U64::from(blocks));
+        self.config_changed(function_selector!("setMaxBlockStaleness", u64));
+        Ok(())
+    }
+
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), VariantsError> {
+        self.only_owner()?;
+        if new_owner == Address::ZERO {
+            return Err(VariantsError::InvalidOwner(InvalidOwner {}));
+        }
+        self.owner.set(new_owner);
+        self.config_changed(function_selector!("transferOwnership", Address));
+        Ok(())
+    }
+
+    pub fn get_anchor(&self) -> Address {
+        self.anchor.get()
+    }
+
+    pub fn get_owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    pub fn is_domain_approved(&self, domain_tag: Bytes) -> bool {
+        self.approved_domains.get(keccak256(domain_tag.as_slice()))
+    }
+
+    pub fn is_chain_id_accepted(&self, chain_id: u64) -> bool {
+        self.accepted_chain_ids.get(U64::from(chain_id))
+    }
+
+    /// Bitmask of layouts verify_receipt_v accepts (bit v - 1 = layout v).
+    pub fn get_accepted_layouts(&self) -> u8 {
+        match self.accepted_layouts.get().to::<u8>() {
+            0 => DEFAULT_ACCEPTED_LAYOUTS,
+            mask => mask,
+        }
+    }
+
+    pub fn get_max_block_staleness(&self) -> u64 {
+        self.max_block_staleness.get().to()
+    }
+
+    /// Sequence number of the last event emitted (0 = none yet).
+    pub fn get_event_seq(&self) -> u64 {
+        self.event_seq.get().to()
+    }
+}
+
+impl AnchorVariants {
+    fn only_owner(&self) -> Result<(), VariantsError> {
+        if msg::sender() != self.owner.get() {
+            return Err(VariantsError::UnauthorizedCaller(UnauthorizedCaller {}));
+        }
+        Ok(())
+    }
+
+    /// The anchor's domain tag (DOMAIN when it has none of its own).
+    fn domain_tag(&self) -> Result<Vec<u8>, Vec<u8>> {
+        Ok(IHardwareAnchor::new(self.anchor.get()).get_domain_tag(Call::new())?)
+    }
+
+    /// Hash `material` with the anchor's digest_algo and match it against
+    /// the claimed digest.
+    fn match_digest(
+        &self,
+        material: &[u8],
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<FixedBytes<32>, Vec<u8>> {
+        let algo = IHardwareAnchor::new(self.anchor.get()).get_digest_algo(Call::new())?;
+        let digest = hash_material(algo, material)?;
+        if digest != claimed_digest {
+            return Err(VariantsError::DigestMismatch(DigestMismatch {}).into());
+        }
+        Ok(digest)
+    }
+
+    /// Hand a matched receipt to the anchor; its revert data (e.g.
+    /// ReplayDetected) is bubbled up unchanged.
+    fn anchor_receipt(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        namespace: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        digest: FixedBytes<32>,
+    ) -> Result<(), Vec<u8>> {
+        IHardwareAnchor::new(self.anchor.get()).anchor_receipt(
+            Call::new_in(self),
+            hw_id,
+            namespace,
+            fw_hash,
+            exec_hash,
+            counter,
+            digest,
+        )?;
+        Ok(())
+    }
+
+    fn next_event_seq(&mut self) -> u64 {
+        let seq = self.event_seq.get() + U64::from(1);
+        self.event_seq.set(seq);
+        seq.to()
+    }
+
+    /// Logs a mutation that has no dedicated event, keyed by the selector
+    /// of the public method that made it.
+    fn config_changed(&mut self, selector: [u8; 4]) {
+        evm::log(ConfigChanged {
+            seq: self.next_event_seq(),
+            selector: selector.into(),
+            by: msg::sender(),
+        });
+    }
+}
+
+#[cfg(all(test, not(feature = "export-abi")))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::{SolCall, SolError, SolEvent};
+    use anchor_interface::layout;
+    use test_vm::{BLOCK_NUMBER, CHAIN_ID};
+
+    sol! {
+        function anchorReceipt(bytes32 hw_id, bytes32 namespace, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 digest) external;
+        error ReplayDetected();
+    }
+
+    const ANCHOR: Address = Address::repeat_byte(0xa0);
+    const HW_ID: FixedBytes<32> = FixedBytes::repeat_byte(1);
+    const FW_HASH: FixedBytes<32> = FixedBytes::repeat_byte(2);
+    const EXEC_HASH: FixedBytes<32> = FixedBytes::repeat_byte(3);
+
+    /// Point the mock anchor at `tag` and `algo`.
+    fn configure_anchor(tag: &[u8], algo: u8) {
+        let byte_word = |value: u8| FixedBytes::<32>::with_last_byte(value);
+        let mut tag_word = FixedBytes::<32>::ZERO;
+        let tag_len = if tag == DOMAIN {
+            0
+        } else {
+            tag_word[..tag.len()].copy_from_slice(tag);
+            tag.len() as u8
+        };
+        test_vm::set_anchor_word(layout::DOMAIN_TAG.key(), tag_word);
+        test_vm::set_anchor_word(layout::DOMAIN_TAG_LEN.key(), byte_word(tag_len));
+        test_vm::set_anchor_word(layout::DIGEST_ALGO.key(), byte_word(algo));
+    }
+
+    /// Initialized variants in front of an anchor with the default domain
+    /// tag and keccak256 digests that accepts every receipt.
+    fn variants() -> AnchorVariants {
+        let mut variants: AnchorVariants = test_vm::contract();
+        assert!(variants.initialize(ANCHOR).is_ok());
+        configure_anchor(DOMAIN, DIGEST_KECCAK256);
+        test_vm::respond_with(true, Vec::new());
+        variants
+    }
+
+    /// The anchor call handing over a receipt for HW_ID.
+    fn anchored(
+        namespace: FixedBytes<32>,
+        counter: u64,
+        digest: FixedBytes<32>,
+    ) -> (Address, Vec<u8>) {
+        let call = anchorReceiptCall {
+            hw_id: HW_ID,
+            namespace,
+            fw_hash: FW_HASH,
+            exec_hash: EXEC_HASH,
+            counter,
+            digest,
+        };
+        (ANCHOR, call.abi_encode())
+    }
+
+    fn emitted<E: SolEvent>(event: E) -> (Vec<FixedBytes<32>>, Vec<u8>) {
+        let topics = event.encode_topics().into_iter().map(|t| t.0).collect();
+        (topics, event.encode_data())
+    }
+
+    #[test]
+    fn domain_receipts_need_approval_and_a_namespace() {
+        let mut variants = variants();
+        let [tenant_a, tenant_b] = [b"TENANT_A".as_slice(), b"TENANT_B"].map(|t| Bytes(t.to_vec()));
+        let digest = |tag: &Bytes, counter| {
+            keccak256(receipt_codec::encode_tagged_material(
+                tag, CHAIN_ID, HW_ID, FW_HASH, EXEC_HASH, counter,
+            ))
+        };
+        let verify = |variants: &mut AnchorVariants, counter, digest, tag: &Bytes| {
+            variants.verify_receipt_domain(HW_ID, FW_HASH, EXEC_HASH, counter, digest, tag.clone())
+        };
+
+        let result = verify(&mut variants, 1, digest(&tenant_a, 1), &tenant_a);
+        assert_eq!(result, Err(DomainNotApproved {}.abi_encode()));
+        assert!(variants.approve_domain(tenant_a.clone()).is_ok());
+        assert!(variants.approve_domain(tenant_b.clone()).is_ok());
+        assert!(variants.is_domain_approved(tenant_a.clone()));
+
+        // The tag is bound into the digest, and each tenant anchors under
+        // its own namespace.
+        let result = verify(&mut variants, 5, digest(&tenant_b, 5), &tenant_a);
+        assert_eq!(result, Err(DigestMismatch {}.abi_encode()));
+        assert!(test_vm::take_calls().is_empty());
+        assert_eq!(
+            verify(&mut variants, 5, digest(&tenant_a, 5), &tenant_a),
+            Ok(())
+        );
+        assert_eq!(
+            verify(&mut variants, 1, digest(&tenant_b, 1), &tenant_b),
+            Ok(())
+        );
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![
+                anchored(keccak256(&tenant_a), 5, digest(&tenant_a, 5)),
+                anchored(keccak256(&tenant_b), 1, digest(&tenant_b, 1)),
+            ]
+        );
+
+        // The anchor's verdict is bubbled up, and revocation closes the
+        // domain.
+        test_vm::respond_with(false, ReplayDetected {}.abi_encode());
+        let result = verify(&mut variants, 5, digest(&tenant_a, 5), &tenant_a);
+        assert_eq!(result, Err(ReplayDetected {}.abi_encode()));
+        assert!(variants.revoke_domain(tenant_a.clone()).is_ok());
+        let result = verify(&mut variants, 6, digest(&tenant_a, 6), &tenant_a);
+        assert_eq!(result, Err(DomainNotApproved {}.abi_encode()));
+    }
+
+    #[test]
+    fn the_anchor_tag_is_not_a_tenant_domain() {
+        // A receipt verify_receipt accepted must not be anchored again
+        // under a tenant namespace with its own counters.
+        let mut variants = variants();
+        let custom = b"TENANT_DOMAIN_V2";
+        for tag in [DOMAIN, custom.as_slice()] {
+            configure_anchor(tag, DIGEST_KECCAK256);
+            let tag = Bytes(tag.to_vec());
+            assert_eq!(
+                variants.approve_domain(tag.clone()),
+                Err(DomainTagReserved {}.abi_encode())
+            );
+            assert!(!variants.is_domain_approved(tag.clone()));
+
+            let digest = keccak256(receipt_codec::encode_tagged_material(
+                &tag, CHAIN_ID, HW_ID, FW_HASH, EXEC_HASH, 1,
+            ));
+            let result = variants.verify_receipt_domain(HW_ID, FW_HASH, EXEC_HASH, 1, digest, tag);
+            assert_eq!(result, Err(DomainNotApproved {}.abi_encode()));
+        }
+        assert!(test_vm::take_calls().is_empty());
+
+        // Other tags are still approvable under a custom anchor tag.
+        assert!(variants.approve_domain(Bytes(DOMAIN.to_vec())).is_ok());
+    }
+
+    #[test]
+    fn foreign_chain_receipts_need_an_accepted_chain_id() {
+        // A receipt signed for the old chain of a migrating device is
+        // refused until the owner accepts that chain.
+        let mut variants = variants();
+        let old_chain = 42170;
+        let digest = |chain_id| {
+            keccak256(receipt_codec::encode_material(
+                chain_id, HW_ID, FW_HASH, EXEC_HASH, 7,
+            ))
+        };
+        let verify = |variants: &mut AnchorVariants, chain_id| {
+            variants.verify_receipt_for_chain(
+                HW_ID,
+                FW_HASH,
+                EXEC_HASH,
+                7,
+                digest(chain_id),
+                chain_id,
+            )
+        };
+
+        assert_eq!(verify(&mut variants, CHAIN_ID), Ok(()));
+        assert_eq!(
+            verify(&mut variants, old_chain),
+            Err(ChainIdNotAccepted {}.abi_encode())
+        );
+        assert!(variants.accept_chain_id(old_chain).is_ok());
+        assert!(variants.is_chain_id_accepted(old_chain));
+        assert_eq!(verify(&mut variants, old_chain), Ok(()));
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![
+                anchored(FixedBytes::ZERO, 7, digest(CHAIN_ID)),
+                anchored(FixedBytes::ZERO, 7, digest(old_chain)),
+            ]
+        );
+
+        assert!(variants.revoke_chain_id(old_chain).is_ok());
+        assert_eq!(
+            verify(&mut variants, old_chain),
+            Err(ChainIdNotAccepted {}.abi_encode())
+        );
+    }
+
+    #[test]
+    fn material_layouts_produce_distinct_digests() {
+        let mut variants = variants();
+        let v1 = keccak256(receipt_codec::encode_material(
+            CHAIN_ID, HW_ID, FW_HASH, EXEC_HASH, 1,
+        ));
+        let v2 = {
+            let mut material = DOMAIN.to_vec();
+            material.extend_from_slice(&CHAIN_ID.to_be_bytes());
+            material.extend_from_slice(&1u64.to_be_bytes());
+            material.extend_from_slice(HW_ID.as_slice());
+            material.extend_from_slice(FW_HASH.as_slice());
+            material.extend_from_slice(EXEC_HASH.as_slice());
+            keccak256(material)
+        };
+        assert_ne!(v1, v2);
+        let verify = |variants: &mut AnchorVariants, digest, layout| {
+            variants.verify_receipt_v(HW_ID, FW_HASH, EXEC_HASH, 1, digest, layout)
+        };
+
+        // Only layout 1 is accepted until the owner widens the mask.
+        assert_eq!(variants.get_accepted_layouts(), 0b01);
+        assert_eq!(
+            verify(&mut variants, v2, LAYOUT_V2),
+            Err(LayoutNotAccepted {}.abi_encode())
+        );
+        assert!(variants.set_accepted_layouts(0b11).is_ok());
+
+        // Each digest only verifies under the layout that produced it.
+        assert_eq!(
+            verify(&mut variants, v1, LAYOUT_V2),
+            Err(DigestMismatch {}.abi_encode())
+        );
+        assert_eq!(verify(&mut variants, v2, LAYOUT_V2), Ok(()));
+        assert_eq!(
+            verify(&mut variants, v1, 3),
+            Err(LayoutNotAccepted {}.abi_encode())
+        );
+        assert_eq!(verify(&mut variants, v1, LAYOUT_V1), Ok(()));
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![
+                anchored(FixedBytes::ZERO, 1, v2),
+                anchored(FixedBytes::ZERO, 1, v1),
+            ]
+        );
+    }
+
+    #[test]
+    fn fresh_receipts_respect_the_staleness_window() {
+        let mut variants = variants();
+        assert!(variants.set_max_block_staleness(10).is_ok());
+        let digest = |counter: u64, block_ref: u64| {
+            let mut material = receipt_codec::encode_tagged_material(
+                DOMAIN, CHAIN_ID, HW_ID, FW_HASH, EXEC_HASH, counter,
+            );
+            material.extend_from_slice(&block_ref.to_be_bytes());
+            keccak256(material)
+        };
+        let verify = |variants: &mut AnchorVariants, counter, block_ref| {
+            let digest = digest(counter, block_ref);
+            variants.verify_receipt_fresh(HW_ID, FW_HASH, EXEC_HASH, counter, block_ref, digest)
+        };
+
+        assert_eq!(
+            verify(&mut variants, 1, BLOCK_NUMBER - 11),
+            Err(StaleReceipt {}.abi_encode())
+        );
+        assert_eq!(verify(&mut variants, 1, BLOCK_NUMBER - 10), Ok(()));
+        assert_eq!(
+            verify(&mut variants, 2, BLOCK_NUMBER + 1),
+            Err(FutureBlockRef {}.abi_encode())
+        );
+        assert_eq!(verify(&mut variants, 2, BLOCK_NUMBER), Ok(()));
+
+        // block_ref is part of the digest, so it cannot be refreshed.
+        let result = variants.verify_receipt_fresh(
+            HW_ID,
+            FW_HASH,
+            EXEC_HASH,
+            3,
+            BLOCK_NUMBER,
+            digest(3, BLOCK_NUMBER - 5),
+        );
+        assert_eq!(result, Err(DigestMismatch {}.abi_encode()));
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![
+                anchored(FixedBytes::ZERO, 1, digest(1, BLOCK_NUMBER - 10)),
+                anchored(FixedBytes::ZERO, 2, digest(2, BLOCK_NUMBER)),
+            ]
+        );
+    }
+
+    #[test]
+    fn digests_follow_the_anchor_configuration() {
+        use sha2::{Digest, Sha256};
+
+        let mut variants = variants();
+        let tag = b"TENANT_DOMAIN_V2";
+        let material =
+            receipt_codec::encode_tagged_material(tag, CHAIN_ID, HW_ID, FW_HASH, EXEC_HASH, 1);
+        let sha = FixedBytes::<32>::from_slice(&Sha256::digest(&material));
+        let verify = |variants: &mut AnchorVariants, digest| {
+            variants.verify_receipt_for_chain(HW_ID, FW_HASH, EXEC_HASH, 1, digest, CHAIN_ID)
+        };
+
+        configure_anchor(tag, 1);
+        assert_eq!(
+            verify(&mut variants, keccak256(&material)),
+            Err(DigestMismatch {}.abi_encode())
+        );
+        assert_eq!(verify(&mut variants, sha), Ok(()));
+        assert_eq!(
+            test_vm::take_calls(),
+            vec![anchored(FixedBytes::ZERO, 1, sha)]
+        );
+    }
+
+    #[test]
+    fn configuration_is_owner_only_and_logged() {
+        type Mutator = fn(&mut AnchorVariants) -> Result<(), Vec<u8>>;
+
+        let mut variants = variants();
+        assert!(matches!(
+            variants.initialize(ANCHOR),
+            Err(VariantsError::AlreadyInitialized(_))
+        ));
+        assert_eq!(variants.get_anchor(), ANCHOR);
+        test_vm::take_logs();
+
+        let mutators: [(Mutator, [u8; 4]); 7] = [
+            (
+                |v| v.approve_domain(Bytes(b"tenant".to_vec())),
+                function_selector!("approveDomain", Bytes),
+            ),
+            (
+                |v| Ok(v.revoke_domain(Bytes(b"tenant".to_vec()))?),
+                function_selector!("revokeDomain", Bytes),
+            ),
+            (
+                |v| Ok(v.accept_chain_id(1)?),
+                function_selector!("acceptChainId", u64),
+            ),
+            (
+                |v| Ok(v.revoke_chain_id(1)?),
+                function_selector!("revokeChainId", u64),
+            ),
+            (
+                |v| Ok(v.set_accepted_layouts(0b11)?),
+                function_selector!("setAcceptedLayouts", u8),
+            ),
+            (
+                |v| Ok(v.set_max_block_staleness(10)?),
+                function_selector!("setMaxBlockStaleness", u64),
+            ),
+            (
+                |v| Ok(v.transfer_ownership(Address::repeat_byte(0xbb))?),
+                function_selector!("transferOwnership", Address),
+            ),
+        ];
+        for (seq, (mutate, selector)) in (1..).zip(mutators) {
+            assert!(mutate(&mut variants).is_ok());
+            let event = ConfigChanged {
+                seq,
+                selector: selector.into(),
+                by: test_vm::SENDER,
+            };
+            assert_eq!(test_vm::take_logs(), vec![emitted(event)]);
+        }
+        assert_eq!(variants.get_event_seq(), 7);
+        assert_eq!(variants.get_owner(), Address::repeat_byte(0xbb));
+
+        for (mutate, _) in mutators {
+            assert_eq!(
+                mutate(&mut variants),
+                Err(UnauthorizedCaller {}.abi_encode())
+            );
+        }
+    }
+}
@@ -1,8 +1,29 @@
 #![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
 extern crate alloc;
 
+use alloy_sol_types::sol;
+use stylus_sdk::alloy_primitives::{Address, FixedBytes, U256};
 use stylus_sdk::prelude::*;
-use stylus_sdk::alloy_primitives::{FixedBytes, U256};
+
+sol! {
+    error AlreadyInitialized();
+    error UnauthorizedCaller();
+    error InvalidLogCapacity();
+    error IndexOutOfBounds();
+    error CountOverflow();
+
+    event ExecutionSubmitted(address indexed submitter, uint256 indexed nonce, bytes32 receipt_digest, bool duplicate);
+    event ExecutionVerified(uint256 new_count, bytes32 receipt);
+}
+
+#[derive(SolidityError)]
+pub enum AnchorError {
+    AlreadyInitialized(AlreadyInitialized),
+    UnauthorizedCaller(UnauthorizedCaller),
+    InvalidLogCapacity(InvalidLogCapacity),
+    IndexOutOfBounds(IndexOutOfBounds),
+    CountOverflow(CountOverflow),
+}
 
 // In Stylus 0.10.x, the storage struct IS the entrypoint.
 // No wrapper struct is needed - #[entrypoint] goes INSIDE sol_storage!
@@ -11,6 +32,17 @@ sol_storage! {
     pub struct AnchorAnchor {
         uint256 verified_count;
         bytes32 last_receipt;
+        address owner;
+        // Receipts accepted by verify_execution, in slot order.
+        // With a nonzero receipt_log_max the log is a ring: once full, the
+        // slot at receipt_log_next (the oldest entry) is overwritten.
+        bytes32[] receipt_log;
+        uint256 receipt_log_max;
+        uint256 receipt_log_next;
+        bool receipt_log_wrapped;
+        // Nonces already processed by verify_execution_idempotent, per
+        // submitting account, so one caller cannot burn another's nonce.
+        mapping(address => mapping(uint256 => bool)) seen_nonces;
     }
 }
 
@@ -18,17 +50,306 @@ sol_storage! {
 // The #[public] macro generates the Router for this struct.
 #[public]
 impl AnchorAnchor {
-    pub fn verify_execution(&mut self, receipt_digest: FixedBytes<32>) {
-        let current = self.verified_count.get();
-        self.verified_count.set(current + U256::from(1));
+    pub fn initialize(&mut self) -> Result<(), AnchorError> {
+        if self.owner.get() != Address::ZERO {
+            return Err(AnchorError::AlreadyInitialized(AlreadyInitialized {}));
+        }
+        let sender = self.vm().msg_sender();
+        self.owner.set(sender);
+        Ok(())
+    }
+
+    pub fn verify_execution(&mut self, receipt_digest: FixedBytes<32>) -> Result<(), AnchorError> {
+        self.verify_execution_returning(receipt_digest)?;
+        Ok(())
+    }
+
+    /// verify_execution that returns the new verified count. Reverts with
+    /// CountOverflow rather than wrapping once the count reaches U256::MAX.
+    pub fn verify_execution_returning(
+        &mut self,
+        receipt_digest: FixedBytes<32>,
+    ) -> Result<U256, AnchorError> {
+        let count = self
+            .verified_count
+            .get()
+            .checked_add(U256::from(1))
+            .ok_or(AnchorError::CountOverflow(CountOverflow {}))?;
+        self.verified_count.set(count);
         self.last_receipt.set(receipt_digest);
+
+        // Append to the receipt log, overwriting the oldest entry once a
+        // capped log is full. Kept inline because a private helper impl
+        // does not build with the contract-client-gen feature.
+        let max = self.receipt_log_max.get();
+        if max == U256::ZERO || U256::from(self.receipt_log.len()) < max {
+            self.receipt_log.push(receipt_digest);
+        } else {
+            let slot = self.receipt_log_next.get();
+            if let Some(mut entry) = self.receipt_log.setter(slot) {
+                entry.set(receipt_digest);
+            }
+            self.receipt_log_next.set((slot + U256::from(1)) % max);
+            self.receipt_log_wrapped.set(true);
+        }
+        self.vm().log(ExecutionVerified {
+            new_count: count,
+            receipt: receipt_digest,
+        });
+        Ok(count)
+    }
+
+    /// verify_execution keyed by a nonce in the caller's own namespace, so
+    /// a resubmitted transaction is counted once. A nonce the caller has
+    /// already used changes nothing and returns the current count;
+    /// ExecutionSubmitted reports which case applied.
+    pub fn verify_execution_idempotent(
+        &mut self,
+        receipt_digest: FixedBytes<32>,
+        nonce: U256,
+    ) -> Result<U256, AnchorError> {
+        let submitter = self.vm().msg_sender();
+        let duplicate = self.seen_nonces.getter(submitter).get(nonce);
+        let count = if duplicate {
+            self.verified_count.get()
+        } else {
+            self.seen_nonces.setter(submitter).insert(nonce, true);
+            self.verify_execution_returning(receipt_digest)?
+        };
+        self.vm().log(ExecutionSubmitted {
+            submitter,
+            nonce,
+            receipt_digest,
+            duplicate,
+        });
+        Ok(count)
+    }
+
+    /// Cap the receipt log at `max` entries (0 = unbounded).
+    ///
+    /// The cap may be raised at any time before the ring first wraps.
+    /// Shrinking below the current length, or resizing after the first
+    /// overwrite, is rejected because either would scramble the ring order.
+    pub fn set_receipt_log_max(&mut self, max: U256) -> Result<(), AnchorError> {
+        if self.vm().msg_sender() != self.owner.get() {
+            return Err(AnchorError::UnauthorizedCaller(UnauthorizedCaller {}));
+        }
+        let len = U256::from(self.receipt_log.len());
+        if self.receipt_log_wrapped.get() || (max != U256::ZERO && max < len) {
+            return Err(AnchorError::InvalidLogCapacity(InvalidLogCapacity {}));
+        }
+        self.receipt_log_max.set(max);
+        Ok(())
     }
 
     pub fn get_verified_count(&self) -> U256 {
         self.verified_count.get()
     }
 
+    /// High-watermark of verified executions. The count never decreases,
+    /// so this is the same value as get_verified_count; integrators that
+    /// track progress should read it under this name.
+    pub fn verified_count(&self) -> U256 {
+        self.verified_count.get()
+    }
+
     pub fn get_last_receipt(&self) -> FixedBytes<32> {
         self.last_receipt.get()
     }
+
+    /// Verified count and last receipt as one consistent snapshot.
+    pub fn get_status(&self) -> (U256, FixedBytes<32>) {
+        (self.verified_count.get(), self.last_receipt.get())
+    }
+
+    pub fn get_owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    pub fn receipt_log_length(&self) -> U256 {
+        U256::from(self.receipt_log.len())
+    }
+
+    pub fn receipt_log_at(&self, index: U256) -> Result<FixedBytes<32>, AnchorError> {
+        self.receipt_log
+            .get(index)
+            .ok_or(AnchorError::IndexOutOfBounds(IndexOutOfBounds {}))
+    }
+
+    pub fn get_receipt_log_max(&self) -> U256 {
+        self.receipt_log_max.get()
+    }
+
+    /// Slot holding the oldest entry (0 until the ring first wraps).
+    pub fn receipt_log_head(&self) -> U256 {
+        self.receipt_log_next.get()
+    }
+}
+
+// Under export-abi or contract-client-gen the #[public] methods are
+// rewritten into ABI stubs or client calls, so the tests only build
+// against the plain contract.
+#[cfg(all(
+    test,
+    not(any(feature = "export-abi", feature = "contract-client-gen"))
+))]
+mod tests {
+    use super::*;
+    use alloy_sol_types::SolEvent;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn large_count_increments_then_refuses_to_wrap() {
+        let vm = TestVM::default();
+        let mut anchor = AnchorAnchor::from(&vm);
+        let receipt = FixedBytes::<32>::repeat_byte(0xab);
+
+        anchor.verified_count.set(U256::MAX - U256::from(1));
+        assert!(matches!(
+            anchor.verify_execution_returning(receipt),
+            Ok(count) if count == U256::MAX
+        ));
+        assert_eq!(anchor.verified_count(), U256::MAX);
+        assert_eq!(anchor.get_verified_count(), U256::MAX);
+
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].0[0], ExecutionVerified::SIGNATURE_HASH);
+        let event =
+            ExecutionVerified::decode_raw_log(logs[0].0.iter().copied(), &logs[0].1).unwrap();
+        assert_eq!(event.new_count, U256::MAX);
+        assert_eq!(event.receipt, receipt);
+
+        let other = FixedBytes::<32>::repeat_byte(0xcd);
+        assert!(matches!(
+            anchor.verify_execution(other),
+            Err(AnchorError::CountOverflow(_))
+        ));
+        assert_eq!(anchor.verified_count(), U256::MAX);
+    }
+
+    #[test]
+    fn status_snapshot_matches_the_individual_getters() {
+        let vm = TestVM::default();
+        let mut anchor = AnchorAnchor::from(&vm);
+        assert_eq!(anchor.get_status(), (U256::ZERO, FixedBytes::ZERO));
+
+        let receipt = FixedBytes::<32>::repeat_byte(0xab);
+        assert!(matches!(
+            anchor.verify_execution_returning(receipt),
+            Ok(count) if count == U256::from(1)
+        ));
+        assert_eq!(
+            anchor.get_status(),
+            (anchor.get_verified_count(), anchor.get_last_receipt())
+        );
+        assert_eq!(anchor.get_status(), (U256::from(1), receipt));
+    }
+
+    #[test]
+    fn idempotent_nonces_are_scoped_to_the_caller() {
+        let vm = TestVM::default();
+        let mut anchor = AnchorAnchor::from(&vm);
+        let receipt = FixedBytes::<32>::repeat_byte(0xab);
+        let nonce = U256::from(7);
+        let relayer = Address::repeat_byte(0x11);
+        let griefer = Address::repeat_byte(0x22);
+
+        // Another caller using the nonce first does not consume it.
+        vm.set_sender(griefer);
+        assert!(matches!(
+            anchor.verify_execution_idempotent(receipt, nonce),
+            Ok(count) if count == U256::from(1)
+        ));
+        vm.set_sender(relayer);
+        assert!(matches!(
+            anchor.verify_execution_idempotent(receipt, nonce),
+            Ok(count) if count == U256::from(2)
+        ));
+
+        // The same caller replaying it is counted once.
+        assert!(matches!(
+            anchor.verify_execution_idempotent(receipt, nonce),
+            Ok(count) if count == U256::from(2)
+        ));
+        let submitted: Vec<_> = vm
+            .get_emitted_logs()
+            .iter()
+            .filter(|(topics, _)| topics[0] == ExecutionSubmitted::SIGNATURE_HASH)
+            .map(|(topics, data)| {
+                ExecutionSubmitted::decode_raw_log(topics.iter().copied(), data).unwrap()
+            })
+            .map(|event| (event.submitter, event.duplicate))
+            .collect();
+        assert_eq!(
+            submitted,
+            [(griefer, false), (relayer, false), (relayer, true)]
+        );
+    }
+
+    #[test]
+    fn capped_receipt_log_overwrites_the_oldest_slot() {
+        let vm = TestVM::default();
+        let mut anchor = AnchorAnchor::from(&vm);
+        let receipts: Vec<_> = (1u8..=5).map(FixedBytes::<32>::repeat_byte).collect();
+        assert!(anchor.initialize().is_ok());
+        assert!(anchor.set_receipt_log_max(U256::from(3)).is_ok());
+
+        for receipt in &receipts[..3] {
+            assert!(anchor.verify_execution(*receipt).is_ok());
+        }
+        assert_eq!(anchor.receipt_log_length(), U256::from(3));
+        assert_eq!(anchor.receipt_log_head(), U256::ZERO);
+
+        // The fourth receipt replaces slot 0 and the head moves past it.
+        assert!(anchor.verify_execution(receipts[3]).is_ok());
+        assert_eq!(anchor.receipt_log_length(), U256::from(3));
+        assert_eq!(anchor.receipt_log_head(), U256::from(1));
+        assert!(matches!(anchor.receipt_log_at(U256::ZERO), Ok(r) if r == receipts[3]));
+        assert!(matches!(anchor.receipt_log_at(U256::from(1)), Ok(r) if r == receipts[1]));
+
+        assert!(anchor.verify_execution(receipts[4]).is_ok());
+        assert_eq!(anchor.receipt_log_head(), U256::from(2));
+        let log: Vec<_> = (0..3)
+            .map(|i| anchor.receipt_log_at(U256::from(i)).ok())
+            .collect();
+        assert_eq!(
+            log,
+            [Some(receipts[3]), Some(receipts[4]), Some(receipts[2])]
+        );
+        assert!(matches!(
+            anchor.receipt_log_at(U256::from(3)),
+            Err(AnchorError::IndexOutOfBounds(_))
+        ));
+    }
+
+    #[test]
+    fn receipt_log_cap_cannot_shrink_below_length_or_change_after_wrapping() {
+        let vm = TestVM::default();
+        let mut anchor = AnchorAnchor::from(&vm);
+        let receipts: Vec<_> = (1u8..=4).map(FixedBytes::<32>::repeat_byte).collect();
+        assert!(anchor.initialize().is_ok());
+        assert!(anchor.set_receipt_log_max(U256::from(3)).is_ok());
+        for receipt in &receipts[..3] {
+            assert!(anchor.verify_execution(*receipt).is_ok());
+        }
+
+        assert!(matches!(
+            anchor.set_receipt_log_max(U256::from(2)),
+            Err(AnchorError::InvalidLogCapacity(_))
+        ));
+        // Raising the cap, or lowering it back to the length, is fine
+        // while the ring has not wrapped.
+        assert!(anchor.set_receipt_log_max(U256::from(4)).is_ok());
+        assert!(anchor.set_receipt_log_max(U256::from(3)).is_ok());
+
+        assert!(anchor.verify_execution(receipts[3]).is_ok());
+        for max in [U256::ZERO, U256::from(3), U256::from(10)] {
+            assert!(matches!(
+                anchor.set_receipt_log_max(max),
+                Err(AnchorError::InvalidLogCapacity(_))
+            ));
+        }
+        assert_eq!(anchor.get_receipt_log_max(), U256::from(3));
+    }
 }
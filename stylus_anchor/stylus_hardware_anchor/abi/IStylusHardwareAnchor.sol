@@ -0,0 +1,145 @@
+interface IStylusHardwareAnchor {
+    function initialize() external;
+
+    function initializeWith(address owner, bytes calldata domain_tag) external;
+
+    function verifyReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external;
+
+    function verifyReceiptReturning(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external returns (bytes32);
+
+    function submitReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external returns (uint8);
+
+    function reportReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external returns (bool);
+
+    function anchorReceipt(bytes32 hw_id, bytes32 namespace, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 digest) external;
+
+    function checkReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external view returns (uint8);
+
+    function isAnchorable(bytes32 hw_id, bytes32 fw_hash, uint64 counter) external view returns (uint8);
+
+    function authorizeNode(bytes32 node_id) external;
+
+    function authorizeNodeUntil(bytes32 node_id, uint64 expiry) external;
+
+    function revokeNode(bytes32 node_id) external;
+
+    function blockNode(bytes32 node_id) external;
+
+    function unblockNode(bytes32 node_id) external;
+
+    function purgeNode(bytes32 node_id) external;
+
+    function setMaxAuthorizedNodes(uint64 max) external;
+
+    function approveFirmware(bytes32 fw_hash) external;
+
+    function approveFirmwareUntil(bytes32 fw_hash, uint64 expiry) external;
+
+    function approveFirmwareVersioned(bytes32 fw_hash, uint32 version) external;
+
+    function setCounterFloor(bytes32 node_id, uint64 floor) external;
+
+    function setAllowReanchor(bytes32 node_id, bool allowed) external;
+
+    function setSignatureRequired(bytes32 node_id, bool required) external;
+
+    function setMinFirmwareVersion(bytes32 node_id, uint32 version) external;
+
+    function setMaxFirmware(uint64 max) external;
+
+    function freezeFirmwareApprovals() external;
+
+    function revokeFirmware(bytes32 fw_hash) external;
+
+    function purgeFirmware(bytes32 fw_hash) external;
+
+    function setExecAllowlistEnabled(bool enabled) external;
+
+    function approveExec(bytes32 exec_hash) external;
+
+    function revokeExec(bytes32 exec_hash) external;
+
+    function setDigestAlgo(uint8 algo) external;
+
+    function setReplayWindow(uint64 window) external;
+
+    function setMinReceiptInterval(uint64 seconds) external;
+
+    function setCounterBits(uint8 bits) external;
+
+    function setVerifier(address verifier, bool allowed) external;
+
+    function transferOwnership(address new_owner) external;
+
+    function supportsInterface(bytes4 interface_id) external view returns (bool);
+
+    function contractVersion() external view returns (string memory);
+
+    function buildCommit() external view returns (bytes20);
+
+    function getOwner() external view returns (address);
+
+    function isNodeAuthorized(bytes32 node_id) external view returns (bool);
+
+    function isFirmwareApproved(bytes32 fw_hash) external view returns (bool);
+
+    function getCounter(bytes32 node_id) external view returns (uint64);
+
+    function previewDigest(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter) external view returns (bytes32);
+
+    function getReceiptRoot() external view returns (bytes32);
+
+    function nextValidCounter(bytes32 node_id) external view returns (uint64);
+
+    function wouldAcceptCounter(bytes32 node_id, uint64 counter) external view returns (bool);
+
+    function readSlot(bytes32 slot) external view returns (bytes32);
+
+    error AlreadyInitialized();
+
+    error UnauthorizedCaller();
+
+    error UnauthorizedHardware();
+
+    error FirmwareNotApproved();
+
+    error ReplayDetected();
+
+    error DigestMismatch();
+
+    error InvalidOwner();
+
+    error FirmwareDowngrade();
+
+    error InvalidReplayWindow();
+
+    error NodeCapExceeded();
+
+    error RateLimited();
+
+    error InvalidCounterBits();
+
+    error CounterOutOfRange();
+
+    error MaxCounterReached();
+
+    error FirmwareExpired();
+
+    error InvalidExpiry();
+
+    error FirmwareApprovalsFrozen();
+
+    error ExecNotApproved();
+
+    error InvalidDigestAlgo();
+
+    error HashUnavailable();
+
+    error NodeAuthorizationExpired();
+
+    error NodeBlocked();
+
+    error DomainTagTooLong();
+
+    error SignatureRequired();
+}
@@ -0,0 +1,357 @@
+interface IStylusHardwareAnchor {
+    function initialize() external;
+
+    function initializeWith(address owner, bytes calldata domain_tag) external;
+
+    function verifyReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external;
+
+    function verifyReceiptReturning(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external returns (bytes32);
+
+    function verifyReceiptDomain(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest, bytes calldata domain_tag) external;
+
+    function verifyReceiptForChain(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest, uint64 chain_id) external;
+
+    function verifyReceiptPacked(bytes calldata receipt) external;
+
+    function submitReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external returns (uint8);
+
+    function reportReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external returns (bool);
+
+    function verifyReceiptP256(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest, bytes32 pubkey_x, bytes32 pubkey_y, bytes32 r, bytes32 s) external;
+
+    function verifyReceiptDelegated(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest, address session_key, uint64 delegation_expiry, uint64 delegation_chain_id, bytes calldata delegation_sig, bytes calldata session_sig) external;
+
+    function verifyReceiptV(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest, uint8 layout_version) external;
+
+    function verifyReceiptFresh(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, uint64 block_ref, bytes32 claimed_digest) external;
+
+    function checkReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external view returns (uint8);
+
+    function simulateVerifyReceipt(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter, bytes32 claimed_digest) external view returns (uint8);
+
+    function verifyReceipts(bytes calldata packed, bool atomic) external returns (bool[] memory);
+
+    function selftest() external returns (bool);
+
+    function isAnchorable(bytes32 hw_id, bytes32 fw_hash, uint64 counter) external view returns (uint8);
+
+    function verifyReceiptsBatch(uint8[] memory packed) external view returns (bool[] memory);
+
+    function verifyReceiptsBatchBytes(bytes calldata packed) external view returns (bool[] memory);
+
+    function verifyReceiptsBatchBitset(uint8[] memory packed) external view returns (bytes32);
+
+    function verifyReceiptsBatchBitsetBytes(bytes calldata packed) external view returns (bytes32);
+
+    function computeReceiptDigestsBatch(uint8[] memory packed) external view returns (bytes32[] memory);
+
+    function authorizeNode(bytes32 node_id) external;
+
+    function authorizeNodeUntil(bytes32 node_id, uint64 expiry) external;
+
+    function permitAuthorizeNode(bytes32 node_id, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
+
+    function revokeNode(bytes32 node_id) external;
+
+    function blockNode(bytes32 node_id) external;
+
+    function unblockNode(bytes32 node_id) external;
+
+    function purgeNode(bytes32 node_id) external;
+
+    function setMaxAuthorizedNodes(uint64 max) external;
+
+    function approveFirmware(bytes32 fw_hash) external;
+
+    function approveFirmwareUntil(bytes32 fw_hash, uint64 expiry) external;
+
+    function approveFirmwareVersioned(bytes32 fw_hash, uint32 version) external;
+
+    function importFirmwareManifest((bytes32,uint32,uint64)[] memory entries) external;
+
+    function setCounterFloor(bytes32 node_id, uint64 floor) external;
+
+    function setAllowReanchor(bytes32 node_id, bool allowed) external;
+
+    function setMinFirmwareVersion(bytes32 node_id, uint32 version) external;
+
+    function requestFirmwareApproval(bytes32 fw_hash, bytes32 commitment) external;
+
+    function finalizeFirmwareApproval(bytes32 fw_hash, bytes calldata preimage) external;
+
+    function setMaxFirmware(uint64 max) external;
+
+    function freezeFirmwareApprovals() external;
+
+    function revokeFirmware(bytes32 fw_hash) external;
+
+    function purgeFirmware(bytes32 fw_hash) external;
+
+    function sweepFirmware(bytes32[] memory fw_hashes) external returns (uint64);
+
+    function setExecAllowlistEnabled(bool enabled) external;
+
+    function approveExec(bytes32 exec_hash) external;
+
+    function revokeExec(bytes32 exec_hash) external;
+
+    function approveDomain(bytes calldata domain_tag) external;
+
+    function revokeDomain(bytes calldata domain_tag) external;
+
+    function setDigestAlgo(uint8 algo) external;
+
+    function acceptChainId(uint64 chain_id) external;
+
+    function revokeChainId(uint64 chain_id) external;
+
+    function registerP256Key(bytes32 node_id, bytes32 pubkey_x, bytes32 pubkey_y) external;
+
+    function setNodeMeta(bytes32 node_id, bytes32 model_id, bytes32 location_hash) external;
+
+    function setNodeSigner(bytes32 node_id, address signer) external;
+
+    function setReplayWindow(uint64 window) external;
+
+    function setMaxBlockStaleness(uint64 blocks) external;
+
+    function setMinReceiptInterval(uint64 seconds) external;
+
+    function setCounterBits(uint8 bits) external;
+
+    function setAcceptedLayouts(uint8 mask) external;
+
+    function transferOwnership(address new_owner) external;
+
+    function setRecoveryOwner(address recovery_owner) external;
+
+    function setRecoveryDelay(uint64 seconds) external;
+
+    function claimOwnershipAfterInactivity() external;
+
+    function setOwners(address[] memory owners, uint8 threshold) external;
+
+    function propose(bytes32 action_hash, uint64 deadline) external;
+
+    function approve(bytes32 action_hash) external;
+
+    function actionHash(bytes4 selector, bytes calldata args) external view returns (bytes32);
+
+    function getOwners() external view returns (address[] memory);
+
+    function getThreshold() external view returns (uint8);
+
+    function getProposal(bytes32 action_hash) external view returns (uint64, uint8, bool);
+
+    function supportsInterface(bytes4 interface_id) external view returns (bool);
+
+    function contractVersion() external view returns (string memory);
+
+    function buildCommit() external view returns (bytes20);
+
+    function getOwner() external view returns (address);
+
+    function getRecoveryOwner() external view returns (address);
+
+    function getRecoveryDelay() external view returns (uint64);
+
+    function getLastOwnerActivity() external view returns (uint64);
+
+    function getMaxAuthorizedNodes() external view returns (uint64);
+
+    function getActiveNodeCount() external view returns (uint64);
+
+    function isNodeAuthorized(bytes32 node_id) external view returns (bool);
+
+    function isFirmwareApproved(bytes32 fw_hash) external view returns (bool);
+
+    function areNodesAuthorized(bytes32[] memory node_ids) external view returns (bool[] memory);
+
+    function areFirmwaresApproved(bytes32[] memory fw_hashes) external view returns (bool[] memory);
+
+    function isFirmwareFrozen() external view returns (bool);
+
+    function getEventSeq() external view returns (uint64);
+
+    function getNodeExpiry(bytes32 node_id) external view returns (uint64);
+
+    function getFirmwareExpiry(bytes32 fw_hash) external view returns (uint64);
+
+    function isExecAllowlistEnabled() external view returns (bool);
+
+    function isExecApproved(bytes32 exec_hash) external view returns (bool);
+
+    function getFirmwareVersion(bytes32 fw_hash) external view returns (uint32);
+
+    function getMinFirmwareVersion(bytes32 node_id) external view returns (uint32);
+
+    function getAcceptedCount(bytes32 node_id) external view returns (uint64);
+
+    function getMaxFirmware() external view returns (uint64);
+
+    function getApprovedFirmwareCount() external view returns (uint64);
+
+    function getDigestAlgo() external view returns (uint8);
+
+    function isChainIdAccepted(uint64 chain_id) external view returns (bool);
+
+    function getFirmwareReceiptCount(bytes32 fw_hash) external view returns (uint64);
+
+    function getFirmwareNodeCount(bytes32 fw_hash) external view returns (uint64);
+
+    function getFirmwareNodeAt(bytes32 fw_hash, uint64 index) external view returns (bytes32);
+
+    function getRejectedCount(bytes32 node_id) external view returns (uint64);
+
+    function getReplayWindow() external view returns (uint64);
+
+    function getMaxBlockStaleness() external view returns (uint64);
+
+    function getMinReceiptInterval() external view returns (uint64);
+
+    function getLastVerifiedAt(bytes32 node_id) external view returns (uint64);
+
+    function getPermitNonce() external view returns (uint256);
+
+    function getPermitDomainSeparator() external view returns (bytes32);
+
+    function getCounterBits() external view returns (uint8);
+
+    function getPendingFirmwareRequester(bytes32 fw_hash, bytes32 commitment) external view returns (address);
+
+    function getCounter(bytes32 node_id) external view returns (uint64);
+
+    function getDomainCounter(bytes calldata domain_tag, bytes32 node_id) external view returns (uint64);
+
+    function isDomainApproved(bytes calldata domain_tag) external view returns (bool);
+
+    function nextValidCounter(bytes32 node_id) external view returns (uint64);
+
+    function getCounterFloor(bytes32 node_id) external view returns (uint64);
+
+    function isReanchorAllowed(bytes32 node_id) external view returns (bool);
+
+    function isNodeBlocked(bytes32 node_id) external view returns (bool);
+
+    function wouldAcceptCounter(bytes32 node_id, uint64 counter) external view returns (bool);
+
+    function getCounters(bytes32[] memory node_ids) external view returns (uint64[] memory);
+
+    function getP256Key(bytes32 node_id) external view returns (bytes32, bytes32);
+
+    function getNodeMeta(bytes32 node_id) external view returns (bytes32, bytes32);
+
+    function getNodeSigner(bytes32 node_id) external view returns (address);
+
+    function previewDigest(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter) external view returns (bytes32);
+
+    function previewMaterial(bytes32 hw_id, bytes32 fw_hash, bytes32 exec_hash, uint64 counter) external view returns (bytes memory);
+
+    function materialLength() external view returns (uint32);
+
+    function previewHwId(bytes16 efuse, bytes32 salt) external view returns (bytes32);
+
+    function getDomainTag() external view returns (bytes memory);
+
+    function getAcceptedLayouts() external view returns (uint8);
+
+    function getReceiptRoot() external view returns (bytes32);
+
+    error AlreadyInitialized();
+
+    error UnauthorizedCaller();
+
+    error UnauthorizedHardware();
+
+    error FirmwareNotApproved();
+
+    error ReplayDetected();
+
+    error DigestMismatch();
+
+    error InvalidOwner();
+
+    error P256Unsupported();
+
+    error P256KeyMismatch();
+
+    error InvalidP256Signature();
+
+    error FirmwareDowngrade();
+
+    error InvalidReplayWindow();
+
+    error NodeCapExceeded();
+
+    error NoPendingApproval();
+
+    error LayoutNotAccepted();
+
+    error StaleReceipt();
+
+    error FutureBlockRef();
+
+    error RateLimited();
+
+    error PermitExpired();
+
+    error InvalidSignature();
+
+    error InvalidCounterBits();
+
+    error CounterOutOfRange();
+
+    error MaxCounterReached();
+
+    error FirmwareExpired();
+
+    error NotAnOwner();
+
+    error InvalidThreshold();
+
+    error InvalidDeadline();
+
+    error InvalidExpiry();
+
+    error ProposalNotFound();
+
+    error ProposalPending();
+
+    error ProposalExpired();
+
+    error AlreadyApproved();
+
+    error ThresholdNotMet();
+
+    error FirmwareApprovalsFrozen();
+
+    error RecoveryNotAvailable();
+
+    error OwnerStillActive();
+
+    error ExecNotApproved();
+
+    error ChainIdNotAccepted();
+
+    error InvalidDigestAlgo();
+
+    error HashUnavailable();
+
+    error MalformedReceipt();
+
+    error NodeAuthorizationExpired();
+
+    error NodeSignerNotSet();
+
+    error ManifestTooLarge();
+
+    error DelegationExpired();
+
+    error DelegationChainMismatch();
+
+    error NodeBlocked();
+
+    error IndexOutOfRange();
+
+    error DomainNotApproved();
+}
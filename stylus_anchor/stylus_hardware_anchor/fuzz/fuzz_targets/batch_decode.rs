@@ -0,0 +1,27 @@
+//! The batch decoders must never panic: any blob either splits into whole
+//! receipts that re-encode to the same bytes, or is rejected.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stylus_hardware_anchor::receipt_codec::{
+    decode_packed, decode_packed_unsigned, encode_packed, encode_packed_unsigned,
+    PACKED_RECEIPT_LEN, PACKED_RECEIPT_V2_LEN,
+};
+
+fuzz_target!(|blob: &[u8]| {
+    match decode_packed(blob) {
+        Ok(receipts) => {
+            assert_eq!(receipts.len() * PACKED_RECEIPT_LEN, blob.len());
+            assert_eq!(encode_packed(&receipts), blob);
+        }
+        Err(_) => assert!(blob.len() % PACKED_RECEIPT_LEN != 0),
+    }
+
+    match decode_packed_unsigned(blob) {
+        Ok(receipts) => {
+            assert_eq!(receipts.len() * PACKED_RECEIPT_V2_LEN, blob.len());
+            assert_eq!(encode_packed_unsigned(&receipts), blob);
+        }
+        Err(_) => assert!(blob.len() % PACKED_RECEIPT_V2_LEN != 0),
+    }
+});
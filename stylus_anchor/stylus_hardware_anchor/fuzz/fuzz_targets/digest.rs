@@ -0,0 +1,38 @@
+//! The contract's offset-copy digest material must hash the same as the
+//! plain concatenation device firmware builds.
+#![no_main]
+
+use alloy_primitives::{keccak256, FixedBytes};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use stylus_hardware_anchor::receipt_codec::{encode_material, DOMAIN};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    chain_id: u64,
+    hw_id: [u8; 32],
+    fw_hash: [u8; 32],
+    exec_hash: [u8; 32],
+    counter: u64,
+}
+
+fuzz_target!(|input: Input| {
+    let material = encode_material(
+        input.chain_id,
+        FixedBytes(input.hw_id),
+        FixedBytes(input.fw_hash),
+        FixedBytes(input.exec_hash),
+        input.counter,
+    );
+
+    let mut concatenated = Vec::new();
+    concatenated.extend_from_slice(DOMAIN);
+    concatenated.extend_from_slice(&input.chain_id.to_be_bytes());
+    concatenated.extend_from_slice(&input.hw_id);
+    concatenated.extend_from_slice(&input.fw_hash);
+    concatenated.extend_from_slice(&input.exec_hash);
+    concatenated.extend_from_slice(&input.counter.to_be_bytes());
+
+    assert_eq!(material.as_slice(), concatenated.as_slice());
+    assert_eq!(keccak256(material), keccak256(&concatenated));
+});
@@ -1,16 +1,184 @@
-#![cfg_attr(not(feature = "export-abi"), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi", feature = "offchain")), no_main)]
+#![cfg_attr(
+    all(
+        target_arch = "wasm32",
+        not(any(feature = "export-abi", feature = "offchain"))
+    ),
+    no_std
+)]
+#![recursion_limit = "512"]
 extern crate alloc;
 
+use alloc::string::String;
 use alloc::vec::Vec;
 use alloy_sol_types::sol;
 use stylus_sdk::abi::Bytes;
-use stylus_sdk::alloy_primitives::{keccak256, Address, FixedBytes, Uint};
-use stylus_sdk::{block, msg, prelude::*};
+use stylus_sdk::alloy_primitives::{address, keccak256, Address, FixedBytes, Uint, U256};
+use stylus_sdk::call::{static_call, Call};
+use stylus_sdk::function_selector;
+use stylus_sdk::storage::{GlobalStorage, StorageCache};
+use stylus_sdk::{block, evm, msg, prelude::*};
 
+pub use anchor_interface::receipt_codec;
+#[cfg(all(test, not(feature = "export-abi")))]
+mod test_vm;
+
+use receipt_codec::DOMAIN;
+
+// Deployed builds leave out std: a panic traps, which reverts the call.
+#[cfg(all(
+    target_arch = "wasm32",
+    not(any(feature = "export-abi", feature = "offchain"))
+))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    core::arch::wasm32::unreachable()
+}
+
+type U8 = Uint<8, 1>;
+type U32 = Uint<32, 1>;
 type U64 = Uint<64, 1>;
-const DOMAIN: &[u8; 13] = b"anchor_RCT_V1";
-const PACKED_RECEIPT_LEN: usize = 137;
-const PACKED_RECEIPT_V2_LEN: usize = 105;
+
+// Largest replay window: the open-counter flags share a word with the
+// 64-bit base counter (see seen_counters).
+const MAX_REPLAY_WINDOW: u64 = 192;
+
+// ERC-165 id of supportsInterface(bytes4) itself.
+const ERC165_INTERFACE_ID: [u8; 4] = [0x01, 0xff, 0xc9, 0xa7];
+
+// ERC-165 id of the anchor's core verify/admin interface: the XOR of the
+// selectors of verifyReceipt(bytes32,bytes32,bytes32,uint64,bytes32),
+// authorizeNode(bytes32), revokeNode(bytes32), approveFirmware(bytes32),
+// revokeFirmware(bytes32), isNodeAuthorized(bytes32),
+// isFirmwareApproved(bytes32) and getCounter(bytes32) = 0xf118ff11.
+const ANCHOR_INTERFACE_ID: [u8; 4] = xor_selectors(&[
+    function_selector!(
+        "verifyReceipt",
+        FixedBytes<32>,
+        FixedBytes<32>,
+        FixedBytes<32>,
+        u64,
+        FixedBytes<32>
+    ),
+    function_selector!("authorizeNode", FixedBytes<32>),
+    function_selector!("revokeNode", FixedBytes<32>),
+    function_selector!("approveFirmware", FixedBytes<32>),
+    function_selector!("revokeFirmware", FixedBytes<32>),
+    function_selector!("isNodeAuthorized", FixedBytes<32>),
+    function_selector!("isFirmwareApproved", FixedBytes<32>),
+    function_selector!("getCounter", FixedBytes<32>),
+]);
+
+// ERC-165 id of the v2 anchor interface, which moved the batch, signed,
+// tenant and getter calls out to the companion contracts: the core id
+// above XORed with the selectors of the calls the companions make,
+// anchorReceipt(bytes32,bytes32,bytes32,bytes32,uint64,bytes32),
+// checkReceipt(bytes32,bytes32,bytes32,uint64,bytes32) and
+// readSlot(bytes32) = 0x7d5e2e34.
+const ANCHOR_INTERFACE_ID_V2: [u8; 4] = xor_selectors(&[
+    ANCHOR_INTERFACE_ID,
+    function_selector!(
+        "anchorReceipt",
+        FixedBytes<32>,
+        FixedBytes<32>,
+        FixedBytes<32>,
+        FixedBytes<32>,
+        u64,
+        FixedBytes<32>
+    ),
+    function_selector!(
+        "checkReceipt",
+        FixedBytes<32>,
+        FixedBytes<32>,
+        FixedBytes<32>,
+        u64,
+        FixedBytes<32>
+    ),
+    function_selector!("readSlot", FixedBytes<32>),
+]);
+
+const fn xor_selectors(selectors: &[[u8; 4]]) -> [u8; 4] {
+    let mut id = [0u8; 4];
+    let mut i = 0;
+    while i < selectors.len() {
+        let mut j = 0;
+        while j < 4 {
+            id[j] ^= selectors[i][j];
+            j += 1;
+        }
+        i += 1;
+    }
+    id
+}
+
+// Counter width policy: receipts must fit the node fleet's configured
+// counter width, and the last COUNTER_HEADROOM values below the width's
+// maximum are refused so a device is rotated before its counter can wrap.
+// A counter that did wrap is numerically smaller than the stored one and
+// is refused as a replay.
+const MIN_COUNTER_BITS: u8 = 16;
+const MAX_COUNTER_BITS: u8 = 64;
+const COUNTER_HEADROOM: u64 = 256;
+
+// Source identity reported by contract_version and build_commit. The
+// commit is taken from ANCHOR_BUILD_COMMIT (40 hex digits) at build time;
+// builds without it report the zero commit.
+const CONTRACT_VERSION: &str = concat!("sha-v", env!("CARGO_PKG_VERSION"));
+const BUILD_COMMIT: [u8; 20] = match option_env!("ANCHOR_BUILD_COMMIT") {
+    Some(commit) => parse_commit(commit.as_bytes()),
+    None => [0; 20],
+};
+
+/// Decodes a 40-hex-digit commit at compile time, so deployed code carries
+/// no hex parser; anything else decodes to the zero commit.
+const fn parse_commit(hex: &[u8]) -> [u8; 20] {
+    const fn nibble(digit: u8) -> Option<u8> {
+        match digit {
+            b'0'..=b'9' => Some(digit - b'0'),
+            b'a'..=b'f' => Some(digit - b'a' + 10),
+            b'A'..=b'F' => Some(digit - b'A' + 10),
+            _ => None,
+        }
+    }
+    let mut commit = [0u8; 20];
+    if hex.len() != 40 {
+        return commit;
+    }
+    let mut i = 0;
+    while i < 20 {
+        match (nibble(hex[2 * i]), nibble(hex[2 * i + 1])) {
+            (Some(high), Some(low)) => commit[i] = high << 4 | low,
+            _ => return [0; 20],
+        }
+        i += 1;
+    }
+    commit
+}
+
+// Hash applied to the receipt digest material, selected by digest_algo.
+// keccak256 is a native hostio; sha256 goes through the precompile and
+// costs a few hundred extra gas per receipt (60 + 12 per word plus the
+// call overhead, against keccak's 30 + 6 per word).
+const DIGEST_KECCAK256: u8 = 0;
+const DIGEST_SHA256: u8 = 1;
+
+// sha256 precompile: input is the message, output the 32-byte hash.
+const SHA256: Address = address!("0000000000000000000000000000000000000002");
+
+// Status codes returned by check_receipt.
+const STATUS_OK: u8 = 0;
+const STATUS_UNAUTHORIZED_HARDWARE: u8 = 1;
+const STATUS_FIRMWARE_NOT_APPROVED: u8 = 2;
+const STATUS_REPLAY_DETECTED: u8 = 3;
+const STATUS_DIGEST_MISMATCH: u8 = 4;
+const STATUS_FIRMWARE_DOWNGRADE: u8 = 5;
+const STATUS_RATE_LIMITED: u8 = 6;
+const STATUS_FIRMWARE_EXPIRED: u8 = 7;
+const STATUS_EXEC_NOT_APPROVED: u8 = 8;
+const STATUS_NODE_EXPIRED: u8 = 9;
+const STATUS_NODE_BLOCKED: u8 = 10;
+const STATUS_SIGNATURE_REQUIRED: u8 = 11;
+const STATUS_OTHER: u8 = u8::MAX;
 
 sol! {
     error AlreadyInitialized();
@@ -20,6 +188,38 @@ sol! {
     error ReplayDetected();
     error DigestMismatch();
     error InvalidOwner();
+    error FirmwareDowngrade();
+    error InvalidReplayWindow();
+    error NodeCapExceeded();
+    error RateLimited();
+    error InvalidCounterBits();
+    error CounterOutOfRange();
+    error MaxCounterReached();
+    error FirmwareExpired();
+    error InvalidExpiry();
+    error FirmwareApprovalsFrozen();
+    error ExecNotApproved();
+    error InvalidDigestAlgo();
+    error HashUnavailable();
+    error NodeAuthorizationExpired();
+    error NodeBlocked();
+    error DomainTagTooLong();
+    error SignatureRequired();
+
+    event ReceiptAnchored(uint64 seq, bytes32 indexed digest, bytes32 prev_root, bytes32 new_root);
+    event FirmwarePurged(uint64 seq, bytes32 indexed fw_hash);
+    event NodePurged(uint64 seq, bytes32 indexed node_id);
+    event ReceiptAccepted(uint64 seq, bytes32 indexed hw_id, bytes32 indexed fw_hash, uint64 counter, bytes32 digest, uint64 firmware_receipts);
+    event ReceiptRejected(uint64 seq, bytes32 indexed hw_id, uint8 reason);
+    event NodeAuthorized(uint64 seq, bytes32 indexed node_id, address indexed by);
+    event NodeRevoked(uint64 seq, bytes32 indexed node_id, address indexed by);
+    event FirmwareApproved(uint64 seq, bytes32 indexed fw_hash, address indexed by);
+    event FirmwareRevoked(uint64 seq, bytes32 indexed fw_hash, address indexed by);
+    event FirmwareEvicted(uint64 seq, bytes32 indexed fw_hash);
+    event ReanchorConsumed(uint64 seq, bytes32 indexed node_id, uint64 counter);
+    event BlockedNodeAdded(uint64 seq, bytes32 indexed node_id, address indexed by);
+    event BlockedNodeRemoved(uint64 seq, bytes32 indexed node_id, address indexed by);
+    event ConfigChanged(uint64 seq, bytes4 indexed selector, address indexed by);
 }
 
 sol_storage! {
@@ -29,6 +229,91 @@ sol_storage! {
         mapping(bytes32 => bool) authorized_nodes;
         mapping(bytes32 => bool) approved_firmware;
         mapping(bytes32 => uint64) counters;
+        // Append-only hash chain over accepted digests:
+        // root = keccak256(root || digest), starting from zero.
+        bytes32 receipt_root;
+        // Release ordering for approved firmware (0 = unversioned) and the
+        // lowest version each node may still present.
+        mapping(bytes32 => uint32) firmware_version;
+        mapping(bytes32 => uint32) min_firmware_version;
+        // How far below a node's highest counter a late receipt may land
+        // (0 = strict monotonic). Per node, seen_counters packs the base
+        // counter in bits 0..64 and open flags in bits 64..256: bit 64 + i - 1
+        // is set while counter base - i has been skipped but not yet seen.
+        // Flags are only meaningful while base equals counters[node]; a stale
+        // word (left behind by strict-mode advances) reads as all-seen.
+        uint64 replay_window;
+        mapping(bytes32 => uint256) seen_counters;
+        // Per-node receipt statistics. Reverted calls roll back storage, so
+        // rejections are only counted by the non-reverting submit_receipt
+        // and report_receipt.
+        mapping(bytes32 => uint64) accepted_count;
+        mapping(bytes32 => uint64) rejected_count;
+        // Cap on simultaneously authorized nodes (0 = no cap).
+        uint64 max_authorized_nodes;
+        uint64 active_node_count;
+        // Domain tag prefixed to the digest material: the first
+        // domain_tag_len bytes of domain_tag, where 0 selects DOMAIN.
+        bytes32 domain_tag;
+        uint8 domain_tag_len;
+        // Block timestamp of each node's last accepted receipt, and the
+        // minimum seconds between accepted receipts per node (0 = no limit).
+        mapping(bytes32 => uint64) last_verified_at;
+        uint64 min_receipt_interval;
+        // Width of device counters in bits (0 = MAX_COUNTER_BITS).
+        uint8 counter_bits;
+        // Block timestamp from which an approval lapses (0 = never). Only
+        // approve_firmware_until sets it; the other approval paths clear it.
+        mapping(bytes32 => uint64) firmware_expiry;
+        // One-way latch: once set, no approval path may add firmware.
+        bool firmware_frozen;
+        // Optional catalog of certified computations; while enabled, only
+        // receipts whose exec_hash is approved verify.
+        bool exec_allowlist_enabled;
+        mapping(bytes32 => bool) approved_exec;
+        // Accepted receipts per firmware hash across all nodes.
+        mapping(bytes32 => uint64) firmware_receipt_count;
+        // Hash over the receipt digest material: DIGEST_KECCAK256 (default)
+        // or DIGEST_SHA256.
+        uint8 digest_algo;
+        // Approval order for the max_firmware cap (0 = no cap), oldest
+        // first from firmware_queue_head. An entry at index i is live while
+        // its hash is approved and firmware_queue_pos[hash] == i + 1;
+        // entries left behind by revocation or re-approval are skipped.
+        bytes32[] firmware_queue;
+        uint64 firmware_queue_head;
+        mapping(bytes32 => uint64) firmware_queue_pos;
+        uint64 approved_firmware_count;
+        uint64 max_firmware;
+        // Block timestamp from which a node's authorization lapses
+        // (u64::MAX = never, 0 = not authorized). Every authorization
+        // path sets it; revoke_node and purge_node clear it.
+        mapping(bytes32 => uint64) node_expiry;
+        // Per-node provisioning watermark: counters at or below it are
+        // refused even while counters[node] is still below it (0 = none).
+        mapping(bytes32 => uint64) counter_floor;
+        // Sequence number of the last emitted event. Every event carries
+        // the next one as its first field, so indexers can detect missed
+        // events. Not every write emits one: submit_receipt's rejected_count
+        // tally is silent.
+        uint64 event_seq;
+        // One-shot reorg recovery: while set, the node's next receipt may
+        // repeat its stored counter. Accepting one clears the flag.
+        mapping(bytes32 => bool) allow_reanchor;
+        // Incident-response blocklist, checked before authorization. Kept
+        // apart from authorized_nodes so re-authorizing cannot unblock.
+        mapping(bytes32 => bool) blocked_nodes;
+        // Reverse index: nodes with an accepted receipt under each
+        // firmware, in first-use order. Append-only; firmware_node_seen
+        // keeps each node to one entry per firmware.
+        mapping(bytes32 => bytes32[]) firmware_nodes;
+        mapping(bytes32 => mapping(bytes32 => bool)) firmware_node_seen;
+        // Contracts allowed to anchor receipts whose digest they checked
+        // themselves (anchor_receipt), e.g. AnchorVariants.
+        mapping(address => bool) verifiers;
+        // Nodes whose receipts only a verifier may anchor, after checking
+        // their signature (AnchorGateway's P-256 and delegated paths).
+        mapping(bytes32 => bool) signature_required;
     }
 }
 
@@ -41,20 +326,215 @@ pub enum HardwareAnchorError {
     ReplayDetected(ReplayDetected),
     DigestMismatch(DigestMismatch),
     InvalidOwner(InvalidOwner),
+    FirmwareDowngrade(FirmwareDowngrade),
+    InvalidReplayWindow(InvalidReplayWindow),
+    NodeCapExceeded(NodeCapExceeded),
+    RateLimited(RateLimited),
+    InvalidCounterBits(InvalidCounterBits),
+    CounterOutOfRange(CounterOutOfRange),
+    MaxCounterReached(MaxCounterReached),
+    FirmwareExpired(FirmwareExpired),
+    InvalidExpiry(InvalidExpiry),
+    FirmwareApprovalsFrozen(FirmwareApprovalsFrozen),
+    ExecNotApproved(ExecNotApproved),
+    InvalidDigestAlgo(InvalidDigestAlgo),
+    HashUnavailable(HashUnavailable),
+    NodeAuthorizationExpired(NodeAuthorizationExpired),
+    NodeBlocked(NodeBlocked),
+    DomainTagTooLong(DomainTagTooLong),
+    SignatureRequired(SignatureRequired),
+}
+
+impl HardwareAnchorError {
+    fn status_code(&self) -> u8 {
+        match self {
+            Self::UnauthorizedHardware(_) => STATUS_UNAUTHORIZED_HARDWARE,
+            Self::FirmwareNotApproved(_) => STATUS_FIRMWARE_NOT_APPROVED,
+            Self::ReplayDetected(_) => STATUS_REPLAY_DETECTED,
+            Self::DigestMismatch(_) => STATUS_DIGEST_MISMATCH,
+            Self::FirmwareDowngrade(_) => STATUS_FIRMWARE_DOWNGRADE,
+            Self::RateLimited(_) => STATUS_RATE_LIMITED,
+            Self::FirmwareExpired(_) => STATUS_FIRMWARE_EXPIRED,
+            Self::ExecNotApproved(_) => STATUS_EXEC_NOT_APPROVED,
+            Self::NodeAuthorizationExpired(_) => STATUS_NODE_EXPIRED,
+            Self::NodeBlocked(_) => STATUS_NODE_BLOCKED,
+            Self::SignatureRequired(_) => STATUS_SIGNATURE_REQUIRED,
+            _ => STATUS_OTHER,
+        }
+    }
+}
+
+/// Canonical hw_id of a device: keccak256 over its raw 16-byte eFuse id
+/// followed by the 32-byte provisioning salt (48 bytes, no domain tag or
+/// length prefix). Host code links it with the `offchain` feature;
+/// AnchorLens's preview_hw_id exposes it on-chain.
+///
+/// ```
+/// use stylus_hardware_anchor::derive_hw_id;
+/// use stylus_sdk::alloy_primitives::{fixed_bytes, FixedBytes};
+///
+/// let efuse: [u8; 16] = core::array::from_fn(|i| i as u8);
+/// let hw_id = derive_hw_id(&efuse, FixedBytes::repeat_byte(0xaa));
+/// assert_eq!(
+///     hw_id,
+///     fixed_bytes!("5c6761fb3a25a437482a0fa9d857af8f41ac7fc92dccc8f8eac74779ba69f88a")
+/// );
+/// ```
+pub fn derive_hw_id(efuse: &[u8; 16], salt: FixedBytes<32>) -> FixedBytes<32> {
+    let mut material = [0u8; 48];
+    material[..16].copy_from_slice(efuse);
+    material[16..].copy_from_slice(salt.as_slice());
+    keccak256(material)
+}
+
+/// Receipt digest exactly as verify_receipt reconstructs it under the
+/// default domain tag and digest_algo. Host code (backends pre-validating
+/// receipts) links it with the `offchain` feature. With `legacy-nochainid`
+/// it hashes the NEXUS_RCT_V1 layout and ignores `chain_id`.
+///
+/// ```
+/// use stylus_hardware_anchor::reconstruct_digest;
+/// use stylus_sdk::alloy_primitives::{fixed_bytes, FixedBytes};
+///
+/// let digest = reconstruct_digest(
+///     42161,
+///     FixedBytes::repeat_byte(0x11),
+///     FixedBytes::repeat_byte(0x22),
+///     FixedBytes::repeat_byte(0x33),
+///     7,
+/// );
+/// # #[cfg(not(feature = "legacy-nochainid"))]
+/// assert_eq!(
+///     digest,
+///     fixed_bytes!("91953eb887cdf7c565a199c094c05fec89a750fa3eada5953aa31d555a7699e6")
+/// );
+/// ```
+pub fn reconstruct_digest(
+    chain_id: u64,
+    hw_id: FixedBytes<32>,
+    fw_hash: FixedBytes<32>,
+    exec_hash: FixedBytes<32>,
+    counter: u64,
+) -> FixedBytes<32> {
+    keccak256(receipt_codec::encode_material(
+        chain_id, hw_id, fw_hash, exec_hash, counter,
+    ))
+}
+
+/// The stateless checks of verify_receipt under the default configuration
+/// (64-bit counters, default domain tag and digest_algo): counter range,
+/// then digest reconstruction. Node, firmware, replay and rate-limit checks
+/// read contract state and are left to the contract.
+///
+/// ```
+/// use stylus_hardware_anchor::{check_receipt_offchain, reconstruct_digest};
+/// use stylus_sdk::alloy_primitives::FixedBytes;
+///
+/// let (hw_id, fw_hash, exec_hash) = (
+///     FixedBytes::repeat_byte(0x11),
+///     FixedBytes::repeat_byte(0x22),
+///     FixedBytes::repeat_byte(0x33),
+/// );
+/// let digest = reconstruct_digest(42161, hw_id, fw_hash, exec_hash, 7);
+/// assert!(check_receipt_offchain(42161, hw_id, fw_hash, exec_hash, 7, digest).is_ok());
+/// assert!(check_receipt_offchain(42161, hw_id, fw_hash, exec_hash, 8, digest).is_err());
+/// assert!(check_receipt_offchain(42161, hw_id, fw_hash, exec_hash, u64::MAX, digest).is_err());
+/// ```
+pub fn check_receipt_offchain(
+    chain_id: u64,
+    hw_id: FixedBytes<32>,
+    fw_hash: FixedBytes<32>,
+    exec_hash: FixedBytes<32>,
+    counter: u64,
+    claimed_digest: FixedBytes<32>,
+) -> Result<(), HardwareAnchorError> {
+    check_counter_width(counter, MAX_COUNTER_BITS)?;
+    let reconstructed = reconstruct_digest(chain_id, hw_id, fw_hash, exec_hash, counter);
+    StylusHardwareAnchor::match_digest(reconstructed, claimed_digest)?;
+    Ok(())
+}
+
+/// Key of a node's counter in an anchor_receipt namespace (for a tenant
+/// domain, keccak256 of its tag): keccak256(namespace || hw_id).
+fn domain_counter_id(namespace: FixedBytes<32>, hw_id: FixedBytes<32>) -> FixedBytes<32> {
+    let mut key = [0u8; 64];
+    key[..32].copy_from_slice(namespace.as_slice());
+    key[32..].copy_from_slice(hw_id.as_slice());
+    keccak256(key)
+}
+
+/// Counter-width policy shared by the contract and check_receipt_offchain.
+fn check_counter_width(counter: u64, bits: u8) -> Result<(), HardwareAnchorError> {
+    let max_counter = u64::MAX >> (MAX_COUNTER_BITS - bits);
+    if counter > max_counter {
+        return Err(HardwareAnchorError::CounterOutOfRange(CounterOutOfRange {}));
+    }
+    if counter > max_counter - COUNTER_HEADROOM {
+        return Err(HardwareAnchorError::MaxCounterReached(MaxCounterReached {}));
+    }
+    Ok(())
 }
 
 #[public]
 impl StylusHardwareAnchor {
     pub fn initialize(&mut self) -> Result<(), HardwareAnchorError> {
+        self.initialize_with(msg::sender(), Bytes(DOMAIN.to_vec()))
+    }
+
+    /// One-time initialization with an explicit owner and domain tag, for
+    /// factory deployments where the sender is not the intended owner.
+    /// An empty tag (or DOMAIN itself) keeps the default 125-byte layout;
+    /// custom tags are at most 32 bytes, longer ones revert with
+    /// DomainTagTooLong.
+    pub fn initialize_with(
+        &mut self,
+        owner: Address,
+        domain_tag: Bytes,
+    ) -> Result<(), HardwareAnchorError> {
         if self.owner.get() != Address::ZERO {
             return Err(HardwareAnchorError::AlreadyInitialized(
                 AlreadyInitialized {},
             ));
         }
-        self.owner.set(msg::sender());
+        if owner == Address::ZERO {
+            return Err(HardwareAnchorError::InvalidOwner(InvalidOwner {}));
+        }
+        if domain_tag.len() > 32 {
+            return Err(HardwareAnchorError::DomainTagTooLong(DomainTagTooLong {}));
+        }
+        self.owner.set(owner);
+        if domain_tag.as_slice() != DOMAIN.as_slice() {
+            self.domain_tag
+                .set(FixedBytes::right_padding_from(domain_tag.as_slice()));
+            self.domain_tag_len.set(U8::from(domain_tag.len() as u8));
+        }
+        self.config_changed(function_selector!("initializeWith", Address, Bytes));
         Ok(())
     }
 
+    /// Storage touched by an accepted call with the default configuration
+    /// (no replay window, rate limit, expiry or custom domain tag):
+    ///
+    /// - reads: blocked_nodes[hw_id], authorized_nodes[hw_id], node_expiry[hw_id],
+    ///   approved_firmware[fw_hash],
+    ///   firmware_expiry[fw_hash], firmware_version[fw_hash],
+    ///   min_firmware_version[hw_id], counter_bits, counter_floor[hw_id],
+    ///   counters[hw_id], allow_reanchor[hw_id] (only at the stored counter),
+    ///   min_receipt_interval, last_verified_at[hw_id], domain_tag, digest_algo,
+    ///   exec_allowlist_enabled, replay_window, accepted_count[hw_id],
+    ///   firmware_receipt_count[fw_hash], firmware_node_seen[fw_hash][hw_id],
+    ///   receipt_root
+    /// - writes: counters[hw_id], allow_reanchor[hw_id] (cleared),
+    ///   accepted_count[hw_id], firmware_receipt_count[fw_hash],
+    ///   last_verified_at[hw_id], receipt_root
+    ///
+    /// A nonzero replay window adds a read and write of
+    /// seen_counters[hw_id]; a re-anchor also clears allow_reanchor[hw_id]. A
+    /// node's first receipt under fw_hash also writes
+    /// firmware_node_seen[fw_hash][hw_id] and appends to
+    /// firmware_nodes[fw_hash].
+    /// The digest itself is built in a fixed 125-byte stack buffer
+    /// (reconstruct_digest) and does not allocate.
     pub fn verify_receipt(
         &mut self,
         hw_id: FixedBytes<32>,
@@ -63,253 +543,2451 @@ impl StylusHardwareAnchor {
         counter: u64,
         claimed_digest: FixedBytes<32>,
     ) -> Result<(), HardwareAnchorError> {
-        if !self.authorized_nodes.get(hw_id) {
-            return Err(HardwareAnchorError::UnauthorizedHardware(
-                UnauthorizedHardware {},
-            ));
-        }
-        if !self.approved_firmware.get(fw_hash) {
-            return Err(HardwareAnchorError::FirmwareNotApproved(
-                FirmwareNotApproved {},
-            ));
-        }
+        self.verify_receipt_returning(hw_id, fw_hash, exec_hash, counter, claimed_digest)?;
+        Ok(())
+    }
+
+    /// verify_receipt that returns the reconstructed digest on success, so
+    /// callers need not rebuild the digest material themselves.
+    pub fn verify_receipt_returning(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<FixedBytes<32>, HardwareAnchorError> {
+        let reconstructed = self.check_receipt_at(
+            block::chainid(),
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+            claimed_digest,
+        )?;
 
-        // Convert u64 to U64 for comparison
-        let counter_u64 = U64::from(counter);
-        let last_counter = self.counters.get(hw_id);
+        self.record_receipt(hw_id, fw_hash, counter, reconstructed);
+        Ok(reconstructed)
+    }
 
-        if counter_u64 <= last_counter {
-            return Err(HardwareAnchorError::ReplayDetected(ReplayDetected {}));
+    /// Non-reverting verify_receipt: accepted receipts update state exactly
+    /// as verify_receipt does, rejected ones are tallied in the node's
+    /// rejected_count. Returns the check_receipt status code.
+    pub fn submit_receipt(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> u8 {
+        match self.try_receipt(hw_id, fw_hash, exec_hash, counter, claimed_digest) {
+            Ok(_) => STATUS_OK,
+            Err(e) => e.status_code(),
         }
+    }
 
-        let chain_id = block::chainid();
-        let reconstructed = Self::compute_digest(chain_id, hw_id, fw_hash, exec_hash, counter);
+    /// Telemetry-friendly submit_receipt: emits ReceiptAccepted or
+    /// ReceiptRejected (reason = check_receipt status code) instead of
+    /// reverting, so monitoring sees every outcome. Returns acceptance.
+    pub fn report_receipt(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> bool {
+        match self.try_receipt(hw_id, fw_hash, exec_hash, counter, claimed_digest) {
+            Ok(digest) => {
+                evm::log(ReceiptAccepted {
+                    seq: self.next_event_seq(),
+                    hw_id,
+                    fw_hash,
+                    counter,
+                    digest,
+                    firmware_receipts: self.firmware_receipt_count.get(fw_hash).saturating_to(),
+                });
+                true
+            }
+            Err(e) => {
+                evm::log(ReceiptRejected {
+                    seq: self.next_event_seq(),
+                    hw_id,
+                    reason: e.status_code(),
+                });
+                false
+            }
+        }
+    }
 
-        if reconstructed != claimed_digest {
-            return Err(HardwareAnchorError::DigestMismatch(DigestMismatch {}));
+    /// Anchor a receipt whose digest the calling verifier (set_verifier)
+    /// has already reconstructed and matched, e.g. under another material
+    /// layout or a tenant domain. Every state check of verify_receipt
+    /// applies and state is updated as it would be, except that a nonzero
+    /// `namespace` keeps the replay counter and window under
+    /// keccak256(namespace || hw_id) instead of hw_id itself, so tenants
+    /// sharing a node do not consume each other's counters.
+    pub fn anchor_receipt(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        namespace: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        digest: FixedBytes<32>,
+    ) -> Result<(), HardwareAnchorError> {
+        if !self.verifiers.get(msg::sender()) {
+            return Err(HardwareAnchorError::UnauthorizedCaller(
+                UnauthorizedCaller {},
+            ));
         }
+        let counter_id = if namespace == FixedBytes::ZERO {
+            hw_id
+        } else {
+            domain_counter_id(namespace, hw_id)
+        };
+        self.check_policy(hw_id, counter_id, fw_hash, Some(exec_hash), counter)?;
 
-        // Store as U64
-        self.counters.insert(hw_id, counter_u64);
+        self.record_receipt_in(hw_id, counter_id, fw_hash, counter, digest);
         Ok(())
     }
 
-    pub fn verify_receipts_batch(&self, packed: Vec<u8>) -> Vec<bool> {
-        if packed.is_empty() {
-            return Vec::new();
+    /// Dry run of verify_receipt: runs the same checks without touching
+    /// state and returns a status code (see the `STATUS_*` constants).
+    pub fn check_receipt(
+        &self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> u8 {
+        match self.check_receipt_at(
+            block::chainid(),
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+            claimed_digest,
+        ) {
+            Ok(_) => STATUS_OK,
+            Err(e) => e.status_code(),
         }
+    }
 
-        if !packed.len().is_multiple_of(PACKED_RECEIPT_LEN) {
-            return Vec::new();
+    /// Pre-flight for devices: would a receipt from `hw_id` on `fw_hash`
+    /// at `counter` be accepted right now, before the device computes its
+    /// digest? Applies the check_receipt policy checks in the same order,
+    /// skipping only the exec allowlist and the digest, and returns:
+    ///
+    /// - STATUS_OK (0): anchorable
+    /// - STATUS_NODE_BLOCKED (10): on the blocklist
+    /// - STATUS_UNAUTHORIZED_HARDWARE (1), STATUS_NODE_EXPIRED (9)
+    /// - STATUS_FIRMWARE_NOT_APPROVED (2), STATUS_FIRMWARE_EXPIRED (7),
+    ///   STATUS_FIRMWARE_DOWNGRADE (5)
+    /// - STATUS_REPLAY_DETECTED (3): at or below the stored counter or
+    ///   counter floor, and not open in the replay window
+    /// - STATUS_RATE_LIMITED (6)
+    /// - STATUS_OTHER (255): counter outside the configured counter width
+    pub fn is_anchorable(
+        &self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        counter: u64,
+    ) -> u8 {
+        match self.check_policy(hw_id, hw_id, fw_hash, None, counter) {
+            Ok(()) => STATUS_OK,
+            Err(e) => e.status_code(),
         }
+    }
 
-        let chain_id = block::chainid();
-        let count = packed.len() / PACKED_RECEIPT_LEN;
-        let mut results = Vec::with_capacity(count);
+    pub fn authorize_node(&mut self, node_id: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.authorize(node_id, u64::MAX)
+    }
+
+    /// authorize_node with an authorization that lapses once the block
+    /// timestamp reaches `expiry`, so a lost device stops verifying even
+    /// if nobody revokes it. u64::MAX never lapses; 0 is rejected with
+    /// InvalidExpiry, since it reads as "not authorized".
+    pub fn authorize_node_until(
+        &mut self,
+        node_id: FixedBytes<32>,
+        expiry: u64,
+    ) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        if expiry == 0 {
+            return Err(HardwareAnchorError::InvalidExpiry(InvalidExpiry {}));
+        }
+        self.authorize(node_id, expiry)
+    }
 
-        for i in 0..count {
-            let start = i * PACKED_RECEIPT_LEN;
-            let end = start + PACKED_RECEIPT_LEN;
-            let receipt = &packed[start..end];
-            results.push(self.verify_packed_receipt(chain_id, receipt));
+    pub fn revoke_node(&mut self, node_id: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        if self.authorized_nodes.get(node_id) {
+            let active = self.active_node_count.get();
+            self.active_node_count.set(active - U64::from(1));
         }
+        self.authorized_nodes.insert(node_id, false);
+        self.node_expiry.delete(node_id);
+        evm::log(NodeRevoked {
+            seq: self.next_event_seq(),
+            node_id,
+            by: msg::sender(),
+        });
+        Ok(())
+    }
 
-        results
+    /// Hard-block a node: every verification path rejects it with
+    /// NodeBlocked ahead of all other checks, whether or not it is
+    /// authorized. Authorization is left untouched, and neither
+    /// authorize_node nor purge_node lifts the block; only unblock_node does.
+    pub fn block_node(&mut self, node_id: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.blocked_nodes.insert(node_id, true);
+        evm::log(BlockedNodeAdded {
+            seq: self.next_event_seq(),
+            node_id,
+            by: msg::sender(),
+        });
+        Ok(())
     }
 
-    pub fn verify_receipts_batch_bytes(&self, packed: Bytes) -> Vec<bool> {
-        self.verify_receipts_batch(packed.to_vec())
+    pub fn unblock_node(&mut self, node_id: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.blocked_nodes.delete(node_id);
+        evm::log(BlockedNodeRemoved {
+            seq: self.next_event_seq(),
+            node_id,
+            by: msg::sender(),
+        });
+        Ok(())
     }
 
-    pub fn verify_receipts_batch_bitset(&self, packed: Vec<u8>) -> FixedBytes<32> {
-        if packed.is_empty() {
-            return FixedBytes::<32>::ZERO;
+    /// Revoke a node and zero its authorization slots, leaving it
+    /// indistinguishable from a never-authorized id. Clearing a nonzero slot
+    /// earns the EIP-3529 refund (4800 gas, capped at a fifth of the
+    /// transaction's gas). The replay counter, its seen flags and the
+    /// minimum firmware version are kept so re-authorizing the node cannot
+    /// reopen replays or downgrades; authorize_node restores it. The
+    /// node's P-256 key, delegating signer and metadata live on
+    /// AnchorGateway, whose purge_node clears them.
+    pub fn purge_node(&mut self, node_id: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        if self.authorized_nodes.get(node_id) {
+            let active = self.active_node_count.get();
+            self.active_node_count.set(active - U64::from(1));
         }
+        self.authorized_nodes.delete(node_id);
+        self.node_expiry.delete(node_id);
+        evm::log(NodeRevoked {
+            seq: self.next_event_seq(),
+            node_id,
+            by: msg::sender(),
+        });
+        evm::log(NodePurged {
+            seq: self.next_event_seq(),
+            node_id,
+        });
+        Ok(())
+    }
 
-        if !packed.len().is_multiple_of(PACKED_RECEIPT_LEN) {
-            return FixedBytes::<32>::ZERO;
-        }
+    /// Cap the number of simultaneously authorized nodes (0 = no cap).
+    /// Lowering the cap below the active count only blocks new
+    /// authorizations; existing nodes stay authorized.
+    pub fn set_max_authorized_nodes(&mut self, max: u64) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.max_authorized_nodes.set(U64::from(max));
+        self.config_changed(function_selector!("setMaxAuthorizedNodes", u64));
+        Ok(())
+    }
 
-        let count = packed.len() / PACKED_RECEIPT_LEN;
-        if count > 256 {
-            return FixedBytes::<32>::ZERO;
+    pub fn approve_firmware(&mut self, fw_hash: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        if self.firmware_frozen.get() {
+            return Err(HardwareAnchorError::FirmwareApprovalsFrozen(
+                FirmwareApprovalsFrozen {},
+            ));
         }
+        self.admit_firmware(fw_hash);
+        self.firmware_expiry.delete(fw_hash);
+        evm::log(FirmwareApproved {
+            seq: self.next_event_seq(),
+            fw_hash,
+            by: msg::sender(),
+        });
+        Ok(())
+    }
 
-        let chain_id = block::chainid();
-        let mut bits = [0u8; 32];
+    /// approve_firmware with an approval that lapses once the block
+    /// timestamp reaches `expiry`. Lapsed entries fail verification with
+    /// FirmwareExpired until purged or re-approved.
+    pub fn approve_firmware_until(
+        &mut self,
+        fw_hash: FixedBytes<32>,
+        expiry: u64,
+    ) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        if self.firmware_frozen.get() {
+            return Err(HardwareAnchorError::FirmwareApprovalsFrozen(
+                FirmwareApprovalsFrozen {},
+            ));
+        }
+        self.admit_firmware(fw_hash);
+        self.firmware_expiry.insert(fw_hash, U64::from(expiry));
+        evm::log(FirmwareApproved {
+            seq: self.next_event_seq(),
+            fw_hash,
+            by: msg::sender(),
+        });
+        Ok(())
+    }
 
-        for i in 0..count {
-            let start = i * PACKED_RECEIPT_LEN;
-            let end = start + PACKED_RECEIPT_LEN;
-            let receipt = &packed[start..end];
-            if self.verify_packed_receipt(chain_id, receipt) {
-                let byte_index = i / 8;
-                let bit_index = i % 8;
-                bits[byte_index] |= 1u8 << bit_index;
-            }
+    pub fn approve_firmware_versioned(
+        &mut self,
+        fw_hash: FixedBytes<32>,
+        version: u32,
+    ) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        if self.firmware_frozen.get() {
+            return Err(HardwareAnchorError::FirmwareApprovalsFrozen(
+                FirmwareApprovalsFrozen {},
+            ));
         }
+        self.admit_firmware(fw_hash);
+        self.firmware_version.insert(fw_hash, U32::from(version));
+        self.firmware_expiry.delete(fw_hash);
+        evm::log(FirmwareApproved {
+            seq: self.next_event_seq(),
+            fw_hash,
+            by: msg::sender(),
+        });
+        Ok(())
+    }
 
-        FixedBytes::<32>::from(bits)
+    /// Refuse counters at or below `floor` for a node, e.g. those consumed
+    /// by burn-in before provisioning. Receipts must then carry a counter
+    /// above both the stored counter and the floor.
+    pub fn set_counter_floor(
+        &mut self,
+        node_id: FixedBytes<32>,
+        floor: u64,
+    ) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.counter_floor.insert(node_id, U64::from(floor));
+        self.config_changed(function_selector!("setCounterFloor", FixedBytes<32>, u64));
+        Ok(())
     }
 
-    pub fn verify_receipts_batch_bitset_bytes(&self, packed: Bytes) -> FixedBytes<32> {
-        self.verify_receipts_batch_bitset(packed.to_vec())
+    /// Let the node's next receipt repeat its stored counter, e.g. to
+    /// re-anchor a receipt whose transaction a reorg dropped. One-shot:
+    /// the first receipt accepted at the stored counter clears the flag
+    /// (emitting ReanchorConsumed), so a second one is a replay again. A
+    /// higher counter accepted first also clears it, so the flag cannot
+    /// be spent later on that counter. Only the node's own counter is
+    /// re-anchored; tenant namespaces (anchor_receipt) never are.
+    pub fn set_allow_reanchor(
+        &mut self,
+        node_id: FixedBytes<32>,
+        allowed: bool,
+    ) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.allow_reanchor.insert(node_id, allowed);
+        self.config_changed(function_selector!("setAllowReanchor", FixedBytes<32>, bool));
+        Ok(())
     }
 
-    pub fn compute_receipt_digests_batch(&self, packed: Vec<u8>) -> Vec<FixedBytes<32>> {
-        if packed.is_empty() {
-            return Vec::new();
-        }
+    /// Accept the node's receipts only through anchor_receipt, from a
+    /// verifier that checked the node's signature (AnchorGateway's
+    /// verify_receipt_p256 and verify_receipt_delegated). While set, the
+    /// unsigned paths (verify_receipt, submit_receipt, report_receipt)
+    /// refuse it with SignatureRequired, and check_receipt returns
+    /// STATUS_SIGNATURE_REQUIRED (11).
+    pub fn set_signature_required(
+        &mut self,
+        node_id: FixedBytes<32>,
+        required: bool,
+    ) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.signature_required.insert(node_id, required);
+        self.config_changed(function_selector!(
+            "setSignatureRequired",
+            FixedBytes<32>,
+            bool
+        ));
+        Ok(())
+    }
 
-        if !packed.len().is_multiple_of(PACKED_RECEIPT_V2_LEN) {
-            return Vec::new();
+    /// Raise the lowest firmware version a node may present. The floor only
+    /// moves up; lowering it would reopen the downgrade path.
+    pub fn set_min_firmware_version(
+        &mut self,
+        node_id: FixedBytes<32>,
+        version: u32,
+    ) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        let version = U32::from(version);
+        if version < self.min_firmware_version.get(node_id) {
+            return Err(HardwareAnchorError::FirmwareDowngrade(FirmwareDowngrade {}));
         }
+        self.min_firmware_version.insert(node_id, version);
+        self.config_changed(function_selector!(
+            "setMinFirmwareVersion",
+            FixedBytes<32>,
+            u32
+        ));
+        Ok(())
+    }
+
+    /// Cap the approved firmware set at `max` hashes (0 = no cap). Past the
+    /// cap each new approval evicts the oldest-approved hash, emitting
+    /// FirmwareEvicted; lowering the cap takes effect on the next approval.
+    pub fn set_max_firmware(&mut self, max: u64) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.max_firmware.set(U64::from(max));
+        self.config_changed(function_selector!("setMaxFirmware", u64));
+        Ok(())
+    }
+
+    /// Permanently stop approving firmware: every approval path reverts
+    /// with FirmwareApprovalsFrozen afterwards, while revocation and
+    /// purging still work, so the approved set can only shrink.
+    pub fn freeze_firmware_approvals(&mut self) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.firmware_frozen.set(true);
+        self.config_changed(function_selector!("freezeFirmwareApprovals"));
+        Ok(())
+    }
 
-        let chain_id = block::chainid();
-        let count = packed.len() / PACKED_RECEIPT_V2_LEN;
-        let mut digests = Vec::with_capacity(count);
+    pub fn revoke_firmware(&mut self, fw_hash: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.release_firmware(fw_hash);
+        self.approved_firmware.insert(fw_hash, false);
+        evm::log(FirmwareRevoked {
+            seq: self.next_event_seq(),
+            fw_hash,
+            by: msg::sender(),
+        });
+        Ok(())
+    }
 
-        for i in 0..count {
-            let start = i * PACKED_RECEIPT_V2_LEN;
-            let end = start + PACKED_RECEIPT_V2_LEN;
-            let receipt = &packed[start..end];
-            digests.push(Self::compute_digest_from_packed_v1(chain_id, receipt));
+    /// Revoke a firmware hash and zero every slot describing it (approval,
+    /// version and expiry), leaving it indistinguishable from a
+    /// never-approved hash. Pending vendor requests are keyed by their
+    /// commitment and stay pending until the owner finalizes one.
+    /// revoke_firmware already zeroes the approval slot; purging also
+    /// reclaims the metadata slots, each earning the EIP-3529 refund (4800
+    /// gas, capped at a fifth of the transaction's gas).
+    /// approve_firmware(_versioned) restores it. Owner-only, except that
+    /// anyone may purge a hash whose approval has expired (as
+    /// AnchorGateway's sweep_firmware does).
+    pub fn purge_firmware(&mut self, fw_hash: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
+        if !self.firmware_expired(fw_hash) {
+            self.only_owner()?;
         }
+        self.release_firmware(fw_hash);
+        self.approved_firmware.delete(fw_hash);
+        self.firmware_version.delete(fw_hash);
+        self.firmware_expiry.delete(fw_hash);
+        evm::log(FirmwareRevoked {
+            seq: self.next_event_seq(),
+            fw_hash,
+            by: msg::sender(),
+        });
+        evm::log(FirmwarePurged {
+            seq: self.next_event_seq(),
+            fw_hash,
+        });
+        Ok(())
+    }
 
-        digests
+    /// Turn the exec_hash allowlist on or off. Approvals are kept while it
+    /// is off.
+    pub fn set_exec_allowlist_enabled(&mut self, enabled: bool) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.exec_allowlist_enabled.set(enabled);
+        self.config_changed(function_selector!("setExecAllowlistEnabled", bool));
+        Ok(())
     }
 
-    pub fn authorize_node(&mut self, node_id: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
-        if msg::sender() != self.owner.get() {
-            return Err(HardwareAnchorError::UnauthorizedCaller(
-                UnauthorizedCaller {},
-            ));
-        }
-        self.authorized_nodes.insert(node_id, true);
+    pub fn approve_exec(&mut self, exec_hash: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.approved_exec.insert(exec_hash, true);
+        self.config_changed(function_selector!("approveExec", FixedBytes<32>));
         Ok(())
     }
 
-    pub fn revoke_node(&mut self, node_id: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
-        if msg::sender() != self.owner.get() {
-            return Err(HardwareAnchorError::UnauthorizedCaller(
-                UnauthorizedCaller {},
-            ));
+    pub fn revoke_exec(&mut self, exec_hash: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.approved_exec.delete(exec_hash);
+        self.config_changed(function_selector!("revokeExec", FixedBytes<32>));
+        Ok(())
+    }
+
+    /// Select the receipt digest hash: 0 = keccak256, 1 = sha256. Devices
+    /// must hash their material the same way, so switch only alongside a
+    /// fleet firmware update.
+    pub fn set_digest_algo(&mut self, algo: u8) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        if algo != DIGEST_KECCAK256 && algo != DIGEST_SHA256 {
+            return Err(HardwareAnchorError::InvalidDigestAlgo(InvalidDigestAlgo {}));
         }
-        self.authorized_nodes.insert(node_id, false);
+        self.digest_algo.set(U8::from(algo));
+        self.config_changed(function_selector!("setDigestAlgo", u8));
         Ok(())
     }
 
-    pub fn approve_firmware(&mut self, fw_hash: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
-        if msg::sender() != self.owner.get() {
-            return Err(HardwareAnchorError::UnauthorizedCaller(
-                UnauthorizedCaller {},
+    /// Accept late receipts up to `window` counters below a node's highest
+    /// counter, each at most once. 0 restores strict monotonic counters.
+    pub fn set_replay_window(&mut self, window: u64) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        if window > MAX_REPLAY_WINDOW {
+            return Err(HardwareAnchorError::InvalidReplayWindow(
+                InvalidReplayWindow {},
             ));
         }
-        self.approved_firmware.insert(fw_hash, true);
+        self.replay_window.set(U64::from(window));
+        self.config_changed(function_selector!("setReplayWindow", u64));
         Ok(())
     }
 
-    pub fn revoke_firmware(&mut self, fw_hash: FixedBytes<32>) -> Result<(), HardwareAnchorError> {
-        if msg::sender() != self.owner.get() {
-            return Err(HardwareAnchorError::UnauthorizedCaller(
-                UnauthorizedCaller {},
+    /// Rate-limit each node to one accepted receipt per `seconds`, measured
+    /// by block timestamp. 0 disables rate limiting.
+    pub fn set_min_receipt_interval(&mut self, seconds: u64) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.min_receipt_interval.set(U64::from(seconds));
+        self.config_changed(function_selector!("setMinReceiptInterval", u64));
+        Ok(())
+    }
+
+    /// Set the device counter width in bits, MIN_COUNTER_BITS (16) through
+    /// MAX_COUNTER_BITS (64); 0 restores the full 64 bits. Counters wider
+    /// than the width are rejected with CounterOutOfRange, and the top
+    /// COUNTER_HEADROOM values with MaxCounterReached.
+    pub fn set_counter_bits(&mut self, bits: u8) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        if bits != 0 && !(MIN_COUNTER_BITS..=MAX_COUNTER_BITS).contains(&bits) {
+            return Err(HardwareAnchorError::InvalidCounterBits(
+                InvalidCounterBits {},
             ));
         }
-        self.approved_firmware.insert(fw_hash, false);
+        self.counter_bits.set(U8::from(bits));
+        self.config_changed(function_selector!("setCounterBits", u8));
         Ok(())
     }
 
+    /// Allow or disallow `verifier` to call anchor_receipt.
+    pub fn set_verifier(
+        &mut self,
+        verifier: Address,
+        allowed: bool,
+    ) -> Result<(), HardwareAnchorError> {
+        self.only_owner()?;
+        self.verifiers.insert(verifier, allowed);
+        self.config_changed(function_selector!("setVerifier", Address, bool));
+        Ok(())
+    }
+
+    /// Hand the anchor to `new_owner`. Deployments needing M-of-N approval
+    /// or break-glass recovery make an AnchorGovernor the owner.
     pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), HardwareAnchorError> {
-        if msg::sender() != self.owner.get() {
-            return Err(HardwareAnchorError::UnauthorizedCaller(
-                UnauthorizedCaller {},
-            ));
-        }
+        self.only_owner()?;
         if new_owner == Address::ZERO {
             return Err(HardwareAnchorError::InvalidOwner(InvalidOwner {}));
         }
         self.owner.set(new_owner);
+        self.config_changed(function_selector!("transferOwnership", Address));
         Ok(())
     }
 
+    /// ERC-165: true for ERC-165 itself, the core anchor interface (whose
+    /// calls every version serves) and the v2 interface.
+    pub fn supports_interface(&self, interface_id: FixedBytes<4>) -> bool {
+        let id = interface_id.0;
+        id == ERC165_INTERFACE_ID || id == ANCHOR_INTERFACE_ID || id == ANCHOR_INTERFACE_ID_V2
+    }
+
+    pub fn contract_version(&self) -> String {
+        CONTRACT_VERSION.into()
+    }
+
+    pub fn build_commit(&self) -> FixedBytes<20> {
+        FixedBytes(BUILD_COMMIT)
+    }
+
     pub fn get_owner(&self) -> Address {
         self.owner.get()
     }
 
+    /// False once the authorization has expired, as for verify_receipt.
     pub fn is_node_authorized(&self, node_id: FixedBytes<32>) -> bool {
-        self.authorized_nodes.get(node_id)
+        self.authorized_nodes.get(node_id) && !self.node_expired(node_id)
     }
 
+    /// False once the approval has expired, as for verify_receipt.
     pub fn is_firmware_approved(&self, fw_hash: FixedBytes<32>) -> bool {
-        self.approved_firmware.get(fw_hash)
+        self.approved_firmware.get(fw_hash) && !self.firmware_expired(fw_hash)
     }
 
     pub fn get_counter(&self, node_id: FixedBytes<32>) -> u64 {
         // Convert U64 to u64 for return
         self.counters.get(node_id).try_into().unwrap_or(0)
     }
+
+    /// Digest verify_receipt expects for these inputs on the current chain;
+    /// zero when sha256 is selected but the chain has no precompile for it.
+    pub fn preview_digest(
+        &self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+    ) -> FixedBytes<32> {
+        self.receipt_digest(block::chainid(), hw_id, fw_hash, exec_hash, counter)
+            .unwrap_or_default()
+    }
+
+    pub fn get_receipt_root(&self) -> FixedBytes<32> {
+        self.receipt_root.get()
+    }
+
+    /// Lowest counter above the node's last accepted one and its counter
+    /// floor, for a device resyncing after losing its counter.
+    pub fn next_valid_counter(&self, node_id: FixedBytes<32>) -> u64 {
+        self.counters
+            .get(node_id)
+            .max(self.counter_floor.get(node_id))
+            .saturating_to::<u64>()
+            .saturating_add(1)
+    }
+
+    /// Whether `counter` passes the current counter-width and replay
+    /// policy (including late counters open in the replay window). The
+    /// other verify_receipt checks are not applied; is_anchorable applies
+    /// them all.
+    pub fn would_accept_counter(&self, node_id: FixedBytes<32>, counter: u64) -> bool {
+        self.check_counter(node_id, node_id, counter).is_ok()
+    }
+
+    /// Raw storage word at `slot`. AnchorLens reads the anchor's state
+    /// through this; anchor_interface::layout says where each field is.
+    pub fn read_slot(&self, slot: FixedBytes<32>) -> FixedBytes<32> {
+        StorageCache::get_word(U256::from_be_bytes(slot.0))
+    }
 }
 
 impl StylusHardwareAnchor {
-    fn verify_packed_receipt(&self, chain_id: u64, receipt: &[u8]) -> bool {
-        if receipt.len() != PACKED_RECEIPT_LEN {
-            return false;
+    fn only_owner(&self) -> Result<(), HardwareAnchorError> {
+        if msg::sender() != self.owner.get() {
+            return Err(HardwareAnchorError::UnauthorizedCaller(
+                UnauthorizedCaller {},
+            ));
         }
+        Ok(())
+    }
 
-        let version = receipt[0];
-        if version != 1 {
-            return false;
+    /// Authorization shared by authorize_node and authorize_node_until:
+    /// idempotent apart from replacing the expiry (u64::MAX = never), and
+    /// subject to max_authorized_nodes for new nodes.
+    fn authorize(
+        &mut self,
+        node_id: FixedBytes<32>,
+        expiry: u64,
+    ) -> Result<(), HardwareAnchorError> {
+        if !self.authorized_nodes.get(node_id) {
+            let active = self.active_node_count.get();
+            let max = self.max_authorized_nodes.get();
+            if max != U64::ZERO && active >= max {
+                return Err(HardwareAnchorError::NodeCapExceeded(NodeCapExceeded {}));
+            }
+            self.authorized_nodes.insert(node_id, true);
+            self.active_node_count.set(active + U64::from(1));
         }
+        self.node_expiry.insert(node_id, U64::from(expiry));
+        evm::log(NodeAuthorized {
+            seq: self.next_event_seq(),
+            node_id,
+            by: msg::sender(),
+        });
+        Ok(())
+    }
 
-        let hw_id = FixedBytes::<32>::from_slice(&receipt[1..33]);
-        let fw_hash = FixedBytes::<32>::from_slice(&receipt[33..65]);
-        let _exec_hash = FixedBytes::<32>::from_slice(&receipt[65..97]);
-        let counter = u64::from_be_bytes(receipt[97..105].try_into().unwrap());
-        let claimed_digest = FixedBytes::<32>::from_slice(&receipt[105..137]);
+    /// Approve a hash and queue it behind the current approvals. A new hash
+    /// beyond max_firmware first evicts (revokes) the oldest approvals,
+    /// FIFO by approval time; re-approving a live hash keeps its place.
+    fn admit_firmware(&mut self, fw_hash: FixedBytes<32>) {
+        if self.approved_firmware.get(fw_hash) {
+            return;
+        }
 
-        if !self.authorized_nodes.get(hw_id) {
-            return false;
+        let max = self.max_firmware.get();
+        let mut count = self.approved_firmware_count.get();
+        let mut head = self.firmware_queue_head.get();
+        while max != U64::ZERO && count >= max {
+            let Some(oldest) = self.firmware_queue.get(head) else {
+                break;
+            };
+            head += U64::from(1);
+            if self.approved_firmware.get(oldest) && self.firmware_queue_pos.get(oldest) == head {
+                self.approved_firmware.insert(oldest, false);
+                self.firmware_queue_pos.delete(oldest);
+                count -= U64::from(1);
+                evm::log(FirmwareEvicted {
+                    seq: self.next_event_seq(),
+                    fw_hash: oldest,
+                });
+            }
         }
+        self.firmware_queue_head.set(head);
+
+        self.firmware_queue.push(fw_hash);
+        self.firmware_queue_pos
+            .insert(fw_hash, U64::from(self.firmware_queue.len()));
+        self.approved_firmware_count.set(count + U64::from(1));
+        self.approved_firmware.insert(fw_hash, true);
+    }
+
+    /// Drop a hash about to be revoked from the max_firmware accounting.
+    fn release_firmware(&mut self, fw_hash: FixedBytes<32>) {
         if !self.approved_firmware.get(fw_hash) {
-            return false;
+            return;
         }
+        let count = self.approved_firmware_count.get();
+        self.approved_firmware_count
+            .set(count.saturating_sub(U64::from(1)));
+        self.firmware_queue_pos.delete(fw_hash);
+    }
 
-        let counter_u64 = U64::from(counter);
-        let last_counter = self.counters.get(hw_id);
-        if counter_u64 <= last_counter {
-            return false;
-        }
+    fn next_event_seq(&mut self) -> u64 {
+        let seq = self.event_seq.get() + U64::from(1);
+        self.event_seq.set(seq);
+        seq.saturating_to()
+    }
 
-        let hw_id = FixedBytes::<32>::from_slice(&receipt[1..33]);
-        let fw_hash = FixedBytes::<32>::from_slice(&receipt[33..65]);
-        let exec_hash = FixedBytes::<32>::from_slice(&receipt[65..97]);
-        let counter = u64::from_be_bytes(receipt[97..105].try_into().unwrap());
+    /// Logs a mutation that has no dedicated event, keyed by the selector
+    /// of the public method that made it.
+    fn config_changed(&mut self, selector: [u8; 4]) {
+        evm::log(ConfigChanged {
+            seq: self.next_event_seq(),
+            selector: selector.into(),
+            by: msg::sender(),
+        });
+    }
 
-        Self::compute_digest(chain_id, hw_id, fw_hash, exec_hash, counter) == claimed_digest
+    fn node_expired(&self, node_id: FixedBytes<32>) -> bool {
+        let expiry: u64 = self.node_expiry.get(node_id).saturating_to();
+        expiry != u64::MAX && block::timestamp() >= expiry
     }
 
-    fn compute_digest_from_packed_v1(chain_id: u64, receipt: &[u8]) -> FixedBytes<32> {
-        let hw_id = FixedBytes::<32>::from_slice(&receipt[1..33]);
-        let fw_hash = FixedBytes::<32>::from_slice(&receipt[33..65]);
-        let exec_hash = FixedBytes::<32>::from_slice(&receipt[65..97]);
-        let counter = u64::from_be_bytes(receipt[97..105].try_into().unwrap());
-        Self::compute_digest(chain_id, hw_id, fw_hash, exec_hash, counter)
+    fn firmware_expired(&self, fw_hash: FixedBytes<32>) -> bool {
+        let expiry: u64 = self.firmware_expiry.get(fw_hash).saturating_to();
+        expiry != 0 && block::timestamp() >= expiry
     }
 
-    fn compute_digest(
-        chain_id: u64,
+    /// Non-reverting verification shared by submit_receipt and
+    /// report_receipt: records accepted receipts and tallies rejections.
+    fn try_receipt(
+        &mut self,
         hw_id: FixedBytes<32>,
         fw_hash: FixedBytes<32>,
         exec_hash: FixedBytes<32>,
         counter: u64,
-    ) -> FixedBytes<32> {
-        let mut material = [0u8; 125];
-        material[0..13].copy_from_slice(DOMAIN);
-        material[13..21].copy_from_slice(&chain_id.to_be_bytes());
-        material[21..53].copy_from_slice(hw_id.as_slice());
-        material[53..85].copy_from_slice(fw_hash.as_slice());
-        material[85..117].copy_from_slice(exec_hash.as_slice());
-        material[117..125].copy_from_slice(&counter.to_be_bytes());
-        keccak256(material)
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<FixedBytes<32>, HardwareAnchorError> {
+        match self.check_receipt_at(
+            block::chainid(),
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+            claimed_digest,
+        ) {
+            Ok(reconstructed) => {
+                self.record_receipt(hw_id, fw_hash, counter, reconstructed);
+                Ok(reconstructed)
+            }
+            Err(e) => {
+                let rejected = self.rejected_count.get(hw_id);
+                self.rejected_count
+                    .insert(hw_id, rejected.saturating_add(U64::from(1)));
+                Err(e)
+            }
+        }
+    }
+
+    /// State updates for an accepted receipt.
+    fn record_receipt(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        counter: u64,
+        digest: FixedBytes<32>,
+    ) {
+        self.record_receipt_in(hw_id, hw_id, fw_hash, counter, digest);
+    }
+
+    /// record_receipt with the replay counter and window kept under
+    /// `counter_id` (hw_id itself, or a domain_counter_id).
+    fn record_receipt_in(
+        &mut self,
+        hw_id: FixedBytes<32>,
+        counter_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        counter: u64,
+        digest: FixedBytes<32>,
+    ) {
+        let last: u64 = self.counters.get(counter_id).saturating_to();
+        let window: u64 = self.replay_window.get().saturating_to();
+
+        if counter > last {
+            // Store as U64
+            self.counters.insert(counter_id, U64::from(counter));
+            if counter_id == hw_id {
+                // The counter a pending re-anchor was for is behind us now.
+                self.allow_reanchor.delete(hw_id);
+            }
+            if window > 0 {
+                // Shift the open flags up to the new base and open every
+                // counter skipped over; the old base itself stays seen.
+                let delta = counter - last;
+                let mut open = self.open_counters(counter_id, last);
+                open = if delta >= MAX_REPLAY_WINDOW {
+                    U256::ZERO
+                } else {
+                    open << delta as usize
+                };
+                let skipped = (delta - 1).min(MAX_REPLAY_WINDOW) as usize;
+                open |= (U256::from(1) << skipped) - U256::from(1);
+                self.store_open_counters(counter_id, counter, open);
+            }
+        } else if counter == last {
+            // Re-anchor allowed by check_counter: consume the flag.
+            self.allow_reanchor.delete(hw_id);
+            evm::log(ReanchorConsumed {
+                seq: self.next_event_seq(),
+                node_id: hw_id,
+                counter,
+            });
+        } else {
+            // Late receipt inside the window: mark it seen.
+            let bit = (last - counter - 1) as usize;
+            let open = self.open_counters(counter_id, last) & !(U256::from(1) << bit);
+            self.store_open_counters(counter_id, last, open);
+        }
+
+        let accepted = self.accepted_count.get(hw_id);
+        self.accepted_count
+            .insert(hw_id, accepted.saturating_add(U64::from(1)));
+        let fw_receipts = self.firmware_receipt_count.get(fw_hash);
+        self.firmware_receipt_count
+            .insert(fw_hash, fw_receipts.saturating_add(U64::from(1)));
+        let mut seen = self.firmware_node_seen.setter(fw_hash);
+        if !seen.get(hw_id) {
+            seen.insert(hw_id, true);
+            self.firmware_nodes.setter(fw_hash).push(hw_id);
+        }
+        self.last_verified_at
+            .insert(hw_id, U64::from(block::timestamp()));
+        self.anchor_digest(digest);
+    }
+
+    /// Open-counter flags relative to `last` (bit i - 1 = counter last - i).
+    fn open_counters(&self, hw_id: FixedBytes<32>, last: u64) -> U256 {
+        let packed = self.seen_counters.get(hw_id);
+        let base: u64 = (packed & U256::from(u64::MAX)).saturating_to();
+        if base != last {
+            return U256::ZERO;
+        }
+        packed >> 64
+    }
+
+    fn store_open_counters(&mut self, hw_id: FixedBytes<32>, base: u64, open: U256) {
+        self.seen_counters
+            .insert(hw_id, (open << 64) | U256::from(base));
+    }
+
+    fn anchor_digest(&mut self, digest: FixedBytes<32>) {
+        let prev_root = self.receipt_root.get();
+        let mut link = [0u8; 64];
+        link[0..32].copy_from_slice(prev_root.as_slice());
+        link[32..64].copy_from_slice(digest.as_slice());
+        let new_root = keccak256(link);
+        self.receipt_root.set(new_root);
+        evm::log(ReceiptAnchored {
+            seq: self.next_event_seq(),
+            digest,
+            prev_root,
+            new_root,
+        });
+    }
+
+    /// Every acceptance check of verify_receipt, in order, without writes.
+    /// Returns the reconstructed digest on success.
+    fn check_receipt_at(
+        &self,
+        chain_id: u64,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<FixedBytes<32>, HardwareAnchorError> {
+        self.check_receipt_state(hw_id, fw_hash, exec_hash, counter)?;
+        let reconstructed = self.receipt_digest(chain_id, hw_id, fw_hash, exec_hash, counter)?;
+        Self::match_digest(reconstructed, claimed_digest)
+    }
+
+    /// The node, firmware, expiry, exec allowlist, counter-width, replay
+    /// and rate-limit checks of verify_receipt, shared by every
+    /// verification path regardless of how the digest is built.
+    fn check_receipt_state(
+        &self,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+    ) -> Result<(), HardwareAnchorError> {
+        if self.signature_required.get(hw_id) {
+            return Err(HardwareAnchorError::SignatureRequired(SignatureRequired {}));
+        }
+        self.check_policy(hw_id, hw_id, fw_hash, Some(exec_hash), counter)
+    }
+
+    /// check_receipt_state, skipping the exec allowlist when `exec_hash`
+    /// is None (is_anchorable). The replay checks use the counter kept
+    /// under `counter_id` (see check_counter).
+    fn check_policy(
+        &self,
+        hw_id: FixedBytes<32>,
+        counter_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: Option<FixedBytes<32>>,
+        counter: u64,
+    ) -> Result<(), HardwareAnchorError> {
+        if self.blocked_nodes.get(hw_id) {
+            return Err(HardwareAnchorError::NodeBlocked(NodeBlocked {}));
+        }
+        if !self.authorized_nodes.get(hw_id) {
+            return Err(HardwareAnchorError::UnauthorizedHardware(
+                UnauthorizedHardware {},
+            ));
+        }
+        if self.node_expired(hw_id) {
+            return Err(HardwareAnchorError::NodeAuthorizationExpired(
+                NodeAuthorizationExpired {},
+            ));
+        }
+        if !self.approved_firmware.get(fw_hash) {
+            return Err(HardwareAnchorError::FirmwareNotApproved(
+                FirmwareNotApproved {},
+            ));
+        }
+        if self.firmware_expired(fw_hash) {
+            return Err(HardwareAnchorError::FirmwareExpired(FirmwareExpired {}));
+        }
+        if let Some(exec_hash) = exec_hash {
+            if self.exec_allowlist_enabled.get() && !self.approved_exec.get(exec_hash) {
+                return Err(HardwareAnchorError::ExecNotApproved(ExecNotApproved {}));
+            }
+        }
+        if self.firmware_version.get(fw_hash) < self.min_firmware_version.get(hw_id) {
+            return Err(HardwareAnchorError::FirmwareDowngrade(FirmwareDowngrade {}));
+        }
+
+        self.check_counter(hw_id, counter_id, counter)?;
+
+        let interval: u64 = self.min_receipt_interval.get().saturating_to();
+        let last_at: u64 = self.last_verified_at.get(hw_id).saturating_to();
+        if interval != 0 && last_at != 0 && block::timestamp().saturating_sub(last_at) < interval {
+            return Err(HardwareAnchorError::RateLimited(RateLimited {}));
+        }
+
+        Ok(())
+    }
+
+    /// Configured counter width in bits; 0 (unset) means the full 64.
+    fn counter_width(&self) -> u8 {
+        match self.counter_bits.get().saturating_to::<u8>() {
+            0 => MAX_COUNTER_BITS,
+            bits => bits,
+        }
+    }
+
+    /// Counter-width and replay checks of check_receipt_state. The floor
+    /// belongs to the node (`hw_id`); the stored counter and replay window
+    /// are those kept under `counter_id`. The re-anchor flag only applies
+    /// to the node's own counter, never to a tenant namespace's.
+    fn check_counter(
+        &self,
+        hw_id: FixedBytes<32>,
+        counter_id: FixedBytes<32>,
+        counter: u64,
+    ) -> Result<(), HardwareAnchorError> {
+        check_counter_width(counter, self.counter_width())?;
+        if counter <= self.counter_floor.get(hw_id).saturating_to::<u64>() {
+            return Err(HardwareAnchorError::ReplayDetected(ReplayDetected {}));
+        }
+
+        let last: u64 = self.counters.get(counter_id).saturating_to();
+        if counter == last && counter_id == hw_id && self.allow_reanchor.get(hw_id) {
+            return Ok(());
+        }
+        if counter <= last {
+            // Only counters skipped within the window and not yet seen pass.
+            let diff = last - counter;
+            let window: u64 = self.replay_window.get().saturating_to();
+            if diff == 0
+                || diff > window
+                || !self
+                    .open_counters(counter_id, last)
+                    .bit((diff - 1) as usize)
+            {
+                return Err(HardwareAnchorError::ReplayDetected(ReplayDetected {}));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn match_digest(
+        reconstructed: FixedBytes<32>,
+        claimed_digest: FixedBytes<32>,
+    ) -> Result<FixedBytes<32>, HardwareAnchorError> {
+        if reconstructed != claimed_digest {
+            return Err(HardwareAnchorError::DigestMismatch(DigestMismatch {}));
+        }
+        Ok(reconstructed)
+    }
+
+    fn domain_bytes(&self) -> Vec<u8> {
+        if self.domain_tag_len.get() == U8::ZERO {
+            return DOMAIN.to_vec();
+        }
+        let len: usize = self.domain_tag_len.get().saturating_to();
+        self.domain_tag.get()[..len].to_vec()
+    }
+
+    /// Digest under the configured domain tag and hash: the fixed 125-byte
+    /// layout for the default tag, otherwise the same fields after the
+    /// custom tag.
+    fn receipt_digest(
+        &self,
+        chain_id: u64,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+    ) -> Result<FixedBytes<32>, HardwareAnchorError> {
+        if self.domain_tag_len.get() != U8::ZERO {
+            return self.hash_material(
+                &self.tagged_material(chain_id, hw_id, fw_hash, exec_hash, counter),
+            );
+        }
+        if self.digest_algo.get().saturating_to::<u8>() == DIGEST_KECCAK256 {
+            return Ok(reconstruct_digest(
+                chain_id, hw_id, fw_hash, exec_hash, counter,
+            ));
+        }
+        self.hash_material(&receipt_codec::encode_material(
+            chain_id, hw_id, fw_hash, exec_hash, counter,
+        ))
+    }
+
+    /// Hash receipt digest material with the configured digest_algo.
+    fn hash_material(&self, material: &[u8]) -> Result<FixedBytes<32>, HardwareAnchorError> {
+        if self.digest_algo.get().saturating_to::<u8>() == DIGEST_KECCAK256 {
+            return Ok(keccak256(material));
+        }
+        let output = static_call(Call::new(), SHA256, material)
+            .map_err(|_| HardwareAnchorError::HashUnavailable(HashUnavailable {}))?;
+        if output.len() != 32 {
+            return Err(HardwareAnchorError::HashUnavailable(HashUnavailable {}));
+        }
+        Ok(FixedBytes::from_slice(&output))
+    }
+
+    /// Material receipt_digest hashes under a custom domain tag.
+    fn tagged_material(
+        &self,
+        chain_id: u64,
+        hw_id: FixedBytes<32>,
+        fw_hash: FixedBytes<32>,
+        exec_hash: FixedBytes<32>,
+        counter: u64,
+    ) -> Vec<u8> {
+        receipt_codec::encode_tagged_material(
+            &self.domain_bytes(),
+            chain_id,
+            hw_id,
+            fw_hash,
+            exec_hash,
+            counter,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    // Counts allocations on the current thread only, so concurrently
+    // running tests do not disturb the measurement.
+    struct CountingAlloc;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            ALLOCATIONS.with(|n| n.set(n.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: CountingAlloc = CountingAlloc;
+
+    fn allocations_during(f: impl FnOnce()) -> usize {
+        let before = ALLOCATIONS.with(Cell::get);
+        f();
+        ALLOCATIONS.with(Cell::get) - before
+    }
+
+    #[test]
+    fn reconstruct_digest_does_not_allocate() {
+        let hw_id = FixedBytes::<32>::repeat_byte(0x11);
+        let fw_hash = FixedBytes::<32>::repeat_byte(0x22);
+        let exec_hash = FixedBytes::<32>::repeat_byte(0x33);
+
+        let mut digest = FixedBytes::ZERO;
+        let allocations = allocations_during(|| {
+            digest = reconstruct_digest(42161, hw_id, fw_hash, exec_hash, 7);
+        });
+
+        assert_eq!(allocations, 0);
+        assert_ne!(digest, FixedBytes::ZERO);
+    }
+
+    #[cfg(not(feature = "legacy-nochainid"))]
+    #[test]
+    fn reconstruct_digest_matches_concatenated_material() {
+        let hw_id = FixedBytes::<32>::repeat_byte(0x11);
+        let fw_hash = FixedBytes::<32>::repeat_byte(0x22);
+        let exec_hash = FixedBytes::<32>::repeat_byte(0x33);
+
+        let mut material = Vec::new();
+        material.extend_from_slice(DOMAIN);
+        material.extend_from_slice(&42161u64.to_be_bytes());
+        material.extend_from_slice(hw_id.as_slice());
+        material.extend_from_slice(fw_hash.as_slice());
+        material.extend_from_slice(exec_hash.as_slice());
+        material.extend_from_slice(&7u64.to_be_bytes());
+        assert_eq!(material.len(), 125);
+
+        assert_eq!(
+            reconstruct_digest(42161, hw_id, fw_hash, exec_hash, 7),
+            keccak256(material)
+        );
+    }
+
+    #[cfg(not(feature = "legacy-nochainid"))]
+    #[test]
+    fn digest_algo_vectors() {
+        // Expected digests of the same 125-byte material under each
+        // digest_algo; device firmware can check its hashing against these.
+        use sha2::{Digest, Sha256};
+        use stylus_sdk::alloy_primitives::fixed_bytes;
+
+        let material = receipt_codec::encode_material(
+            42161,
+            FixedBytes::repeat_byte(0x11),
+            FixedBytes::repeat_byte(0x22),
+            FixedBytes::repeat_byte(0x33),
+            7,
+        );
+
+        assert_eq!(
+            keccak256(material),
+            fixed_bytes!("91953eb887cdf7c565a199c094c05fec89a750fa3eada5953aa31d555a7699e6")
+        );
+        assert_eq!(
+            FixedBytes::<32>::from_slice(&Sha256::digest(material)),
+            fixed_bytes!("806d0d28387a7a723d3d6d825ea741b718e678af5c26b1dd2cf5a1028c8860b8")
+        );
+    }
+
+    #[cfg(feature = "legacy-nochainid")]
+    #[test]
+    fn legacy_layout_omits_chain_id() {
+        let hw_id = FixedBytes::<32>::repeat_byte(0x11);
+        let fw_hash = FixedBytes::<32>::repeat_byte(0x22);
+        let exec_hash = FixedBytes::<32>::repeat_byte(0x33);
+
+        let mut material = Vec::new();
+        material.extend_from_slice(b"NEXUS_RCT_V1");
+        material.extend_from_slice(hw_id.as_slice());
+        material.extend_from_slice(fw_hash.as_slice());
+        material.extend_from_slice(exec_hash.as_slice());
+        material.extend_from_slice(&7u64.to_be_bytes());
+        assert_eq!(material.len(), 116);
+
+        for chain_id in [0, 42161, 42170] {
+            assert_eq!(
+                reconstruct_digest(chain_id, hw_id, fw_hash, exec_hash, 7),
+                keccak256(&material)
+            );
+        }
+    }
+
+    #[cfg(feature = "legacy-nochainid")]
+    #[test]
+    fn legacy_digest_algo_vectors() {
+        // The digest_algo_vectors inputs under the NEXUS_RCT_V1 layout.
+        use sha2::{Digest, Sha256};
+        use stylus_sdk::alloy_primitives::fixed_bytes;
+
+        let material = receipt_codec::encode_material(
+            42161,
+            FixedBytes::repeat_byte(0x11),
+            FixedBytes::repeat_byte(0x22),
+            FixedBytes::repeat_byte(0x33),
+            7,
+        );
+
+        assert_eq!(
+            keccak256(material),
+            fixed_bytes!("068739016dfceb1acbe485c1af076091b15e5b9cf005224c2004742724300d31")
+        );
+        assert_eq!(
+            FixedBytes::<32>::from_slice(&Sha256::digest(material)),
+            fixed_bytes!("516851dbb4beebcce9ae16daf436255942b6b8df2f42f426098c4872e9b16620")
+        );
+    }
+
+    #[test]
+    fn derive_hw_id_vectors() {
+        use stylus_sdk::alloy_primitives::fixed_bytes;
+
+        let efuse: [u8; 16] = core::array::from_fn(|i| i as u8);
+        let salt = FixedBytes::<32>::repeat_byte(0xaa);
+        let mut material = efuse.to_vec();
+        material.extend_from_slice(salt.as_slice());
+        assert_eq!(derive_hw_id(&efuse, salt), keccak256(&material));
+
+        for (efuse, salt, expected) in [
+            (
+                efuse,
+                salt,
+                fixed_bytes!("5c6761fb3a25a437482a0fa9d857af8f41ac7fc92dccc8f8eac74779ba69f88a"),
+            ),
+            (
+                [0u8; 16],
+                FixedBytes::ZERO,
+                fixed_bytes!("c980e59163ce244bb4bb6211f48c7b46f88a4f40943e84eb99bdc41e129bd293"),
+            ),
+        ] {
+            assert_eq!(derive_hw_id(&efuse, salt), expected);
+        }
+        // The salt separates deployments sharing a device.
+        assert_ne!(
+            derive_hw_id(&efuse, salt),
+            derive_hw_id(&efuse, FixedBytes::repeat_byte(0xab))
+        );
+    }
+
+    fn emitted<E: alloy_sol_types::SolEvent>(event: E) -> (Vec<FixedBytes<32>>, Vec<u8>) {
+        let topics = event.encode_topics().into_iter().map(|t| t.0).collect();
+        (topics, event.encode_data())
+    }
+
+    #[test]
+    fn admin_events_index_subject_and_caller() {
+        let node_id = FixedBytes::<32>::repeat_byte(0x11);
+        let fw_hash = FixedBytes::<32>::repeat_byte(0x22);
+        let by = Address::repeat_byte(0xaa);
+        let by_topic = by.into_word();
+        let seq = 7;
+
+        let cases = [
+            (
+                emitted(NodeAuthorized { seq, node_id, by }),
+                "NodeAuthorized(uint64,bytes32,address)",
+                node_id,
+            ),
+            (
+                emitted(NodeRevoked { seq, node_id, by }),
+                "NodeRevoked(uint64,bytes32,address)",
+                node_id,
+            ),
+            (
+                emitted(FirmwareApproved { seq, fw_hash, by }),
+                "FirmwareApproved(uint64,bytes32,address)",
+                fw_hash,
+            ),
+            (
+                emitted(FirmwareRevoked { seq, fw_hash, by }),
+                "FirmwareRevoked(uint64,bytes32,address)",
+                fw_hash,
+            ),
+        ];
+        for ((topics, data), signature, subject) in cases {
+            assert_eq!(topics, [keccak256(signature), subject, by_topic]);
+            assert_eq!(data, U256::from(seq).to_be_bytes::<32>());
+        }
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn approvals_past_max_firmware_evict_oldest_first() {
+        let mut anchor: StylusHardwareAnchor = test_vm::contract();
+        assert!(anchor.initialize().is_ok());
+        assert!(anchor.set_max_firmware(2).is_ok());
+
+        let [a, b, c, d] = [1u8, 2, 3, 4].map(FixedBytes::<32>::repeat_byte);
+        assert!(anchor.approve_firmware(a).is_ok());
+        assert!(anchor.approve_firmware(b).is_ok());
+        test_vm::take_logs();
+
+        // The third approval evicts the first.
+        assert!(anchor.approve_firmware(c).is_ok());
+        assert!(!anchor.is_firmware_approved(a));
+        assert!(anchor.is_firmware_approved(b) && anchor.is_firmware_approved(c));
+        assert_eq!(anchor.approved_firmware_count.get().to::<u64>(), 2);
+        let evicted = emitted(FirmwareEvicted { seq: 5, fw_hash: a });
+        assert!(test_vm::take_logs().contains(&evicted));
+
+        // A revoked hash frees its place without being evicted again, and
+        // re-approving it queues it behind c.
+        assert!(anchor.revoke_firmware(b).is_ok());
+        assert!(anchor.approve_firmware(b).is_ok());
+        assert!(anchor.is_firmware_approved(c));
+        assert!(anchor.approve_firmware(d).is_ok());
+        assert!(!anchor.is_firmware_approved(c));
+        assert!(anchor.is_firmware_approved(b) && anchor.is_firmware_approved(d));
+        assert_eq!(anchor.approved_firmware_count.get().to::<u64>(), 2);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn node_authorization_lapses_at_expiry() {
+        use test_vm::{CHAIN_ID, TIMESTAMP};
+
+        let mut anchor: StylusHardwareAnchor = test_vm::contract();
+        assert!(anchor.initialize().is_ok());
+        let [hw_id, fw_hash, exec_hash] = [1u8, 2, 3].map(FixedBytes::<32>::repeat_byte);
+        assert!(anchor.approve_firmware(fw_hash).is_ok());
+        let digest = reconstruct_digest(CHAIN_ID, hw_id, fw_hash, exec_hash, 1);
+        let status = |anchor: &StylusHardwareAnchor| {
+            anchor.check_receipt(hw_id, fw_hash, exec_hash, 1, digest)
+        };
+
+        for (expiry, expected) in [
+            (TIMESTAMP - 1, STATUS_NODE_EXPIRED),
+            (TIMESTAMP, STATUS_NODE_EXPIRED),
+            (TIMESTAMP + 1, STATUS_OK),
+            (u64::MAX, STATUS_OK),
+        ] {
+            assert!(anchor.authorize_node_until(hw_id, expiry).is_ok());
+            assert_eq!(anchor.node_expiry.get(hw_id).to::<u64>(), expiry);
+            assert_eq!(status(&anchor), expected, "expiry {expiry}");
+            assert_eq!(anchor.is_node_authorized(hw_id), expected == STATUS_OK);
+        }
+
+        // 0 would read as "not authorized", so it is refused outright and
+        // leaves the current expiry in place.
+        assert!(matches!(
+            anchor.authorize_node_until(hw_id, 0),
+            Err(HardwareAnchorError::InvalidExpiry(_))
+        ));
+        assert_eq!(anchor.node_expiry.get(hw_id).to::<u64>(), u64::MAX);
+
+        // authorize_node never lapses; revocation clears the expiry.
+        assert!(anchor.authorize_node_until(hw_id, TIMESTAMP).is_ok());
+        assert!(anchor.authorize_node(hw_id).is_ok());
+        assert_eq!(anchor.node_expiry.get(hw_id).to::<u64>(), u64::MAX);
+        assert_eq!(status(&anchor), STATUS_OK);
+        assert!(anchor.authorize_node_until(hw_id, TIMESTAMP).is_ok());
+        assert!(anchor.revoke_node(hw_id).is_ok());
+        assert_eq!(anchor.node_expiry.get(hw_id).to::<u64>(), 0);
+        assert_eq!(status(&anchor), STATUS_UNAUTHORIZED_HARDWARE);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn expired_nodes_read_as_unauthorized() {
+        use test_vm::TIMESTAMP;
+
+        // The SDK caches block_timestamp, so instead of warping the clock
+        // past the expiry this moves the expiry back onto the clock.
+        let (mut anchor, hw_id, fw_hash, _) = test_vm::fixture();
+        let node = FixedBytes::<32>::repeat_byte(9);
+        assert!(anchor.authorize_node_until(node, TIMESTAMP + 1).is_ok());
+        assert!(anchor.is_node_authorized(node));
+        assert!(anchor.is_node_authorized(hw_id));
+
+        assert!(anchor.authorize_node_until(node, TIMESTAMP).is_ok());
+        assert!(!anchor.is_node_authorized(node));
+        assert!(anchor.is_node_authorized(hw_id));
+        assert!(matches!(
+            test_vm::verify(&mut anchor, node, fw_hash, 1),
+            Err(HardwareAnchorError::NodeAuthorizationExpired(_))
+        ));
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn events_carry_consecutive_sequence_numbers() {
+        let (mut anchor, node_id, fw_hash, _) = test_vm::fixture();
+        assert!(anchor.revoke_firmware(fw_hash).is_ok());
+        assert!(anchor.purge_node(node_id).is_ok());
+
+        // seq is the first, unindexed field: the first data word.
+        let seqs: Vec<U256> = test_vm::take_logs()
+            .iter()
+            .map(|(_, data)| U256::from_be_slice(&data[..32]))
+            .collect();
+        let expected: Vec<U256> = (1..=6u64).map(U256::from).collect();
+        assert_eq!(seqs, expected);
+        assert_eq!(anchor.event_seq.get().to::<u64>(), 6);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn mutators_without_a_dedicated_event_still_consume_a_seq() {
+        type Mutator = fn(&mut StylusHardwareAnchor) -> Result<(), HardwareAnchorError>;
+
+        let mut anchor: StylusHardwareAnchor = test_vm::contract();
+        assert!(anchor.initialize().is_ok());
+        test_vm::take_logs();
+
+        let mutators: [(Mutator, [u8; 4]); 14] = [
+            (
+                |a| a.set_max_authorized_nodes(8),
+                function_selector!("setMaxAuthorizedNodes", u64),
+            ),
+            (
+                |a| a.set_counter_floor(FixedBytes::repeat_byte(1), 5),
+                function_selector!("setCounterFloor", FixedBytes<32>, u64),
+            ),
+            (
+                |a| a.set_allow_reanchor(FixedBytes::repeat_byte(1), true),
+                function_selector!("setAllowReanchor", FixedBytes<32>, bool),
+            ),
+            (
+                |a| a.set_min_firmware_version(FixedBytes::repeat_byte(1), 2),
+                function_selector!("setMinFirmwareVersion", FixedBytes<32>, u32),
+            ),
+            (
+                |a| a.set_max_firmware(4),
+                function_selector!("setMaxFirmware", u64),
+            ),
+            (
+                |a| a.set_exec_allowlist_enabled(true),
+                function_selector!("setExecAllowlistEnabled", bool),
+            ),
+            (
+                |a| a.approve_exec(FixedBytes::repeat_byte(1)),
+                function_selector!("approveExec", FixedBytes<32>),
+            ),
+            (
+                |a| a.set_digest_algo(DIGEST_SHA256),
+                function_selector!("setDigestAlgo", u8),
+            ),
+            (
+                |a| a.set_replay_window(64),
+                function_selector!("setReplayWindow", u64),
+            ),
+            (
+                |a| a.set_min_receipt_interval(60),
+                function_selector!("setMinReceiptInterval", u64),
+            ),
+            (
+                |a| a.set_counter_bits(MAX_COUNTER_BITS),
+                function_selector!("setCounterBits", u8),
+            ),
+            (
+                |a| a.freeze_firmware_approvals(),
+                function_selector!("freezeFirmwareApprovals"),
+            ),
+            (
+                |a| a.set_verifier(Address::repeat_byte(0xcc), true),
+                function_selector!("setVerifier", Address, bool),
+            ),
+            (
+                |a| a.transfer_ownership(Address::repeat_byte(0xbb)),
+                function_selector!("transferOwnership", Address),
+            ),
+        ];
+        for (seq, (mutate, selector)) in (2..).zip(mutators) {
+            assert!(mutate(&mut anchor).is_ok());
+            let event = ConfigChanged {
+                seq,
+                selector: selector.into(),
+                by: test_vm::SENDER,
+            };
+            assert_eq!(test_vm::take_logs(), vec![emitted(event)]);
+        }
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn simulation_matches_verification_without_writing() {
+        let (mut anchor, hw_id, fw_hash, exec_hash) = test_vm::fixture();
+        let digest = reconstruct_digest(test_vm::CHAIN_ID, hw_id, fw_hash, exec_hash, 1);
+
+        for _ in 0..2 {
+            let status = anchor.check_receipt(hw_id, fw_hash, exec_hash, 1, digest);
+            assert_eq!(status, STATUS_OK);
+        }
+        assert_eq!(anchor.get_counter(hw_id), 0);
+        assert_eq!(
+            anchor.check_receipt(hw_id, fw_hash, exec_hash, 1, FixedBytes::ZERO),
+            STATUS_DIGEST_MISMATCH
+        );
+
+        assert!(anchor
+            .verify_receipt(hw_id, fw_hash, exec_hash, 1, digest)
+            .is_ok());
+        assert_eq!(
+            anchor.check_receipt(hw_id, fw_hash, exec_hash, 1, digest),
+            STATUS_REPLAY_DETECTED
+        );
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn counter_floor_refuses_provisioning_counters() {
+        let (mut anchor, hw_id, fw_hash, _) = test_vm::fixture();
+        assert!(anchor.set_counter_floor(hw_id, 100).is_ok());
+        assert_eq!(anchor.counter_floor.get(hw_id).to::<u64>(), 100);
+
+        for counter in [1, 99, 100] {
+            assert_eq!(
+                anchor.is_anchorable(hw_id, fw_hash, counter),
+                STATUS_REPLAY_DETECTED
+            );
+            assert!(matches!(
+                test_vm::verify(&mut anchor, hw_id, fw_hash, counter),
+                Err(HardwareAnchorError::ReplayDetected(_))
+            ));
+        }
+        assert_eq!(anchor.is_anchorable(hw_id, fw_hash, 101), STATUS_OK);
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 101).is_ok());
+
+        // Once the stored counter passes the floor it governs as before.
+        assert!(matches!(
+            test_vm::verify(&mut anchor, hw_id, fw_hash, 101),
+            Err(HardwareAnchorError::ReplayDetected(_))
+        ));
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 102).is_ok());
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn reanchor_is_one_shot_and_only_for_the_node_counter() {
+        let (mut anchor, hw_id, fw_hash, _) = test_vm::fixture();
+        let replay = |result| matches!(result, Err(HardwareAnchorError::ReplayDetected(_)));
+
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 5).is_ok());
+        assert!(replay(test_vm::verify(&mut anchor, hw_id, fw_hash, 5)));
+        assert!(anchor.set_allow_reanchor(hw_id, true).is_ok());
+        assert!(anchor.allow_reanchor.get(hw_id));
+        assert!(replay(test_vm::verify(&mut anchor, hw_id, fw_hash, 4)));
+        test_vm::take_logs();
+
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 5).is_ok());
+        assert!(!anchor.allow_reanchor.get(hw_id));
+        assert_eq!(anchor.get_counter(hw_id), 5);
+        assert_eq!(anchor.accepted_count.get(hw_id).to::<u64>(), 2);
+        let logs = test_vm::take_logs();
+        let consumed = emitted(ReanchorConsumed {
+            seq: 0,
+            node_id: hw_id,
+            counter: 5,
+        });
+        assert!(logs.iter().any(|(topics, _)| topics[..] == consumed.0[..]));
+        assert!(replay(test_vm::verify(&mut anchor, hw_id, fw_hash, 5)));
+
+        // Advancing past the stored counter spends the flag, so the new
+        // counter cannot be replayed on it.
+        assert!(anchor.set_allow_reanchor(hw_id, true).is_ok());
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 6).is_ok());
+        assert!(!anchor.allow_reanchor.get(hw_id));
+        assert!(replay(test_vm::verify(&mut anchor, hw_id, fw_hash, 6)));
+
+        // The node's flag does not re-anchor a tenant namespace's counter.
+        let tenant = FixedBytes::<32>::repeat_byte(0x77);
+        let digest = FixedBytes::<32>::repeat_byte(0x99);
+        assert!(anchor.set_verifier(test_vm::SENDER, true).is_ok());
+        assert!(anchor
+            .anchor_receipt(hw_id, tenant, fw_hash, test_vm::EXEC_HASH, 3, digest)
+            .is_ok());
+        assert!(anchor.set_allow_reanchor(hw_id, true).is_ok());
+        assert!(replay(anchor.anchor_receipt(
+            hw_id,
+            tenant,
+            fw_hash,
+            test_vm::EXEC_HASH,
+            3,
+            digest
+        )));
+        assert!(anchor.allow_reanchor.get(hw_id));
+        // Nor does a tenant receipt advancing past it spend the flag.
+        assert!(anchor
+            .anchor_receipt(hw_id, tenant, fw_hash, test_vm::EXEC_HASH, 4, digest)
+            .is_ok());
+        assert!(anchor.allow_reanchor.get(hw_id));
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 6).is_ok());
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn counter_views_follow_floor_window_and_width() {
+        let (mut anchor, hw_id, fw_hash, _) = test_vm::fixture();
+        assert!(anchor.set_counter_floor(hw_id, 100).is_ok());
+        assert_eq!(anchor.next_valid_counter(hw_id), 101);
+        for counter in [1, 99, 100] {
+            assert!(!anchor.would_accept_counter(hw_id, counter));
+        }
+        assert!(anchor.would_accept_counter(hw_id, 101));
+
+        // Stored counter 110 with 107 and 109 still open in a window of 4.
+        assert!(anchor.set_replay_window(4).is_ok());
+        for counter in [106, 110, 108] {
+            assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, counter).is_ok());
+        }
+        assert_eq!(anchor.next_valid_counter(hw_id), 111);
+        for (counter, accepted) in [
+            (111, true),
+            (110, false),
+            (109, true),
+            (108, false),
+            (107, true),
+            (106, false),
+            (105, false),
+        ] {
+            assert_eq!(
+                anchor.would_accept_counter(hw_id, counter),
+                accepted,
+                "counter {counter}"
+            );
+        }
+
+        // The stored counter passes once more while re-anchoring is allowed.
+        assert!(anchor.set_allow_reanchor(hw_id, true).is_ok());
+        assert!(anchor.would_accept_counter(hw_id, 110));
+
+        // A 16-bit width refuses its top COUNTER_HEADROOM values.
+        assert!(anchor.set_counter_bits(16).is_ok());
+        assert!(anchor.would_accept_counter(hw_id, 0xffff - 256));
+        assert!(!anchor.would_accept_counter(hw_id, 0xffff - 255));
+        assert!(!anchor.would_accept_counter(hw_id, 1 << 16));
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn preview_digest_matches_the_digest_vectors() {
+        // digest_algo_vectors (legacy_digest_algo_vectors under
+        // legacy-nochainid), on the test chain.
+        use stylus_sdk::alloy_primitives::fixed_bytes;
+
+        let (keccak, sha256) = if cfg!(feature = "legacy-nochainid") {
+            (
+                fixed_bytes!("068739016dfceb1acbe485c1af076091b15e5b9cf005224c2004742724300d31"),
+                fixed_bytes!("516851dbb4beebcce9ae16daf436255942b6b8df2f42f426098c4872e9b16620"),
+            )
+        } else {
+            (
+                fixed_bytes!("91953eb887cdf7c565a199c094c05fec89a750fa3eada5953aa31d555a7699e6"),
+                fixed_bytes!("806d0d28387a7a723d3d6d825ea741b718e678af5c26b1dd2cf5a1028c8860b8"),
+            )
+        };
+        let (mut anchor, ..) = test_vm::fixture();
+        let preview = |anchor: &StylusHardwareAnchor| {
+            anchor.preview_digest(
+                FixedBytes::repeat_byte(0x11),
+                FixedBytes::repeat_byte(0x22),
+                FixedBytes::repeat_byte(0x33),
+                7,
+            )
+        };
+
+        assert_eq!(preview(&anchor), keccak);
+        assert!(anchor.set_digest_algo(1).is_ok());
+        assert_eq!(preview(&anchor), sha256);
+        test_vm::remove_sha256();
+        assert_eq!(preview(&anchor), FixedBytes::ZERO);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn anchorable_tracks_policy_without_a_digest() {
+        use test_vm::TIMESTAMP;
+
+        let mut anchor: StylusHardwareAnchor = test_vm::contract();
+        assert!(anchor.initialize().is_ok());
+        let [hw_id, fw_hash, exec_hash] = [1u8, 2, 3].map(FixedBytes::<32>::repeat_byte);
+        assert_eq!(
+            anchor.is_anchorable(hw_id, fw_hash, 1),
+            STATUS_UNAUTHORIZED_HARDWARE
+        );
+        assert!(anchor.authorize_node_until(hw_id, TIMESTAMP).is_ok());
+        assert_eq!(anchor.is_anchorable(hw_id, fw_hash, 1), STATUS_NODE_EXPIRED);
+        assert!(anchor.authorize_node(hw_id).is_ok());
+        assert_eq!(
+            anchor.is_anchorable(hw_id, fw_hash, 1),
+            STATUS_FIRMWARE_NOT_APPROVED
+        );
+        assert!(anchor.approve_firmware_until(fw_hash, TIMESTAMP).is_ok());
+        assert_eq!(
+            anchor.is_anchorable(hw_id, fw_hash, 1),
+            STATUS_FIRMWARE_EXPIRED
+        );
+        assert!(anchor.approve_firmware(fw_hash).is_ok());
+
+        // The exec allowlist is not consulted: the device has not said
+        // which computation it ran.
+        assert!(anchor.set_exec_allowlist_enabled(true).is_ok());
+        assert_eq!(anchor.is_anchorable(hw_id, fw_hash, 1), STATUS_OK);
+        assert!(anchor.approve_exec(exec_hash).is_ok());
+
+        // Counters skipped while the window is open stay anchorable.
+        assert!(anchor.set_replay_window(4).is_ok());
+        let digest = reconstruct_digest(test_vm::CHAIN_ID, hw_id, fw_hash, exec_hash, 5);
+        assert!(anchor
+            .verify_receipt(hw_id, fw_hash, exec_hash, 5, digest)
+            .is_ok());
+        assert_eq!(
+            anchor.is_anchorable(hw_id, fw_hash, 5),
+            STATUS_REPLAY_DETECTED
+        );
+        assert_eq!(anchor.is_anchorable(hw_id, fw_hash, 6), STATUS_OK);
+        assert_eq!(anchor.is_anchorable(hw_id, fw_hash, 3), STATUS_OK);
+        assert!(anchor.set_counter_floor(hw_id, 10).is_ok());
+        assert_eq!(
+            anchor.is_anchorable(hw_id, fw_hash, 6),
+            STATUS_REPLAY_DETECTED
+        );
+        assert_eq!(anchor.is_anchorable(hw_id, fw_hash, 11), STATUS_OK);
+        assert!(anchor.set_counter_bits(16).is_ok());
+        assert_eq!(anchor.is_anchorable(hw_id, fw_hash, 1 << 16), STATUS_OTHER);
+        assert!(anchor.set_min_receipt_interval(60).is_ok());
+        assert_eq!(
+            anchor.is_anchorable(hw_id, fw_hash, 11),
+            STATUS_RATE_LIMITED
+        );
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn blocklist_overrides_authorization() {
+        let mut anchor: StylusHardwareAnchor = test_vm::contract();
+        assert!(anchor.initialize().is_ok());
+        let [hw_id, fw_hash, exec_hash] = [1u8, 2, 3].map(FixedBytes::<32>::repeat_byte);
+        let digest =
+            |counter| reconstruct_digest(test_vm::CHAIN_ID, hw_id, fw_hash, exec_hash, counter);
+
+        // Blocked ahead of every other check, even for an unknown node.
+        assert!(anchor.block_node(hw_id).is_ok());
+        assert!(anchor.blocked_nodes.get(hw_id));
+        assert!(matches!(
+            anchor.verify_receipt(hw_id, fw_hash, exec_hash, 1, FixedBytes::ZERO),
+            Err(HardwareAnchorError::NodeBlocked(_))
+        ));
+
+        assert!(anchor.authorize_node(hw_id).is_ok());
+        assert!(anchor.approve_firmware(fw_hash).is_ok());
+        assert!(matches!(
+            anchor.verify_receipt(hw_id, fw_hash, exec_hash, 1, digest(1)),
+            Err(HardwareAnchorError::NodeBlocked(_))
+        ));
+        assert_eq!(
+            anchor.check_receipt(hw_id, fw_hash, exec_hash, 1, digest(1)),
+            STATUS_NODE_BLOCKED
+        );
+        assert_eq!(anchor.is_anchorable(hw_id, fw_hash, 1), STATUS_NODE_BLOCKED);
+
+        // Re-authorizing, or purging and re-authorizing, keeps the block.
+        assert!(anchor.revoke_node(hw_id).is_ok());
+        assert!(anchor.authorize_node(hw_id).is_ok());
+        assert!(anchor.purge_node(hw_id).is_ok());
+        assert!(anchor.authorize_node(hw_id).is_ok());
+        assert!(matches!(
+            anchor.verify_receipt(hw_id, fw_hash, exec_hash, 1, digest(1)),
+            Err(HardwareAnchorError::NodeBlocked(_))
+        ));
+        test_vm::take_logs();
+
+        assert!(anchor.unblock_node(hw_id).is_ok());
+        assert!(!anchor.blocked_nodes.get(hw_id));
+        assert_eq!(
+            test_vm::take_logs(),
+            vec![emitted(BlockedNodeRemoved {
+                seq: anchor.event_seq.get().to::<u64>(),
+                node_id: hw_id,
+                by: test_vm::SENDER,
+            })]
+        );
+        assert!(anchor
+            .verify_receipt(hw_id, fw_hash, exec_hash, 1, digest(1))
+            .is_ok());
+
+        test_vm::take_logs();
+        assert!(anchor.block_node(hw_id).is_ok());
+        assert_eq!(
+            test_vm::take_logs(),
+            vec![emitted(BlockedNodeAdded {
+                seq: anchor.event_seq.get().to::<u64>(),
+                node_id: hw_id,
+                by: test_vm::SENDER,
+            })]
+        );
+        assert!(anchor.is_node_authorized(hw_id));
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn firmware_node_index_appends_each_node_once() {
+        let mut anchor: StylusHardwareAnchor = test_vm::contract();
+        assert!(anchor.initialize().is_ok());
+        let [node_a, node_b, fw_old, fw_new] = [1u8, 2, 4, 5].map(FixedBytes::<32>::repeat_byte);
+        for node in [node_a, node_b] {
+            assert!(anchor.authorize_node(node).is_ok());
+        }
+        for fw in [fw_old, fw_new] {
+            assert!(anchor.approve_firmware(fw).is_ok());
+        }
+        assert!(test_vm::verify(&mut anchor, node_b, fw_old, 1).is_ok());
+        assert!(test_vm::verify(&mut anchor, node_a, fw_old, 1).is_ok());
+        assert!(test_vm::verify(&mut anchor, node_b, fw_old, 2).is_ok());
+        assert!(test_vm::verify(&mut anchor, node_a, fw_new, 2).is_ok());
+        assert!(test_vm::verify(&mut anchor, node_a, fw_old, 3).is_ok());
+        // A rejected receipt is not indexed.
+        assert!(test_vm::verify(&mut anchor, node_b, fw_new, 1).is_err());
+
+        let nodes = |anchor: &StylusHardwareAnchor, fw_hash| {
+            let indexed = anchor.firmware_nodes.get(fw_hash);
+            (0..indexed.len())
+                .filter_map(|i| indexed.get(i))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(nodes(&anchor, fw_old), vec![node_b, node_a]);
+        assert_eq!(nodes(&anchor, fw_new), vec![node_a]);
+
+        // History outlives revocation and purging.
+        assert!(anchor.purge_firmware(fw_old).is_ok());
+        assert_eq!(nodes(&anchor, fw_old), vec![node_b, node_a]);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn custom_domain_tag_prefixes_the_digest_material() {
+        let [hw_id, fw_hash, exec_hash] = [1u8, 2, 3].map(FixedBytes::<32>::repeat_byte);
+        let mut tagged: StylusHardwareAnchor = test_vm::contract();
+        // The tag is kept in a single word.
+        let tag = Bytes(vec![b'T'; 33]);
+        assert!(matches!(
+            tagged.initialize_with(test_vm::SENDER, tag),
+            Err(HardwareAnchorError::DomainTagTooLong(_))
+        ));
+
+        let tag = b"TENANT_DOMAIN_V2";
+        assert!(tagged
+            .initialize_with(test_vm::SENDER, Bytes(tag.to_vec()))
+            .is_ok());
+        assert!(tagged.authorize_node(hw_id).is_ok());
+        assert!(tagged.approve_firmware(fw_hash).is_ok());
+        assert_eq!(tagged.domain_bytes(), tag.to_vec());
+
+        let material = receipt_codec::encode_tagged_material(
+            tag,
+            test_vm::CHAIN_ID,
+            hw_id,
+            fw_hash,
+            exec_hash,
+            1,
+        );
+        assert_eq!(material.len(), 16 + 112);
+        let digest = keccak256(material);
+        assert_ne!(
+            digest,
+            reconstruct_digest(test_vm::CHAIN_ID, hw_id, fw_hash, exec_hash, 1)
+        );
+        assert_eq!(
+            tagged.check_receipt(hw_id, fw_hash, exec_hash, 1, digest),
+            STATUS_OK
+        );
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn raised_minimum_version_rejects_downgrades_per_node() {
+        let mut anchor: StylusHardwareAnchor = test_vm::contract();
+        assert!(anchor.initialize().is_ok());
+        let [hw_id, other_hw_id, v1, v2] = [1u8, 2, 4, 5].map(FixedBytes::<32>::repeat_byte);
+        assert!(anchor.authorize_node(hw_id).is_ok());
+        assert!(anchor.authorize_node(other_hw_id).is_ok());
+        assert!(anchor.approve_firmware_versioned(v1, 1).is_ok());
+        assert!(anchor.approve_firmware_versioned(v2, 2).is_ok());
+
+        // Without a minimum either version verifies, in either order.
+        assert!(test_vm::verify(&mut anchor, hw_id, v2, 1).is_ok());
+        assert!(test_vm::verify(&mut anchor, hw_id, v1, 2).is_ok());
+
+        // Once the node advances to version 2, version 1 is a downgrade
+        // for it but stays approved for nodes that have not advanced.
+        assert!(anchor.set_min_firmware_version(hw_id, 2).is_ok());
+        assert!(matches!(
+            test_vm::verify(&mut anchor, hw_id, v1, 3),
+            Err(HardwareAnchorError::FirmwareDowngrade(_))
+        ));
+        assert!(test_vm::verify(&mut anchor, hw_id, v2, 3).is_ok());
+        assert!(anchor.is_firmware_approved(v1));
+        assert!(test_vm::verify(&mut anchor, other_hw_id, v1, 1).is_ok());
+
+        // The minimum only moves forward.
+        assert!(matches!(
+            anchor.set_min_firmware_version(hw_id, 1),
+            Err(HardwareAnchorError::FirmwareDowngrade(_))
+        ));
+        assert_eq!(anchor.min_firmware_version.get(hw_id).to::<u32>(), 2);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn replay_window_accepts_late_counters_once() {
+        let (mut anchor, hw_id, fw_hash, _) = test_vm::fixture();
+        let replayed = |result| matches!(result, Err(HardwareAnchorError::ReplayDetected(_)));
+
+        // Window 0 is strict monotonicity.
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 2).is_ok());
+        assert!(replayed(test_vm::verify(&mut anchor, hw_id, fw_hash, 1)));
+
+        assert!(anchor.set_replay_window(4).is_ok());
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 10).is_ok());
+        // Reordered counters inside the window pass once each.
+        for counter in [8, 6, 9] {
+            assert!(
+                test_vm::verify(&mut anchor, hw_id, fw_hash, counter).is_ok(),
+                "counter {counter}"
+            );
+        }
+        for counter in [8, 6, 9, 10] {
+            assert!(
+                replayed(test_vm::verify(&mut anchor, hw_id, fw_hash, counter)),
+                "duplicate {counter}"
+            );
+        }
+        // 5 is one past the window behind 10.
+        assert!(replayed(test_vm::verify(&mut anchor, hw_id, fw_hash, 5)));
+        assert_eq!(anchor.get_counter(hw_id), 10);
+
+        // Advancing slides the window: 7 is still open, 8 stays seen and
+        // 6 has dropped out.
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 11).is_ok());
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 7).is_ok());
+        for counter in [8, 6] {
+            assert!(
+                replayed(test_vm::verify(&mut anchor, hw_id, fw_hash, counter)),
+                "counter {counter}"
+            );
+        }
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn node_cap_counts_each_node_once() {
+        let mut anchor: StylusHardwareAnchor = test_vm::contract();
+        assert!(anchor.initialize().is_ok());
+        let [a, b, c] = [1u8, 2, 3].map(FixedBytes::<32>::repeat_byte);
+        assert!(anchor.set_max_authorized_nodes(2).is_ok());
+        let capped = |result| matches!(result, Err(HardwareAnchorError::NodeCapExceeded(_)));
+
+        assert!(anchor.authorize_node(a).is_ok());
+        assert!(anchor.authorize_node(b).is_ok());
+        assert_eq!(anchor.active_node_count.get().to::<u64>(), 2);
+        assert!(capped(anchor.authorize_node(c)));
+        assert!(!anchor.is_node_authorized(c));
+
+        // Re-authorizing a node at the cap, by any path, is not a new node.
+        assert!(anchor.authorize_node(a).is_ok());
+        assert!(anchor.authorize_node_until(b, u64::MAX).is_ok());
+        assert_eq!(anchor.active_node_count.get().to::<u64>(), 2);
+
+        // Revocation frees exactly one place, and revoking twice frees no more.
+        assert!(anchor.revoke_node(a).is_ok());
+        assert!(anchor.revoke_node(a).is_ok());
+        assert_eq!(anchor.active_node_count.get().to::<u64>(), 1);
+        assert!(anchor.authorize_node(c).is_ok());
+        assert!(capped(anchor.authorize_node(a)));
+        assert_eq!(anchor.active_node_count.get().to::<u64>(), 2);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn rate_limit_spaces_receipts_by_the_interval() {
+        use test_vm::TIMESTAMP;
+
+        let (mut anchor, hw_id, fw_hash, _) = test_vm::fixture();
+        assert!(anchor.set_min_receipt_interval(60).is_ok());
+        let limited = |result| matches!(result, Err(HardwareAnchorError::RateLimited(_)));
+
+        // The first receipt is free; the next two land inside the interval.
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 1).is_ok());
+        assert_eq!(anchor.last_verified_at.get(hw_id).to::<u64>(), TIMESTAMP);
+        assert!(limited(test_vm::verify(&mut anchor, hw_id, fw_hash, 2)));
+        assert!(limited(test_vm::verify(&mut anchor, hw_id, fw_hash, 3)));
+
+        // The clock is fixed, so age the last receipt instead.
+        anchor
+            .last_verified_at
+            .insert(hw_id, U64::from(TIMESTAMP - 59));
+        assert!(limited(test_vm::verify(&mut anchor, hw_id, fw_hash, 2)));
+        anchor
+            .last_verified_at
+            .insert(hw_id, U64::from(TIMESTAMP - 60));
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 2).is_ok());
+        assert!(limited(test_vm::verify(&mut anchor, hw_id, fw_hash, 3)));
+
+        assert!(anchor.set_min_receipt_interval(0).is_ok());
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 3).is_ok());
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn verifiers_anchor_receipts_under_their_namespace() {
+        let (mut anchor, hw_id, fw_hash, exec_hash) = test_vm::fixture();
+        let tenant = keccak256(b"TENANT_A");
+        let digest = FixedBytes::<32>::repeat_byte(0x44);
+        let anchor_receipt = |anchor: &mut StylusHardwareAnchor, namespace, counter| {
+            anchor.anchor_receipt(hw_id, namespace, fw_hash, exec_hash, counter, digest)
+        };
+
+        assert!(matches!(
+            anchor_receipt(&mut anchor, FixedBytes::ZERO, 5),
+            Err(HardwareAnchorError::UnauthorizedCaller(_))
+        ));
+        assert!(anchor.set_verifier(test_vm::SENDER, true).is_ok());
+        assert!(anchor.verifiers.get(test_vm::SENDER));
+
+        // The zero namespace is the node's own counter.
+        let root = anchor.receipt_root.get();
+        assert!(anchor_receipt(&mut anchor, FixedBytes::ZERO, 5).is_ok());
+        assert_eq!(anchor.get_counter(hw_id), 5);
+        assert_eq!(
+            anchor.get_receipt_root(),
+            keccak256([root, digest].concat())
+        );
+        assert!(matches!(
+            anchor_receipt(&mut anchor, FixedBytes::ZERO, 5),
+            Err(HardwareAnchorError::ReplayDetected(_))
+        ));
+
+        // A tenant namespace keeps its own counter.
+        assert!(anchor_receipt(&mut anchor, tenant, 1).is_ok());
+        assert!(matches!(
+            anchor_receipt(&mut anchor, tenant, 1),
+            Err(HardwareAnchorError::ReplayDetected(_))
+        ));
+        assert_eq!(
+            anchor.counters.get(domain_counter_id(tenant, hw_id)),
+            U64::from(1)
+        );
+        assert_eq!(anchor.get_counter(hw_id), 5);
+        assert_eq!(anchor.accepted_count.get(hw_id).to::<u64>(), 2);
+
+        // Node-level policy still applies.
+        assert!(anchor.block_node(hw_id).is_ok());
+        assert!(matches!(
+            anchor_receipt(&mut anchor, tenant, 2),
+            Err(HardwareAnchorError::NodeBlocked(_))
+        ));
+        assert!(anchor.set_verifier(test_vm::SENDER, false).is_ok());
+        assert!(matches!(
+            anchor_receipt(&mut anchor, tenant, 2),
+            Err(HardwareAnchorError::UnauthorizedCaller(_))
+        ));
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn signature_required_nodes_anchor_only_through_verifiers() {
+        let (mut anchor, hw_id, fw_hash, exec_hash) = test_vm::fixture();
+        let digest =
+            |counter| reconstruct_digest(test_vm::CHAIN_ID, hw_id, fw_hash, exec_hash, counter);
+
+        assert!(anchor.set_signature_required(hw_id, true).is_ok());
+        assert!(anchor.signature_required.get(hw_id));
+        assert!(matches!(
+            anchor.verify_receipt(hw_id, fw_hash, exec_hash, 1, digest(1)),
+            Err(HardwareAnchorError::SignatureRequired(_))
+        ));
+        assert_eq!(
+            anchor.check_receipt(hw_id, fw_hash, exec_hash, 1, digest(1)),
+            STATUS_SIGNATURE_REQUIRED
+        );
+        assert_eq!(
+            anchor.submit_receipt(hw_id, fw_hash, exec_hash, 1, digest(1)),
+            STATUS_SIGNATURE_REQUIRED
+        );
+        assert!(!anchor.report_receipt(hw_id, fw_hash, exec_hash, 1, digest(1)));
+        assert_eq!(anchor.get_counter(hw_id), 0);
+
+        // A verifier that checked the signature still anchors the node.
+        assert!(anchor.set_verifier(test_vm::SENDER, true).is_ok());
+        assert!(anchor
+            .anchor_receipt(hw_id, FixedBytes::ZERO, fw_hash, exec_hash, 1, digest(1))
+            .is_ok());
+        assert_eq!(anchor.get_counter(hw_id), 1);
+
+        assert!(anchor.set_signature_required(hw_id, false).is_ok());
+        assert!(anchor
+            .verify_receipt(hw_id, fw_hash, exec_hash, 2, digest(2))
+            .is_ok());
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn anyone_may_purge_expired_firmware_but_not_live_firmware() {
+        let (mut anchor, _, fw_hash, _) = test_vm::fixture();
+        let [expired, live] = [0xe1, 0x1e].map(FixedBytes::<32>::repeat_byte);
+        assert!(anchor
+            .approve_firmware_until(expired, test_vm::TIMESTAMP)
+            .is_ok());
+        assert!(anchor
+            .approve_firmware_until(live, test_vm::TIMESTAMP + 60)
+            .is_ok());
+        // From here on the sender is a stranger to the anchor.
+        assert!(anchor
+            .transfer_ownership(Address::repeat_byte(0xbb))
+            .is_ok());
+        assert!(!anchor.is_firmware_approved(expired));
+        assert!(anchor.approved_firmware.get(expired));
+
+        assert!(anchor.purge_firmware(expired).is_ok());
+        assert!(!anchor.is_firmware_approved(expired));
+        assert_eq!(anchor.firmware_expiry.get(expired), U64::ZERO);
+
+        for fw_hash in [live, fw_hash] {
+            assert!(matches!(
+                anchor.purge_firmware(fw_hash),
+                Err(HardwareAnchorError::UnauthorizedCaller(_))
+            ));
+            assert!(anchor.is_firmware_approved(fw_hash));
+        }
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn supports_erc165_and_the_anchor_interfaces_only() {
+        let anchor: StylusHardwareAnchor = test_vm::contract();
+        for (id, supported) in [
+            ([0x01, 0xff, 0xc9, 0xa7], true),
+            ([0xf1, 0x18, 0xff, 0x11], true),
+            ([0x7d, 0x5e, 0x2e, 0x34], true),
+            ([0xff, 0xff, 0xff, 0xff], false),
+        ] {
+            assert_eq!(anchor.supports_interface(FixedBytes(id)), supported);
+        }
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn submitted_receipts_return_their_status_without_reverting() {
+        let (mut anchor, hw_id, fw_hash, exec_hash) = test_vm::fixture();
+        let digest =
+            |counter| reconstruct_digest(test_vm::CHAIN_ID, hw_id, fw_hash, exec_hash, counter);
+
+        assert_eq!(
+            anchor.submit_receipt(hw_id, fw_hash, exec_hash, 1, digest(1)),
+            STATUS_OK
+        );
+        for (hw_id, fw_hash, counter, claimed_digest, status) in [
+            (hw_id, fw_hash, 1, digest(1), STATUS_REPLAY_DETECTED),
+            (hw_id, fw_hash, 2, digest(3), STATUS_DIGEST_MISMATCH),
+            (
+                hw_id,
+                FixedBytes::repeat_byte(9),
+                2,
+                digest(2),
+                STATUS_FIRMWARE_NOT_APPROVED,
+            ),
+            (
+                FixedBytes::repeat_byte(9),
+                fw_hash,
+                2,
+                digest(2),
+                STATUS_UNAUTHORIZED_HARDWARE,
+            ),
+        ] {
+            assert_eq!(
+                anchor.submit_receipt(hw_id, fw_hash, exec_hash, counter, claimed_digest),
+                status
+            );
+        }
+
+        // Only the accepted receipt moved the counter; the node's
+        // rejections were tallied instead of rolled back.
+        assert_eq!(anchor.get_counter(hw_id), 1);
+        assert_eq!(anchor.accepted_count.get(hw_id).to::<u64>(), 1);
+        assert_eq!(anchor.rejected_count.get(hw_id).to::<u64>(), 3);
+        assert_eq!(
+            anchor.submit_receipt(hw_id, fw_hash, exec_hash, 2, digest(2)),
+            STATUS_OK
+        );
+        assert_eq!(anchor.get_counter(hw_id), 2);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn reported_receipts_log_acceptance_or_the_rejection_status() {
+        let (mut anchor, hw_id, fw_hash, exec_hash) = test_vm::fixture();
+        let digest = reconstruct_digest(test_vm::CHAIN_ID, hw_id, fw_hash, exec_hash, 1);
+        test_vm::take_logs();
+        let seq = anchor.event_seq.get().to::<u64>();
+        let prev_root = anchor.receipt_root.get();
+
+        assert!(anchor.report_receipt(hw_id, fw_hash, exec_hash, 1, digest));
+        assert!(!anchor.report_receipt(hw_id, fw_hash, exec_hash, 1, digest));
+        assert!(!anchor.report_receipt(hw_id, fw_hash, exec_hash, 2, digest));
+        assert_eq!(
+            test_vm::take_logs(),
+            vec![
+                emitted(ReceiptAnchored {
+                    seq: seq + 1,
+                    digest,
+                    prev_root,
+                    new_root: keccak256([prev_root, digest].concat()),
+                }),
+                emitted(ReceiptAccepted {
+                    seq: seq + 2,
+                    hw_id,
+                    fw_hash,
+                    counter: 1,
+                    digest,
+                    firmware_receipts: 1,
+                }),
+                emitted(ReceiptRejected {
+                    seq: seq + 3,
+                    hw_id,
+                    reason: STATUS_REPLAY_DETECTED,
+                }),
+                emitted(ReceiptRejected {
+                    seq: seq + 4,
+                    hw_id,
+                    reason: STATUS_DIGEST_MISMATCH,
+                }),
+            ]
+        );
+        assert_eq!(anchor.get_counter(hw_id), 1);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn verify_receipt_returning_returns_the_reconstructed_digest() {
+        let (mut anchor, hw_id, fw_hash, exec_hash) = test_vm::fixture();
+        let digest = reconstruct_digest(test_vm::CHAIN_ID, hw_id, fw_hash, exec_hash, 1);
+
+        let returned = anchor.verify_receipt_returning(hw_id, fw_hash, exec_hash, 1, digest);
+        assert_eq!(returned.ok(), Some(digest));
+        assert_eq!(anchor.get_counter(hw_id), 1);
+        assert!(matches!(
+            anchor.verify_receipt_returning(hw_id, fw_hash, exec_hash, 1, digest),
+            Err(HardwareAnchorError::ReplayDetected(_))
+        ));
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn reports_its_version_and_build_commit() {
+        use stylus_sdk::alloy_primitives::hex;
+
+        let anchor: StylusHardwareAnchor = test_vm::contract();
+        assert_eq!(
+            anchor.contract_version(),
+            format!("sha-v{}", env!("CARGO_PKG_VERSION"))
+        );
+        assert_eq!(anchor.build_commit(), FixedBytes(BUILD_COMMIT));
+
+        let commit = hex!("0123456789abcdef0123456789abcdef01234567");
+        assert_eq!(
+            parse_commit(b"0123456789abcdef0123456789abcdef01234567"),
+            commit
+        );
+        assert_eq!(
+            parse_commit(b"0123456789ABCDEF0123456789ABCDEF01234567"),
+            commit
+        );
+        // Short, long or non-hex commits decode to the zero commit.
+        for hex in [
+            &b"0123456789abcdef"[..],
+            b"0123456789abcdef0123456789abcdef012345678",
+            b"0123456789abcdef0123456789abcdef0123456g",
+        ] {
+            assert_eq!(parse_commit(hex), [0; 20]);
+        }
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn sha256_digests_go_through_the_precompile() {
+        use sha2::{Digest, Sha256};
+
+        let (mut anchor, hw_id, fw_hash, exec_hash) = test_vm::fixture();
+        assert!(anchor.set_digest_algo(DIGEST_SHA256).is_ok());
+        let material =
+            receipt_codec::encode_material(test_vm::CHAIN_ID, hw_id, fw_hash, exec_hash, 1);
+        let digest = FixedBytes::<32>::from_slice(&Sha256::digest(material));
+
+        // The keccak256 digest no longer matches.
+        let keccak = reconstruct_digest(test_vm::CHAIN_ID, hw_id, fw_hash, exec_hash, 1);
+        assert!(matches!(
+            anchor.verify_receipt(hw_id, fw_hash, exec_hash, 1, keccak),
+            Err(HardwareAnchorError::DigestMismatch(_))
+        ));
+        assert_eq!(
+            anchor
+                .verify_receipt_returning(hw_id, fw_hash, exec_hash, 1, digest)
+                .ok(),
+            Some(digest)
+        );
+
+        // Without the precompile no sha256 receipt can be checked.
+        test_vm::remove_sha256();
+        assert!(matches!(
+            anchor.verify_receipt(hw_id, fw_hash, exec_hash, 2, digest),
+            Err(HardwareAnchorError::HashUnavailable(_))
+        ));
+        assert_eq!(anchor.get_counter(hw_id), 1);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn firmware_receipt_count_tallies_accepted_receipts_across_nodes() {
+        let (mut anchor, hw_id, fw_hash, _) = test_vm::fixture();
+        let other = FixedBytes::<32>::repeat_byte(9);
+        assert!(anchor.authorize_node(other).is_ok());
+
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 1).is_ok());
+        assert!(test_vm::verify(&mut anchor, other, fw_hash, 1).is_ok());
+        assert!(test_vm::verify(&mut anchor, hw_id, fw_hash, 1).is_err());
+        assert_eq!(anchor.firmware_receipt_count.get(fw_hash).to::<u64>(), 2);
+    }
+
+    #[cfg(not(feature = "export-abi"))]
+    #[test]
+    fn layout_locates_every_field_read_slot_serves() {
+        use anchor_interface::layout::{self, Field};
+
+        let mut anchor: StylusHardwareAnchor = test_vm::contract();
+        assert!(anchor
+            .initialize_with(test_vm::SENDER, Bytes(b"TAG".to_vec()))
+            .is_ok());
+        let key = FixedBytes::<32>::repeat_byte(0x5a);
+        let scalar = |anchor: &StylusHardwareAnchor, field: Field| {
+            field.decode(anchor.read_slot(field.key()))
+        };
+        let mapped = |anchor: &StylusHardwareAnchor, field: Field| {
+            field.decode(anchor.read_slot(field.key_at(key)))
+        };
+
+        // Neighbours sharing a word get distinct values, so an offset that
+        // is off by a field reads the wrong one.
+        anchor.receipt_root.set(FixedBytes::repeat_byte(0xee));
+        anchor.replay_window.set(U64::from(7));
+        anchor.max_authorized_nodes.set(U64::from(11));
+        anchor.active_node_count.set(U64::from(12));
+        anchor.min_receipt_interval.set(U64::from(15));
+        anchor.counter_bits.set(U8::from(48));
+        anchor.firmware_frozen.set(true);
+        anchor.exec_allowlist_enabled.set(false);
+        anchor.digest_algo.set(U8::from(DIGEST_SHA256));
+        anchor.approved_firmware_count.set(U64::from(24));
+        anchor.max_firmware.set(U64::from(25));
+        anchor.event_seq.set(U64::from(27));
+        let scalars = [
+            (layout::RECEIPT_ROOT, U256::from_be_bytes([0xee; 32])),
+            (layout::REPLAY_WINDOW, U256::from(7)),
+            (layout::MAX_AUTHORIZED_NODES, U256::from(11)),
+            (layout::ACTIVE_NODE_COUNT, U256::from(12)),
+            (layout::DOMAIN_TAG_LEN, U256::from(3)),
+            (layout::MIN_RECEIPT_INTERVAL, U256::from(15)),
+            (layout::COUNTER_BITS, U256::from(48)),
+            (layout::FIRMWARE_FROZEN, U256::from(1)),
+            (layout::EXEC_ALLOWLIST_ENABLED, U256::ZERO),
+            (layout::DIGEST_ALGO, U256::from(DIGEST_SHA256)),
+            (layout::APPROVED_FIRMWARE_COUNT, U256::from(24)),
+            (layout::MAX_FIRMWARE, U256::from(25)),
+            (layout::EVENT_SEQ, U256::from(27)),
+        ];
+        for (field, expected) in scalars {
+            assert_eq!(scalar(&anchor, field), expected, "{field:?}");
+        }
+        assert_eq!(anchor.read_slot(layout::DOMAIN_TAG.key())[..3], b"TAG"[..]);
+
+        anchor.counters.insert(key, U64::from(3));
+        anchor.firmware_version.insert(key, U32::from(5));
+        anchor.min_firmware_version.insert(key, U32::from(6));
+        anchor.seen_counters.insert(key, U256::from(8) << 200);
+        anchor.accepted_count.insert(key, U64::from(9));
+        anchor.rejected_count.insert(key, U64::from(10));
+        anchor.last_verified_at.insert(key, U64::from(14));
+        anchor.firmware_expiry.insert(key, U64::from(16));
+        anchor.approved_exec.insert(key, true);
+        anchor.firmware_receipt_count.insert(key, U64::from(19));
+        anchor.node_expiry.insert(key, U64::from(25));
+        anchor.counter_floor.insert(key, U64::from(26));
+        anchor.allow_reanchor.insert(key, true);
+        anchor.blocked_nodes.insert(key, true);
+        anchor.signature_required.insert(key, true);
+        let mappings = [
+            (layout::COUNTERS, U256::from(3)),
+            (layout::FIRMWARE_VERSION, U256::from(5)),
+            (layout::MIN_FIRMWARE_VERSION, U256::from(6)),
+            (layout::SEEN_COUNTERS, U256::from(8) << 200),
+            (layout::ACCEPTED_COUNT, U256::from(9)),
+            (layout::REJECTED_COUNT, U256::from(10)),
+            (layout::LAST_VERIFIED_AT, U256::from(14)),
+            (layout::FIRMWARE_EXPIRY, U256::from(16)),
+            (layout::APPROVED_EXEC, U256::from(1)),
+            (layout::FIRMWARE_RECEIPT_COUNT, U256::from(19)),
+            (layout::NODE_EXPIRY, U256::from(25)),
+            (layout::COUNTER_FLOOR, U256::from(26)),
+            (layout::ALLOW_REANCHOR, U256::from(1)),
+            (layout::BLOCKED_NODES, U256::from(1)),
+            (layout::SIGNATURE_REQUIRED, U256::from(1)),
+        ];
+        for (field, expected) in mappings {
+            assert_eq!(mapped(&anchor, field), expected, "{field:?}");
+        }
+
+        let verifier = Address::repeat_byte(0xcc);
+        assert!(anchor.set_verifier(verifier, true).is_ok());
+        let verifier_key = layout::VERIFIERS.key_at(verifier.into_word());
+        assert_eq!(
+            layout::VERIFIERS.decode(anchor.read_slot(verifier_key)),
+            U256::from(1)
+        );
+
+        let nodes = [0x61, 0x62].map(FixedBytes::<32>::repeat_byte);
+        for node in nodes {
+            anchor.firmware_nodes.setter(key).push(node);
+        }
+        let nodes_key = layout::FIRMWARE_NODES.key_at(key);
+        assert_eq!(
+            layout::FIRMWARE_NODES.decode(anchor.read_slot(nodes_key)),
+            U256::from(2)
+        );
+        for (i, node) in (0..).zip(nodes) {
+            assert_eq!(anchor.read_slot(layout::element_key(nodes_key, i)), node);
+        }
     }
 }
@@ -0,0 +1,235 @@
+//! Native stand-ins for the hostios StylusHardwareAnchor uses, so unit
+//! tests can drive the contract against in-memory storage.
+//!
+//! Each test thread has its own storage and log. Static calls to the
+//! ecrecover precompile are served with k256 and those to the sha256
+//! precompile with sha2, until `remove_sha256` takes it off the thread's
+//! chain; every other external call fails with no return data. The SDK
+//! caches msg::sender, block::timestamp and the other environment reads
+//! for the whole process, so every call comes from SENDER at a fixed block.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use stylus_sdk::alloy_primitives::{address, keccak256, Address, FixedBytes, B256, U256};
+use stylus_sdk::storage::StorageType;
+
+use crate::{reconstruct_digest, HardwareAnchorError, StylusHardwareAnchor};
+
+pub const SENDER: Address = address!("00000000000000000000000000000000000a11ce");
+pub const CONTRACT: Address = address!("000000000000000000000000000000000000c0de");
+pub const CHAIN_ID: u64 = 42161;
+pub const BLOCK_NUMBER: u64 = 1_000;
+pub const TIMESTAMP: u64 = 1_700_000_000;
+
+const ECRECOVER: Address = address!("0000000000000000000000000000000000000001");
+const SHA256: Address = address!("0000000000000000000000000000000000000002");
+
+/// An emitted log: topics, then ABI-encoded data.
+pub type Log = (Vec<B256>, Vec<u8>);
+
+thread_local! {
+    static STORAGE: RefCell<HashMap<B256, B256>> = RefCell::new(HashMap::new());
+    static LOGS: RefCell<Vec<Log>> = const { RefCell::new(Vec::new()) };
+    static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static SHA256_DEPLOYED: RefCell<bool> = const { RefCell::new(true) };
+}
+
+/// A contract rooted at slot zero of this thread's storage.
+pub fn contract<T: StorageType>() -> T {
+    unsafe { T::new(U256::ZERO, 0) }
+}
+
+/// Logs emitted on this thread since the last call.
+pub fn take_logs() -> Vec<Log> {
+    LOGS.with(|logs| logs.take())
+}
+
+/// Remove the sha256 precompile from this thread's chain: calls to its
+/// address then succeed with empty output, like any call to an empty
+/// account.
+pub fn remove_sha256() {
+    SHA256_DEPLOYED.with(|deployed| *deployed.borrow_mut() = false);
+}
+
+/// Execution hash of every receipt `verify` submits.
+pub const EXEC_HASH: FixedBytes<32> = FixedBytes::repeat_byte(3);
+
+/// An initialized contract with `hw_id` authorized and `fw_hash` approved,
+/// returned with them and EXEC_HASH.
+pub fn fixture() -> (
+    StylusHardwareAnchor,
+    FixedBytes<32>,
+    FixedBytes<32>,
+    FixedBytes<32>,
+) {
+    let mut anchor: StylusHardwareAnchor = contract();
+    assert!(anchor.initialize().is_ok());
+    let [hw_id, fw_hash] = [1u8, 2].map(FixedBytes::<32>::repeat_byte);
+    assert!(anchor.authorize_node(hw_id).is_ok());
+    assert!(anchor.approve_firmware(fw_hash).is_ok());
+    (anchor, hw_id, fw_hash, EXEC_HASH)
+}
+
+/// verify_receipt for EXEC_HASH with the digest the device signs on
+/// CHAIN_ID.
+pub fn verify(
+    anchor: &mut StylusHardwareAnchor,
+    hw_id: FixedBytes<32>,
+    fw_hash: FixedBytes<32>,
+    counter: u64,
+) -> Result<(), HardwareAnchorError> {
+    let digest = reconstruct_digest(CHAIN_ID, hw_id, fw_hash, EXEC_HASH, counter);
+    anchor.verify_receipt(hw_id, fw_hash, EXEC_HASH, counter, digest)
+}
+
+unsafe fn read<const N: usize>(ptr: *const u8) -> [u8; N] {
+    let mut out = [0u8; N];
+    std::ptr::copy_nonoverlapping(ptr, out.as_mut_ptr(), N);
+    out
+}
+
+unsafe fn write(ptr: *mut u8, bytes: &[u8]) {
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+}
+
+#[no_mangle]
+unsafe extern "C" fn storage_load_bytes32(key: *const u8, dest: *mut u8) {
+    let key = B256::from(read::<32>(key));
+    let value = STORAGE.with(|s| s.borrow().get(&key).copied().unwrap_or_default());
+    write(dest, value.as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn storage_cache_bytes32(key: *const u8, value: *const u8) {
+    let (key, value) = (B256::from(read::<32>(key)), B256::from(read::<32>(value)));
+    STORAGE.with(|s| s.borrow_mut().insert(key, value));
+}
+
+#[no_mangle]
+extern "C" fn storage_flush_cache(_clear: bool) {}
+
+#[no_mangle]
+unsafe extern "C" fn emit_log(data: *const u8, len: usize, topics: usize) {
+    let bytes = std::slice::from_raw_parts(data, len);
+    let (topic_bytes, data) = bytes.split_at(topics * 32);
+    let topics = topic_bytes.chunks(32).map(B256::from_slice).collect();
+    LOGS.with(|logs| logs.borrow_mut().push((topics, data.to_vec())));
+}
+
+#[no_mangle]
+unsafe extern "C" fn msg_sender(sender: *mut u8) {
+    write(sender, SENDER.as_slice());
+}
+
+#[no_mangle]
+unsafe extern "C" fn contract_address(address: *mut u8) {
+    write(address, CONTRACT.as_slice());
+}
+
+#[no_mangle]
+extern "C" fn chainid() -> u64 {
+    CHAIN_ID
+}
+
+#[no_mangle]
+extern "C" fn block_number() -> u64 {
+    BLOCK_NUMBER
+}
+
+#[no_mangle]
+extern "C" fn block_timestamp() -> u64 {
+    TIMESTAMP
+}
+
+#[no_mangle]
+unsafe extern "C" fn call_contract(
+    _contract: *const u8,
+    _calldata: *const u8,
+    _calldata_len: usize,
+    _value: *const u8,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    *return_data_len = 0;
+    1
+}
+
+#[no_mangle]
+unsafe extern "C" fn delegate_call_contract(
+    _contract: *const u8,
+    _calldata: *const u8,
+    _calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    *return_data_len = 0;
+    1
+}
+
+/// The ecrecover precompile: hash || v || r || s in, the signer
+/// left-padded to 32 bytes out, nothing for an invalid signature.
+fn ecrecover(input: &[u8]) -> Vec<u8> {
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    let mut padded = [0u8; 128];
+    let len = input.len().min(128);
+    padded[..len].copy_from_slice(&input[..len]);
+    let v = padded[63];
+    if padded[32..63].iter().any(|b| *b != 0) || !(v == 27 || v == 28) {
+        return Vec::new();
+    }
+    let Ok(signature) = Signature::from_slice(&padded[64..128]) else {
+        return Vec::new();
+    };
+    let recovery_id = RecoveryId::from_byte(v - 27).unwrap();
+    let Ok(key) = VerifyingKey::recover_from_prehash(&padded[..32], &signature, recovery_id) else {
+        return Vec::new();
+    };
+    let point = key.to_encoded_point(false);
+    let mut output = vec![0u8; 32];
+    output[12..].copy_from_slice(&keccak256(&point.as_bytes()[1..])[12..]);
+    output
+}
+
+#[no_mangle]
+unsafe extern "C" fn static_call_contract(
+    contract: *const u8,
+    calldata: *const u8,
+    calldata_len: usize,
+    _gas: u64,
+    return_data_len: *mut usize,
+) -> u8 {
+    use sha2::{Digest, Sha256};
+
+    let input = std::slice::from_raw_parts(calldata, calldata_len);
+    let output = match Address::from(read::<20>(contract)) {
+        ECRECOVER => ecrecover(input),
+        SHA256 if SHA256_DEPLOYED.with(|deployed| *deployed.borrow()) => {
+            Sha256::digest(input).to_vec()
+        }
+        SHA256 => Vec::new(),
+        _ => {
+            *return_data_len = 0;
+            return 1;
+        }
+    };
+    *return_data_len = output.len();
+    RETURN_DATA.with(|data| *data.borrow_mut() = output);
+    0
+}
+
+#[no_mangle]
+unsafe extern "C" fn read_return_data(dest: *mut u8, offset: usize, size: usize) -> usize {
+    RETURN_DATA.with(|data| {
+        let data = data.borrow();
+        let start = offset.min(data.len());
+        let end = offset.saturating_add(size).min(data.len());
+        write(dest, &data[start..end]);
+        end - start
+    })
+}
+
+#[no_mangle]
+extern "C" fn return_data_size() -> usize {
+    RETURN_DATA.with(|data| data.borrow().len())
+}
@@ -0,0 +1,173 @@
+//! Golden check of the exported Solidity interface.
+//!
+//! Client bindings are generated from this interface, so any drift (a
+//! renamed function, reordered parameters, a changed return type) fails
+//! here instead of at runtime. After an intentional interface change,
+//! regenerate the golden file with:
+//!
+//!     UPDATE_ABI=1 cargo test --features export-abi --test abi
+//!
+//! A function that leaves the anchor also has to be listed against the
+//! frozen v1 interface below, with the companion that took it over.
+#![cfg(feature = "export-abi")]
+
+use core::fmt;
+use stylus_hardware_anchor::StylusHardwareAnchor;
+use stylus_sdk::abi::export::GenerateAbi;
+
+const GOLDEN: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/abi/IStylusHardwareAnchor.sol");
+
+// The v1 interface (contract_version sha-v0.1.0), kept as deployed
+// clients were generated from it. Never regenerated.
+const GOLDEN_V1: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/abi/IStylusHardwareAnchor.v1.sol"
+);
+
+// v1 functions the v2 anchor no longer serves, each with the companion
+// contract that serves it now under the same signature.
+const MOVED_IN_V2: &[(&str, &str)] = &[
+    ("acceptChainId", "AnchorVariants"),
+    ("actionHash", "AnchorGovernor"),
+    ("approve", "AnchorGovernor"),
+    ("approveDomain", "AnchorVariants"),
+    ("areFirmwaresApproved", "AnchorLens"),
+    ("areNodesAuthorized", "AnchorLens"),
+    ("claimOwnershipAfterInactivity", "AnchorGovernor"),
+    ("computeReceiptDigestsBatch", "AnchorLens"),
+    ("finalizeFirmwareApproval", "AnchorGovernor"),
+    ("getAcceptedCount", "AnchorLens"),
+    ("getAcceptedLayouts", "AnchorVariants"),
+    ("getActiveNodeCount", "AnchorLens"),
+    ("getApprovedFirmwareCount", "AnchorLens"),
+    ("getCounterBits", "AnchorLens"),
+    ("getCounterFloor", "AnchorLens"),
+    ("getCounters", "AnchorLens"),
+    ("getDigestAlgo", "AnchorLens"),
+    ("getDomainCounter", "AnchorLens"),
+    ("getDomainTag", "AnchorLens"),
+    ("getEventSeq", "AnchorLens"),
+    ("getFirmwareExpiry", "AnchorLens"),
+    ("getFirmwareNodeAt", "AnchorLens"),
+    ("getFirmwareNodeCount", "AnchorLens"),
+    ("getFirmwareReceiptCount", "AnchorLens"),
+    ("getFirmwareVersion", "AnchorLens"),
+    ("getLastOwnerActivity", "AnchorGovernor"),
+    ("getLastVerifiedAt", "AnchorLens"),
+    ("getMaxAuthorizedNodes", "AnchorLens"),
+    ("getMaxBlockStaleness", "AnchorVariants"),
+    ("getMaxFirmware", "AnchorLens"),
+    ("getMinFirmwareVersion", "AnchorLens"),
+    ("getMinReceiptInterval", "AnchorLens"),
+    ("getNodeExpiry", "AnchorLens"),
+    ("getNodeMeta", "AnchorGateway"),
+    ("getNodeSigner", "AnchorGateway"),
+    ("getOwners", "AnchorGovernor"),
+    ("getP256Key", "AnchorGateway"),
+    ("getPendingFirmwareRequester", "AnchorGovernor"),
+    ("getPermitDomainSeparator", "AnchorGovernor"),
+    ("getPermitNonce", "AnchorGovernor"),
+    ("getProposal", "AnchorGovernor"),
+    ("getRecoveryDelay", "AnchorGovernor"),
+    ("getRecoveryOwner", "AnchorGovernor"),
+    ("getRejectedCount", "AnchorLens"),
+    ("getReplayWindow", "AnchorLens"),
+    ("getThreshold", "AnchorGovernor"),
+    ("importFirmwareManifest", "AnchorGovernor"),
+    ("isChainIdAccepted", "AnchorVariants"),
+    ("isDomainApproved", "AnchorVariants"),
+    ("isExecAllowlistEnabled", "AnchorLens"),
+    ("isExecApproved", "AnchorLens"),
+    ("isFirmwareFrozen", "AnchorLens"),
+    ("isNodeBlocked", "AnchorLens"),
+    ("isReanchorAllowed", "AnchorLens"),
+    ("materialLength", "AnchorLens"),
+    ("permitAuthorizeNode", "AnchorGovernor"),
+    ("previewHwId", "AnchorLens"),
+    ("previewMaterial", "AnchorLens"),
+    ("propose", "AnchorGovernor"),
+    ("registerP256Key", "AnchorGateway"),
+    ("requestFirmwareApproval", "AnchorGovernor"),
+    ("revokeChainId", "AnchorVariants"),
+    ("revokeDomain", "AnchorVariants"),
+    ("selftest", "AnchorGovernor"),
+    ("setAcceptedLayouts", "AnchorVariants"),
+    ("setMaxBlockStaleness", "AnchorVariants"),
+    ("setNodeMeta", "AnchorGateway"),
+    ("setNodeSigner", "AnchorGateway"),
+    ("setOwners", "AnchorGovernor"),
+    ("setRecoveryDelay", "AnchorGovernor"),
+    ("setRecoveryOwner", "AnchorGovernor"),
+    ("simulateVerifyReceipt", "AnchorLens"),
+    ("sweepFirmware", "AnchorGateway"),
+    ("verifyReceiptDelegated", "AnchorGateway"),
+    ("verifyReceiptDomain", "AnchorVariants"),
+    ("verifyReceiptForChain", "AnchorVariants"),
+    ("verifyReceiptFresh", "AnchorVariants"),
+    ("verifyReceiptP256", "AnchorGateway"),
+    ("verifyReceiptPacked", "AnchorGateway"),
+    ("verifyReceiptV", "AnchorVariants"),
+    ("verifyReceipts", "AnchorGateway"),
+    ("verifyReceiptsBatch", "AnchorLens"),
+    ("verifyReceiptsBatchBitset", "AnchorLens"),
+    ("verifyReceiptsBatchBitsetBytes", "AnchorLens"),
+    ("verifyReceiptsBatchBytes", "AnchorLens"),
+];
+
+struct Abi;
+
+impl fmt::Display for Abi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        StylusHardwareAnchor::fmt_abi(f)
+    }
+}
+
+#[test]
+fn abi_matches_golden() {
+    let abi = Abi.to_string();
+    if std::env::var_os("UPDATE_ABI").is_some() {
+        std::fs::write(GOLDEN, &abi).expect("write golden ABI");
+        return;
+    }
+
+    let golden = std::fs::read_to_string(GOLDEN).expect("read golden ABI");
+    assert!(
+        abi == golden,
+        "exported ABI differs from {GOLDEN}; rerun with UPDATE_ABI=1 if the change is intentional\n{abi}"
+    );
+}
+
+#[test]
+fn v2_keeps_or_moves_every_v1_function() {
+    let abi = Abi.to_string();
+    let served: Vec<&str> = abi.lines().map(str::trim).collect();
+    let v1 = std::fs::read_to_string(GOLDEN_V1).expect("read v1 golden ABI");
+    let v1_functions: Vec<&str> = v1
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("function "))
+        .collect();
+    let name = |line: &str| {
+        line["function ".len()..]
+            .split('(')
+            .next()
+            .unwrap()
+            .to_owned()
+    };
+
+    for line in &v1_functions {
+        let name = name(line);
+        let moved = MOVED_IN_V2.iter().any(|(moved, _)| *moved == name);
+        assert!(
+            served.contains(line) != moved,
+            "v1 `{name}` must be served unchanged or listed as moved, not both"
+        );
+    }
+    let v1_names: Vec<String> = v1_functions.iter().map(|line| name(line)).collect();
+    for (moved, _) in MOVED_IN_V2 {
+        assert!(
+            v1_names.iter().any(|name| name == moved),
+            "`{moved}` is not a v1 function"
+        );
+    }
+}